@@ -0,0 +1,127 @@
+//! Configuring an [`AudioRecorder`] before its ring buffer is allocated
+
+use std::{
+    collections::VecDeque,
+    sync::{atomic::AtomicUsize, Arc, Mutex},
+    time::Duration,
+};
+
+use echoes_config::VadConfig;
+use rtrb::RingBuffer;
+
+use crate::{AudioRecorder, WavFormat, WAVEFORM_SAMPLES};
+
+/// Longest recording by default: 5 minutes
+const DEFAULT_MAX_DURATION_SECONDS: u64 = 300;
+
+/// Sample rate the ring buffer is sized for when none is requested
+const DEFAULT_SAMPLE_RATE: u32 = 16000;
+
+/// Further attempts to open a busy input device by default. None, since the
+/// retry delay blocks the calling thread, which is usually the UI's.
+const DEFAULT_DEVICE_BUSY_RETRIES: u32 = 0;
+
+/// Builds an [`AudioRecorder`], allocating its ring buffer once from the
+/// final settings. Created with [`AudioRecorder::builder`].
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct AudioRecorderBuilder {
+    vad: bool,
+    max_duration: Duration,
+    sample_rate: Option<u32>,
+    output_format: WavFormat,
+    preferred_device: Option<String>,
+    device_busy_retries: u32,
+}
+
+impl Default for AudioRecorderBuilder {
+    fn default() -> Self {
+        Self {
+            vad: true,
+            max_duration: Duration::from_secs(DEFAULT_MAX_DURATION_SECONDS),
+            sample_rate: None,
+            output_format: WavFormat::default(),
+            preferred_device: None,
+            device_busy_retries: DEFAULT_DEVICE_BUSY_RETRIES,
+        }
+    }
+}
+
+impl AudioRecorderBuilder {
+    /// Detect speech segments when a recording stops (default: enabled)
+    pub const fn vad(mut self, vad: bool) -> Self {
+        self.vad = vad;
+        self
+    }
+
+    /// Longest recording the ring buffer holds without being drained, rounded
+    /// down to whole seconds (default: 5 minutes)
+    pub const fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = max_duration;
+        self
+    }
+
+    /// Capture at `sample_rate` when the input device supports it instead of
+    /// at the device default. The ring buffer is sized for this rate.
+    pub const fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    /// Sample format of the encoded WAV data (default: 16-bit PCM)
+    pub const fn output_format(mut self, output_format: WavFormat) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
+    /// Record from the input device with this name, falling back to the
+    /// default device when it isn't connected. `None` always uses the default.
+    pub fn preferred_device(mut self, preferred_device: Option<String>) -> Self {
+        self.preferred_device = preferred_device;
+        self
+    }
+
+    /// Retry starting a recording this many times, after a short delay each,
+    /// while the input device is held by another application. The default, 0,
+    /// fails right away with [`crate::AudioError::DeviceBusy`]; retries block
+    /// [`AudioRecorder::start_recording`], so only enable them when it is
+    /// called off the UI thread.
+    pub const fn device_busy_retries(mut self, retries: u32) -> Self {
+        self.device_busy_retries = retries;
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> AudioRecorder {
+        let sample_rate = self.sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE);
+        let max_duration_seconds = u32::try_from(self.max_duration.as_secs()).unwrap_or(u32::MAX);
+        let ring_buffer_capacity = (max_duration_seconds as usize).saturating_mul(sample_rate as usize);
+        let (producer, consumer) = RingBuffer::new(ring_buffer_capacity);
+
+        AudioRecorder {
+            ring_buffer_producer: Some(producer),
+            ring_buffer_consumer: Some(consumer),
+            stream: None,
+            use_vad: self.vad,
+            trim_silence: false,
+            gain_db: 0.0,
+            normalize_peak_dbfs: None,
+            recording_peak: 0.0,
+            segment_tail_pad_ms: 0,
+            sample_rate,
+            preferred_sample_rate: self.sample_rate,
+            preferred_device: self.preferred_device,
+            output_format: self.output_format,
+            max_duration_seconds,
+            ring_buffer_capacity,
+            recent_samples: Arc::new(Mutex::new(VecDeque::with_capacity(WAVEFORM_SAMPLES))),
+            vad_config: VadConfig::default(),
+            streaming: false,
+            streaming_vad: None,
+            captured: Vec::new(),
+            last_recording_samples: 0,
+            dropped_samples: Arc::new(AtomicUsize::new(0)),
+            device_busy_retries: self.device_busy_retries,
+        }
+    }
+}
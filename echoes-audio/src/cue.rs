@@ -0,0 +1,141 @@
+//! Short synthesized beeps played on the default output device as audible
+//! feedback, so no sound files need to be bundled
+
+use std::{f32::consts::TAU, time::Duration};
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    SampleFormat,
+};
+use tracing::{debug, error, warn};
+
+use crate::{AudioError, Result};
+
+/// Length of each beep
+const CUE_DURATION: Duration = Duration::from_millis(120);
+
+/// Peak amplitude, well below full scale so the cue isn't jarring
+const CUE_VOLUME: f32 = 0.2;
+
+/// Share of the beep spent fading in and out, which avoids audible clicks
+const FADE_FRACTION: f32 = 0.2;
+
+/// Extra time the stream is kept open for the device to play out its buffer
+const DRAIN_MARGIN: Duration = Duration::from_millis(80);
+
+/// An audible cue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    /// Recording started: a higher tone
+    Start,
+    /// Recording stopped: a lower tone
+    Stop,
+}
+
+impl Cue {
+    const fn frequency(self) -> f32 {
+        match self {
+            Self::Start => 880.0,
+            Self::Stop => 587.33,
+        }
+    }
+
+    /// Mono samples of the beep at `sample_rate`
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn samples(self, sample_rate: u32) -> Vec<f32> {
+        let len = (CUE_DURATION.as_secs_f32() * sample_rate as f32) as usize;
+        let fade_len = (len as f32 * FADE_FRACTION).max(1.0);
+        let step = TAU * self.frequency() / sample_rate as f32;
+
+        (0..len)
+            .map(|i| {
+                let envelope = (i.min(len - 1 - i) as f32 / fade_len).min(1.0);
+                (i as f32 * step).sin() * CUE_VOLUME * envelope
+            })
+            .collect()
+    }
+}
+
+/// Plays `cue` on the default output device in the background. Failures,
+/// e.g. without an output device, are only logged.
+pub fn play_cue(cue: Cue) {
+    let spawned = std::thread::Builder::new()
+        .name("echoes-sound-cue".into())
+        .spawn(move || {
+            if let Err(e) = play_blocking(cue) {
+                warn!("Failed to play {:?} cue: {}", cue, e);
+            }
+        });
+    if let Err(e) = spawned {
+        warn!("Failed to spawn the sound cue thread: {}", e);
+    }
+}
+
+/// Plays `cue` and returns once it has finished
+fn play_blocking(cue: Cue) -> Result<()> {
+    let host = cpal::default_host();
+    let device = host.default_output_device().ok_or(AudioError::NoOutputDevice)?;
+    let config = device
+        .default_output_config()
+        .map_err(|e| AudioError::StreamCreationFailed(e.to_string()))?;
+    debug!("Playing {:?} cue with output config {:?}", cue, config);
+
+    let samples = cue.samples(config.sample_rate().0);
+    let stream_config = config.config();
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => build_output_stream::<f32>(&device, &stream_config, samples)?,
+        SampleFormat::I16 => build_output_stream::<i16>(&device, &stream_config, samples)?,
+        SampleFormat::U16 => build_output_stream::<u16>(&device, &stream_config, samples)?,
+        sample_format => {
+            return Err(AudioError::UnsupportedFormat(format!("{sample_format:?}")));
+        }
+    };
+
+    stream
+        .play()
+        .map_err(|e| AudioError::StreamCreationFailed(e.to_string()))?;
+    std::thread::sleep(CUE_DURATION + DRAIN_MARGIN);
+    Ok(())
+}
+
+/// Output stream writing `samples` to every channel, then silence
+fn build_output_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig, samples: Vec<f32>) -> Result<cpal::Stream>
+where
+    T: cpal::SizedSample + cpal::FromSample<f32> + Send + 'static,
+{
+    let channels = usize::from(config.channels);
+    let mut samples = samples.into_iter();
+
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                for frame in data.chunks_mut(channels) {
+                    frame.fill(T::from_sample(samples.next().unwrap_or(0.0)));
+                }
+            },
+            |err| error!("An error occurred on the cue output stream: {}", err),
+            None,
+        )
+        .map_err(|e| AudioError::StreamCreationFailed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cue_fades_in_and_out() {
+        let samples = Cue::Start.samples(48_000);
+        assert_eq!(samples.len(), 5760);
+        assert!(samples.iter().all(|sample| sample.abs() <= CUE_VOLUME));
+        assert!(samples[0].abs() < 1e-3);
+        assert!(samples[samples.len() - 1].abs() < 1e-3);
+        assert!(samples.iter().any(|sample| sample.abs() > CUE_VOLUME * 0.9));
+    }
+}
@@ -7,6 +7,14 @@ pub enum AudioError {
     #[error("No input device available")]
     NoInputDevice,
 
+    #[error("No output device available")]
+    NoOutputDevice,
+
+    /// The input device is held by another application, e.g. in exclusive
+    /// mode on Windows, or was disconnected
+    #[error("Input device is in use by another application")]
+    DeviceBusy,
+
     #[error("Stream creation failed: {0}")]
     StreamCreationFailed(String),
 
@@ -22,6 +30,9 @@ pub enum AudioError {
     #[error("VAD processing failed: {0}")]
     VadProcessingFailed(String),
 
+    #[error("VAD chunk size {0} is not supported at {1}Hz")]
+    UnsupportedVadChunkSize(usize, u32),
+
     #[error("Other error: {0}")]
     Other(String),
 }
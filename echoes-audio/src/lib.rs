@@ -1,27 +1,184 @@
+mod builder;
+mod cue;
 pub mod error;
+pub mod resample;
+mod streaming;
 pub mod vad;
 
-use std::io::Cursor;
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    io::Cursor,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
+pub use builder::AudioRecorderBuilder;
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
     SampleFormat,
 };
+pub use cue::{play_cue, Cue};
+use echoes_config::VadConfig;
 pub use error::{AudioError, Result};
+pub use resample::resample;
 use rtrb::{Consumer, Producer, RingBuffer};
-use tracing::{debug, error};
-use vad::VadProcessor;
+use streaming::StreamingVad;
+use tracing::{debug, error, warn};
+use vad::{merge_segments, trim_silence, SpeechSegment, VadProcessor, VAD_SAMPLE_RATE};
+
+/// Number of most recent samples kept for live waveform display
+pub const WAVEFORM_SAMPLES: usize = 4096;
+
+/// Peak level in dBFS below which [`AudioRecorder::test_capture`] picked up
+/// no signal: quieter than the noise floor of any working microphone
+pub const NO_SIGNAL_DBFS: f32 = -60.0;
+
+/// Wait between attempts to open a busy input device
+const DEVICE_BUSY_RETRY_DELAY: Duration = Duration::from_millis(300);
+
+/// Sample format of encoded WAV data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WavFormat {
+    /// 16-bit integer PCM, understood by every STT provider
+    #[default]
+    Int16,
+    /// 32-bit float, keeping the full precision of the captured samples. Not
+    /// read by Local Whisper.
+    Float32,
+}
+
+/// A speech segment of a finished recording
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// WAV data at [`VAD_SAMPLE_RATE`]
+    pub wav: Vec<u8>,
+    /// Start of the segment from the beginning of the recording, before any
+    /// silence trimming
+    pub start_ms: u64,
+    /// End of the segment from the beginning of the recording
+    pub end_ms: u64,
+}
+
+/// Everything produced by [`AudioRecorder::stop_recording_detailed`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingResult {
+    /// WAV data of the entire recording
+    pub raw_wav: Vec<u8>,
+    /// Detected speech segments in recording order (empty if VAD is disabled)
+    pub segments: Vec<Segment>,
+    pub stats: RecordingStats,
+}
+
+/// Levels and speech coverage of a finished recording, measured on the
+/// captured samples before gain or trimming
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RecordingStats {
+    pub duration: Duration,
+    /// Largest absolute sample value (0.0-1.0)
+    pub peak: f32,
+    /// Root mean square of all samples
+    pub rms: f32,
+    /// Fraction of the recording covered by speech segments; `None` when VAD
+    /// is disabled
+    pub speech_ratio: Option<f32>,
+}
+
+impl RecordingStats {
+    /// Measures levels and duration; `speech_ratio` is filled in once VAD ran
+    #[allow(clippy::cast_precision_loss)]
+    fn measure(samples: &[f32], sample_rate: u32) -> Self {
+        let rms = if samples.is_empty() {
+            0.0
+        } else {
+            (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+        };
+
+        Self {
+            duration: samples_to_duration(samples.len(), sample_rate),
+            peak: peak(samples),
+            rms,
+            speech_ratio: None,
+        }
+    }
+
+    /// Loudest sample in dBFS; negative infinity for pure silence
+    #[must_use]
+    pub fn peak_dbfs(&self) -> f32 {
+        20.0 * self.peak.log10()
+    }
+}
+
+/// Fraction of `duration` covered by the speech segments
+fn speech_ratio(segments: &[SpeechSegment], duration: Duration) -> f32 {
+    if duration.is_zero() {
+        return 0.0;
+    }
+    let speech: usize = segments
+        .iter()
+        .map(|segment| segment.end_sample - segment.start_sample)
+        .sum();
+    (samples_to_duration(speech, VAD_SAMPLE_RATE).as_secs_f32() / duration.as_secs_f32()).min(1.0)
+}
+
+/// Destinations the audio callback writes captured samples to
+struct StreamSinks {
+    producer: Producer<f32>,
+    /// Rolling window for level/waveform display
+    recent: Arc<Mutex<VecDeque<f32>>>,
+    /// Count of samples that didn't fit the ring buffer
+    dropped: Arc<AtomicUsize>,
+}
 
 pub struct AudioRecorder {
     ring_buffer_producer: Option<Producer<f32>>,
     ring_buffer_consumer: Option<Consumer<f32>>,
     stream: Option<cpal::Stream>,
     use_vad: bool,
+    /// Trim leading/trailing silence from the raw recording
+    trim_silence: bool,
+    /// Gain in dB applied before encoding
+    gain_db: f32,
+    /// Peak level in dBFS to normalize the recording to
+    normalize_peak_dbfs: Option<f32>,
+    /// Loudest sample of the current or last recording, which normalization
+    /// scales to `normalize_peak_dbfs`; the loudest so far while streaming
+    recording_peak: f32,
+    /// Milliseconds of silence appended to each encoded speech segment
+    segment_tail_pad_ms: u32,
     sample_rate: u32,
+    /// Sample rate to capture at when the input device supports it
+    preferred_sample_rate: Option<u32>,
+    /// Name of the input device to record from instead of the default one
+    preferred_device: Option<String>,
+    /// Sample format of the encoded WAV data
+    output_format: WavFormat,
     /// Maximum recording duration in seconds (default: 300 seconds = 5 minutes)
     max_duration_seconds: u32,
     /// Ring buffer capacity in samples
     ring_buffer_capacity: usize,
+    /// Rolling window of the latest samples for level/waveform display
+    recent_samples: Arc<Mutex<VecDeque<f32>>>,
+    /// Tuning for voice activity detection
+    vad_config: VadConfig,
+    /// Run VAD while recording so segments can be polled before stopping
+    streaming: bool,
+    /// Online VAD state of the current streaming recording
+    streaming_vad: Option<StreamingVad>,
+    /// Samples already drained from the ring buffer by
+    /// [`AudioRecorder::poll_segments`]
+    captured: Vec<f32>,
+    /// Sample count of the last finished recording
+    last_recording_samples: usize,
+    /// Samples the audio callback had to discard because the ring buffer was
+    /// full, since the recording started
+    dropped_samples: Arc<AtomicUsize>,
+    /// Further attempts [`AudioRecorder::start_recording`] makes while the
+    /// input device is busy
+    device_busy_retries: u32,
 }
 
 impl Default for AudioRecorder {
@@ -31,37 +188,45 @@ impl Default for AudioRecorder {
 }
 
 impl AudioRecorder {
+    /// Create a recorder with VAD enabled and a 5 minute ring buffer
     #[must_use]
     pub fn new() -> Self {
-        let ring_buffer_capacity = 300 * 16000;
-        let (producer, consumer) = RingBuffer::new(ring_buffer_capacity);
-
-        Self {
-            ring_buffer_producer: Some(producer),
-            ring_buffer_consumer: Some(consumer),
-            stream: None,
-            use_vad: true,
-            sample_rate: 16000,
-            max_duration_seconds: 300,
-            ring_buffer_capacity,
-        }
+        Self::builder().build()
     }
 
     /// Create a new recorder with VAD disabled
     #[must_use]
     pub fn new_without_vad() -> Self {
-        let ring_buffer_capacity = 300 * 16000;
-        let (producer, consumer) = RingBuffer::new(ring_buffer_capacity);
+        Self::builder().vad(false).build()
+    }
 
-        Self {
-            ring_buffer_producer: Some(producer),
-            ring_buffer_consumer: Some(consumer),
-            stream: None,
-            use_vad: false,
-            sample_rate: 16000,
-            max_duration_seconds: 300,
-            ring_buffer_capacity,
-        }
+    /// Configure a recorder; its ring buffer is allocated once by
+    /// [`AudioRecorderBuilder::build`]
+    pub fn builder() -> AudioRecorderBuilder {
+        AudioRecorderBuilder::default()
+    }
+
+    /// Length of the last recording stopped with
+    /// [`AudioRecorder::stop_recording`], before silence trimming
+    #[must_use]
+    pub fn last_recording_duration(&self) -> Duration {
+        samples_to_duration(self.last_recording_samples, self.sample_rate)
+    }
+
+    /// Whether an input stream is open, i.e. [`AudioRecorder::start_recording`]
+    /// succeeded and the recording hasn't been stopped since
+    #[must_use]
+    pub const fn is_recording(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Samples lost to a full ring buffer in the current or last recording.
+    /// Nonzero means the recording has a gap: the buffer holds
+    /// [`AudioRecorder::set_max_duration`] seconds unless it is drained while
+    /// recording, as streaming does.
+    #[must_use]
+    pub fn dropped_samples(&self) -> usize {
+        self.dropped_samples.load(Ordering::Relaxed)
     }
 
     /// Enable or disable VAD processing
@@ -69,16 +234,122 @@ impl AudioRecorder {
         self.use_vad = use_vad;
     }
 
+    /// Set the voice activity detection tuning used for speech segments
+    pub const fn set_vad_config(&mut self, vad_config: VadConfig) {
+        self.vad_config = vad_config;
+    }
+
+    /// Enable or disable trimming leading/trailing silence from the raw
+    /// recording returned by [`AudioRecorder::stop_recording`]. Samples
+    /// quieter than the `trim_threshold` of the VAD config count as silence,
+    /// as they do when trimming speech segments.
+    pub const fn set_trim_silence(&mut self, trim_silence: bool) {
+        self.trim_silence = trim_silence;
+    }
+
+    /// Set the gain in dB applied to samples before they are encoded; 0 dB
+    /// leaves them unchanged. Ignored while normalization is enabled.
+    pub const fn set_gain(&mut self, db: f32) {
+        self.gain_db = db;
+    }
+
+    /// Scale the recording so its loudest sample reaches `target_peak_dbfs`
+    /// (e.g. -3.0), or disable normalization with `None`.
+    ///
+    /// The scale is computed once from the whole recording and applied to the
+    /// raw WAV and every speech segment alike, so the level doesn't pump
+    /// between segments. Segments polled while streaming can only use the
+    /// loudest sample captured so far.
+    pub const fn set_normalize(&mut self, target_peak_dbfs: Option<f32>) {
+        self.normalize_peak_dbfs = target_peak_dbfs;
+    }
+
+    /// Append `ms` milliseconds of silence to each speech segment before it is
+    /// encoded, so a final word cut off abruptly isn't dropped by the STT
+    /// provider; 0 disables it. Segment timings are unaffected.
+    pub const fn set_segment_tail_pad(&mut self, ms: u32) {
+        self.segment_tail_pad_ms = ms;
+    }
+
+    /// Enable or disable streaming mode. While a streaming recording runs,
+    /// [`AudioRecorder::poll_segments`] returns speech segments as soon as VAD
+    /// finalizes them. Has no effect when VAD is disabled.
+    pub const fn set_streaming(&mut self, streaming: bool) {
+        self.streaming = streaming;
+    }
+
     /// Set maximum recording duration in seconds
     pub fn set_max_duration(&mut self, seconds: u32) {
         self.max_duration_seconds = seconds;
-        let ring_buffer_capacity = (seconds as usize) * (self.sample_rate as usize);
+        self.resize_ring_buffer();
+    }
+
+    /// Size the ring buffer to hold the maximum duration at the current
+    /// sample rate, reallocating it only if the capacity changed
+    fn resize_ring_buffer(&mut self) {
+        let ring_buffer_capacity = (self.max_duration_seconds as usize).saturating_mul(self.sample_rate as usize);
+        if ring_buffer_capacity == self.ring_buffer_capacity {
+            return;
+        }
         let (producer, consumer) = RingBuffer::new(ring_buffer_capacity);
         self.ring_buffer_producer = Some(producer);
         self.ring_buffer_consumer = Some(consumer);
         self.ring_buffer_capacity = ring_buffer_capacity;
     }
 
+    /// Returns a copy of the most recent samples (up to [`WAVEFORM_SAMPLES`]),
+    /// oldest first. Empty when not recording.
+    #[must_use]
+    pub fn recent_samples(&self) -> Vec<f32> {
+        self.recent_samples
+            .lock()
+            .map(|recent| recent.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    fn clear_recent_samples(&self) {
+        if let Ok(mut recent) = self.recent_samples.lock() {
+            recent.clear();
+        }
+    }
+
+    /// Returns the speech segments VAD finalized since the last call, encoded
+    /// and timed like those of [`AudioRecorder::stop_recording_detailed`].
+    /// Always empty unless a streaming recording is running.
+    ///
+    /// Segments returned here are not repeated by
+    /// [`AudioRecorder::stop_recording`]. If VAD fails, the error is logged
+    /// and the rest of the recording is segmented when it stops.
+    pub fn poll_segments(&mut self) -> Vec<Segment> {
+        let Some(streaming_vad) = &mut self.streaming_vad else {
+            return Vec::new();
+        };
+
+        let samples = drain_consumer(self.ring_buffer_consumer.as_mut());
+        if samples.is_empty() {
+            return Vec::new();
+        }
+        let segments = streaming_vad.push(&samples);
+        self.recording_peak = self.recording_peak.max(peak(&samples));
+        self.captured.extend_from_slice(&samples);
+
+        match segments {
+            Ok(segments) => segments
+                .iter()
+                .filter_map(|segment| {
+                    self.encode_segment(segment, 0)
+                        .map_err(|e| error!("Failed to encode speech segment: {}", e))
+                        .ok()
+                })
+                .collect(),
+            Err(e) => {
+                error!("Streaming VAD failed, segmenting the rest after recording: {}", e);
+                self.streaming_vad = None;
+                Vec::new()
+            }
+        }
+    }
+
     /// Clear the audio buffer by consuming all available samples
     ///
     /// # Errors
@@ -113,31 +384,28 @@ impl AudioRecorder {
 
         // Stop and drop the stream
         self.stream = None;
+        self.clear_recent_samples();
 
-        // Collect all samples from the ring buffer
-        let mut samples = Vec::new();
-        if let Some(ref mut consumer) = self.ring_buffer_consumer {
-            while let Ok(chunk) = consumer.read_chunk(consumer.slots()) {
-                if chunk.is_empty() {
-                    break;
-                }
-                // Copy data from the chunk to our samples Vec
-                let (first_slice, second_slice) = chunk.as_slices();
-                samples.extend_from_slice(first_slice);
-                samples.extend_from_slice(second_slice);
-                chunk.commit_all();
-            }
-        }
+        // Collect all samples from the ring buffer, after any already drained
+        // by streaming
+        let mut samples = std::mem::take(&mut self.captured);
+        samples.extend(drain_consumer(self.ring_buffer_consumer.as_mut()));
 
         // Recreate the ring buffer for the next recording
+        self.reset_ring_buffer();
+
+        Ok(samples)
+    }
+
+    /// Replace the ring buffer with an empty one of the same capacity
+    fn reset_ring_buffer(&mut self) {
         let (producer, consumer) = RingBuffer::new(self.ring_buffer_capacity);
         self.ring_buffer_producer = Some(producer);
         self.ring_buffer_consumer = Some(consumer);
-
-        Ok(samples)
     }
 
-    /// Start audio recording from the default input device
+    /// Start audio recording from the preferred input device, or the default
+    /// one
     ///
     /// # Errors
     ///
@@ -145,56 +413,176 @@ impl AudioRecorder {
     /// - No input device is available
     /// - Audio stream creation fails
     /// - Ring buffer is not available
+    /// - The input device stays busy after the configured retries
     pub fn start_recording(&mut self) -> Result<()> {
+        let mut retries = self.device_busy_retries;
+        loop {
+            match self.try_start_recording() {
+                Err(AudioError::DeviceBusy) if retries > 0 => {
+                    retries -= 1;
+                    warn!(
+                        "Input device is busy, retrying in {}ms",
+                        DEVICE_BUSY_RETRY_DELAY.as_millis()
+                    );
+                    std::thread::sleep(DEVICE_BUSY_RETRY_DELAY);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn try_start_recording(&mut self) -> Result<()> {
         // Clear any existing samples
         self.clear_buffer()?;
+        self.clear_recent_samples();
 
         let host = cpal::default_host();
-        let device = host.default_input_device().ok_or(AudioError::NoInputDevice)?;
+        let device = self.input_device(&host)?;
 
         let device_name = device
             .name()
             .map_err(|e| AudioError::StreamCreationFailed(e.to_string()))?;
         debug!("Using input device: {}", device_name);
 
-        let config = device
-            .default_input_config()
-            .map_err(|e| AudioError::StreamCreationFailed(e.to_string()))?;
-        debug!("Default input config: {:?}", config);
+        let config = self.input_config(&device)?;
+        debug!("Input config: {:?}", config);
 
+        // The buffer was sized for the rate assumed before the device was
+        // known, so a 48kHz device would otherwise get a third of the duration
         self.sample_rate = config.sample_rate().0;
+        self.resize_ring_buffer();
+
+        self.captured.clear();
+        self.recording_peak = 0.0;
+        self.dropped_samples.store(0, Ordering::Relaxed);
+        self.streaming_vad = if self.streaming && self.use_vad {
+            Some(StreamingVad::new(self.sample_rate, &self.vad_config)?)
+        } else {
+            None
+        };
+
+        debug!("Ring buffer capacity: {} samples", self.ring_buffer_capacity);
+
+        let recent = Arc::clone(&self.recent_samples);
+        let dropped = Arc::clone(&self.dropped_samples);
+        let stream = self.with_producer(|producer| {
+            let sinks = StreamSinks {
+                producer,
+                recent,
+                dropped,
+            };
+            let stream_config = config.config();
+            let stream = match config.sample_format() {
+                SampleFormat::F32 => Self::build_input_stream::<f32>(&device, &stream_config, sinks)?,
+                SampleFormat::I16 => Self::build_input_stream::<i16>(&device, &stream_config, sinks)?,
+                SampleFormat::U16 => Self::build_input_stream::<u16>(&device, &stream_config, sinks)?,
+                sample_format => {
+                    return Err(AudioError::UnsupportedFormat(format!("{sample_format:?}")));
+                }
+            };
+            stream.play().map_err(play_stream_error)?;
+            Ok(stream)
+        })?;
+        self.stream = Some(stream);
+
+        Ok(())
+    }
 
-        // Take the producer from the option (we'll need to recreate it if this fails)
+    /// Hands the ring buffer's producer to `start`, which moves it into a
+    /// stream. When `start` fails the producer is gone with it, so a fresh
+    /// ring buffer is set up for the next attempt.
+    fn with_producer<T>(&mut self, start: impl FnOnce(Producer<f32>) -> Result<T>) -> Result<T> {
         let producer = self
             .ring_buffer_producer
             .take()
             .ok_or_else(|| AudioError::Other("Ring buffer producer not available".into()))?;
 
-        debug!("Ring buffer capacity: {} samples", self.ring_buffer_capacity);
+        let result = start(producer);
+        if result.is_err() {
+            self.reset_ring_buffer();
+        }
+        result
+    }
 
-        let stream = match config.sample_format() {
-            SampleFormat::F32 => Self::build_input_stream::<f32>(&device, &config.into(), producer)?,
-            SampleFormat::I16 => Self::build_input_stream::<i16>(&device, &config.into(), producer)?,
-            SampleFormat::U16 => Self::build_input_stream::<u16>(&device, &config.into(), producer)?,
-            sample_format => {
-                return Err(AudioError::UnsupportedFormat(format!("{sample_format:?}")));
+    /// The preferred input device if it is connected, the default one
+    /// otherwise
+    fn input_device(&self, host: &cpal::Host) -> Result<cpal::Device> {
+        if let Some(name) = &self.preferred_device {
+            let device = host
+                .input_devices()
+                .ok()
+                .and_then(|mut devices| devices.find(|device| device.name().is_ok_and(|n| n == *name)));
+            if let Some(device) = device {
+                return Ok(device);
             }
-        };
+            warn!("Input device {} not found, using the default one", name);
+        }
+        host.default_input_device().ok_or(AudioError::NoInputDevice)
+    }
 
-        stream
-            .play()
+    /// The default config of `device`, switched to the preferred sample rate
+    /// when the device supports it with the same channels and format
+    fn input_config(&self, device: &cpal::Device) -> Result<cpal::SupportedStreamConfig> {
+        let default = device
+            .default_input_config()
             .map_err(|e| AudioError::StreamCreationFailed(e.to_string()))?;
-        self.stream = Some(stream);
+        let Some(rate) = self
+            .preferred_sample_rate
+            .filter(|&rate| rate != default.sample_rate().0)
+        else {
+            return Ok(default);
+        };
 
-        Ok(())
+        let supported = device.supported_input_configs().ok().and_then(|mut configs| {
+            configs.find(|config| {
+                config.channels() == default.channels()
+                    && config.sample_format() == default.sample_format()
+                    && (config.min_sample_rate().0..=config.max_sample_rate().0).contains(&rate)
+            })
+        });
+        Ok(supported.map_or_else(
+            || {
+                debug!(
+                    "Input device doesn't support {}Hz, using {}Hz",
+                    rate,
+                    default.sample_rate().0
+                );
+                default
+            },
+            |config| config.with_sample_rate(cpal::SampleRate(rate)),
+        ))
+    }
+
+    /// Records from the input device for `duration_ms` and measures
+    /// the levels, to check that the microphone picks anything up. Blocks for
+    /// the duration; the samples are discarded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a recording is already running or capturing fails
+    /// as in [`AudioRecorder::start_recording`]
+    pub fn test_capture(&mut self, duration_ms: u32) -> Result<RecordingStats> {
+        if self.stream.is_some() {
+            return Err(AudioError::Other("A recording is already running".into()));
+        }
+
+        self.start_recording()?;
+        std::thread::sleep(Duration::from_millis(duration_ms.into()));
+        self.streaming_vad = None;
+        let samples = self.stop_and_collect_samples()?;
+        Ok(RecordingStats::measure(&samples, self.sample_rate))
     }
 
     /// Stop audio recording and return results based on VAD setting
     ///
     /// Returns a tuple containing:
-    /// - Raw WAV data of the entire recording
+    /// - Raw WAV data of the entire recording (with head/tail silence removed
+    ///   if [`AudioRecorder::set_trim_silence`] is enabled)
     /// - Vector of WAV data for each detected speech segment (empty if VAD is
-    ///   disabled)
+    ///   disabled). In streaming mode only the segments not yet returned by
+    ///   [`AudioRecorder::poll_segments`] are included.
+    ///
+    /// See [`AudioRecorder::stop_recording_detailed`] for segment timing.
     ///
     /// # Errors
     ///
@@ -205,38 +593,90 @@ impl AudioRecorder {
     /// - Audio resampling fails (if VAD is enabled)
     /// - Stream stop fails
     pub fn stop_recording(&mut self) -> Result<(Vec<u8>, Vec<Vec<u8>>)> {
-        let samples = self.stop_and_collect_samples()?;
+        let result = self.stop_recording_detailed()?;
+        let segments = result.segments.into_iter().map(|segment| segment.wav).collect();
+        Ok((result.raw_wav, segments))
+    }
+
+    /// Stop audio recording like [`AudioRecorder::stop_recording`], keeping
+    /// where each speech segment sat in the recording
+    ///
+    /// # Errors
+    ///
+    /// Same as [`AudioRecorder::stop_recording`]
+    pub fn stop_recording_detailed(&mut self) -> Result<RecordingResult> {
+        let streaming_vad = self.streaming_vad.take();
+        let streamed_len = self.captured.len();
+        let mut samples = self.stop_and_collect_samples()?;
+        self.last_recording_samples = samples.len();
+        let mut stats = RecordingStats::measure(&samples, self.sample_rate);
+        self.recording_peak = stats.peak;
+        let dropped = self.dropped_samples();
+        if dropped > 0 {
+            warn!(
+                "Recording is missing {} samples ({:.1}s) that didn't fit the ring buffer",
+                dropped,
+                samples_to_duration(dropped, self.sample_rate).as_secs_f32()
+            );
+        }
 
         // Always create the raw WAV
-        let raw_wav = self.samples_to_wav(&samples)?;
+        let raw_wav = if self.trim_silence {
+            self.samples_to_wav(&trim_silence(&samples, self.vad_config.trim_threshold))?
+        } else {
+            self.samples_to_wav(&samples)?
+        };
 
-        if self.use_vad {
-            let vad_segments = self.process_samples_with_vad(samples)?;
-            Ok((raw_wav, vad_segments))
+        let (speech_segments, offset_ms) = if let Some(mut streaming_vad) = streaming_vad {
+            let mut segments = streaming_vad.push(&samples[streamed_len..])?;
+            segments.extend(streaming_vad.finish()?);
+            stats.speech_ratio = Some(speech_ratio(&segments, stats.duration));
+            (segments, 0)
+        } else if self.use_vad {
+            // After a streaming VAD failure, skip the audio it already handled
+            samples.drain(..streamed_len);
+            let offset_ms = samples_to_ms(streamed_len, self.sample_rate);
+            let segments = self.process_samples_with_vad(samples)?;
+            // Segments polled before the failure aren't known here
+            if streamed_len == 0 {
+                stats.speech_ratio = Some(speech_ratio(&segments, stats.duration));
+            }
+            (segments, offset_ms)
         } else {
-            Ok((raw_wav, Vec::new())) // Empty segments when VAD is disabled
-        }
+            (Vec::new(), 0) // Empty segments when VAD is disabled
+        };
+
+        let segments = speech_segments
+            .iter()
+            .map(|segment| self.encode_segment(segment, offset_ms))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(RecordingResult {
+            raw_wav,
+            segments,
+            stats,
+        })
     }
 
-    /// Process samples with VAD and return speech segments as WAV data
+    /// Process samples with VAD and return the speech segments at 16kHz
     ///
     /// # Errors
     ///
-    /// Returns an error if VAD processing or WAV encoding fails
-    fn process_samples_with_vad(&mut self, samples: Vec<f32>) -> Result<Vec<Vec<u8>>> {
+    /// Returns an error if VAD processing or resampling fails
+    fn process_samples_with_vad(&self, samples: Vec<f32>) -> Result<Vec<SpeechSegment>> {
         // Resample to 16kHz if needed for VAD
         let samples_16k = if self.sample_rate == 16000 {
             samples
         } else {
             debug!("Resampling from {}Hz to 16000Hz", self.sample_rate);
             let original_len = samples.len();
-            let resampled = self.resample_to_16khz(&samples)?;
+            let resampled = resample(&samples, self.sample_rate, VAD_SAMPLE_RATE)?;
             debug!("Resampled from {} samples to {} samples", original_len, resampled.len());
             resampled
         };
 
         // Process with VAD
-        let mut vad = VadProcessor::new()?;
+        let mut vad = VadProcessor::with_config(&self.vad_config)?;
         let mut speech_segments = vad.process_audio(&samples_16k)?;
 
         // Check if there's a final segment
@@ -244,98 +684,57 @@ impl AudioRecorder {
             speech_segments.push(final_segment);
         }
 
-        // Convert each segment to WAV (at 16kHz)
-        let mut wav_segments = Vec::new();
-        let original_rate = self.sample_rate;
-        self.sample_rate = 16000; // Temporarily set to 16kHz for WAV output
-
-        for segment in speech_segments {
-            let wav_data = self.samples_to_wav(&segment)?;
-            wav_segments.push(wav_data);
-        }
-
-        self.sample_rate = original_rate; // Restore original rate
-
-        Ok(wav_segments)
-    }
-
-    /// Resample audio from current sample rate to 16kHz
-    fn resample_to_16khz(&self, samples: &[f32]) -> Result<Vec<f32>> {
-        use rubato::{Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction};
-
-        let params = SincInterpolationParameters {
-            sinc_len: 256,
-            f_cutoff: 0.95,
-            interpolation: SincInterpolationType::Linear,
-            oversampling_factor: 256,
-            window: WindowFunction::BlackmanHarris2,
-        };
-
-        // Create resampler with proper chunk size
-        let chunk_size = 1024;
-        let mut resampler =
-            SincFixedIn::<f32>::new(16000_f64 / f64::from(self.sample_rate), 2.0, params, chunk_size, 1)
-                .map_err(|e| AudioError::StreamCreationFailed(format!("Failed to create resampler: {e}")))?;
-
-        // Process all samples in chunks
-        let mut output = Vec::new();
-        let mut position = 0;
-
-        while position < samples.len() {
-            let end = (position + chunk_size).min(samples.len());
-            let chunk = &samples[position..end];
-
-            if chunk.len() == chunk_size {
-                // Process full chunk
-                let waves_in = vec![chunk.to_vec()];
-                let waves_out = resampler
-                    .process(&waves_in, None)
-                    .map_err(|e| AudioError::StreamCreationFailed(format!("Resampling failed: {e}")))?;
-                if let Some(out_chunk) = waves_out.first() {
-                    output.extend_from_slice(out_chunk);
-                }
-            } else if !chunk.is_empty() {
-                // Process last partial chunk with padding
-                let mut padded = chunk.to_vec();
-                padded.resize(chunk_size, 0.0);
-                let waves_in = vec![padded];
-                let waves_out = resampler
-                    .process(&waves_in, None)
-                    .map_err(|e| AudioError::StreamCreationFailed(format!("Resampling failed: {e}")))?;
-                if let Some(out_chunk) = waves_out.first() {
-                    // Only take the proportional amount of output samples
-                    // Safe: chunk.len() is audio chunk size (typically small), calculation result
-                    // is bounded by resampling ratio
-                    #[allow(
-                        clippy::cast_precision_loss,
-                        clippy::cast_possible_truncation,
-                        clippy::cast_sign_loss
-                    )]
-                    let output_len = (chunk.len() as f64 * 16000.0 / f64::from(self.sample_rate)) as usize;
-                    output.extend_from_slice(&out_chunk[..output_len.min(out_chunk.len())]);
-                }
-            }
-
-            position = end;
-        }
-
-        Ok(output)
+        let merge_gap = samples_from_ms(self.vad_config.merge_gap_ms, VAD_SAMPLE_RATE);
+        Ok(merge_segments(speech_segments, merge_gap, &samples_16k))
     }
 
     fn build_input_stream<T>(
-        device: &cpal::Device, config: &cpal::StreamConfig, mut producer: Producer<f32>,
+        device: &cpal::Device, config: &cpal::StreamConfig, sinks: StreamSinks,
     ) -> Result<cpal::Stream>
     where
         T: cpal::SizedSample + Send + 'static,
         f32: cpal::FromSample<T>,
     {
         let err_fn = |err| error!("An error occurred on the audio stream: {}", err);
+        let channels = usize::from(config.channels);
+        if channels > 1 {
+            debug!("Down-mixing {} input channels to mono", channels);
+        }
+        let StreamSinks {
+            mut producer,
+            recent,
+            dropped,
+        } = sinks;
+
+        // cpal owns the callback thread, so its log lines are tagged by span instead of thread name
+        let span = tracing::info_span!("audio_input");
+        let err_span = span.clone();
+        let err_fn = move |err| {
+            let _entered = err_span.enter();
+            err_fn(err);
+        };
 
         let stream = device
             .build_input_stream(
                 config,
                 move |data: &[T], _: &cpal::InputCallbackInfo| {
+                    let _entered = span.enter();
                     let samples: Vec<f32> = data.iter().map(|sample| sample.to_sample::<f32>()).collect();
+                    let samples = downmix_to_mono(samples, channels);
+
+                    // Never block the audio thread; skip the display update if the UI holds the lock
+                    if let Ok(mut recent) = recent.try_lock() {
+                        recent.extend(&samples);
+                        let excess = recent.len().saturating_sub(WAVEFORM_SAMPLES);
+                        recent.drain(..excess);
+                    }
+
+                    // Keep what fits and count the rest, rather than losing the whole callback
+                    let writable = samples.len().min(producer.slots());
+                    if writable < samples.len() && dropped.fetch_add(samples.len() - writable, Ordering::Relaxed) == 0 {
+                        warn!("Ring buffer full, dropping audio samples");
+                    }
+                    let samples = &samples[..writable];
 
                     if let Ok(mut chunk) = producer.write_chunk_uninit(samples.len()) {
                         let mut write_pos = 0;
@@ -358,46 +757,56 @@ impl AudioRecorder {
                         unsafe {
                             chunk.commit_all();
                         }
-                    } else {
-                        debug!("Ring buffer full, dropping audio samples");
                     }
                 },
                 err_fn,
                 None,
             )
-            .map_err(|e| AudioError::StreamCreationFailed(e.to_string()))?;
+            .map_err(build_stream_error)?;
 
         Ok(stream)
     }
 
     fn samples_to_wav(&self, samples: &[f32]) -> Result<Vec<u8>> {
-        let spec = hound::WavSpec {
-            channels: 1,
-            sample_rate: self.sample_rate, // Use actual sample rate
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
+        encode_wav_as(&self.apply_level(samples), self.sample_rate, self.output_format)
+    }
 
-        let mut cursor = Cursor::new(Vec::new());
-        {
-            let mut writer =
-                hound::WavWriter::new(&mut cursor, spec).map_err(|e| AudioError::WavEncodingFailed(e.to_string()))?;
+    /// Encode `segment`, whose offsets start `offset_ms` into the recording
+    fn encode_segment(&self, segment: &SpeechSegment, offset_ms: u64) -> Result<Segment> {
+        Ok(Segment {
+            wav: self.segment_to_wav(&pad_with_silence(&segment.samples, self.segment_tail_pad_ms))?,
+            start_ms: offset_ms + samples_to_ms(segment.start_sample, VAD_SAMPLE_RATE),
+            end_ms: offset_ms + samples_to_ms(segment.end_sample, VAD_SAMPLE_RATE),
+        })
+    }
 
-            for sample in samples {
-                // Proper conversion from f32 audio sample [-1.0, 1.0] to int16 with clamping
-                #[allow(clippy::cast_possible_truncation)]
-                let amplitude = (sample.clamp(-1.0, 1.0) * 32767.0).round().clamp(-32768.0, 32767.0) as i16;
-                writer
-                    .write_sample(amplitude)
-                    .map_err(|e| AudioError::WavEncodingFailed(e.to_string()))?;
-            }
+    /// Encode mono speech samples at [`VAD_SAMPLE_RATE`] as WAV data,
+    /// applying the configured gain or the last recording's normalization
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if WAV encoding fails
+    pub fn segment_to_wav(&self, segment: &[f32]) -> Result<Vec<u8>> {
+        encode_wav_as(&self.apply_level(segment), VAD_SAMPLE_RATE, self.output_format)
+    }
 
-            writer
-                .finalize()
-                .map_err(|e| AudioError::WavEncodingFailed(e.to_string()))?;
-        }
+    /// Apply the configured gain or the recording's peak normalization, then
+    /// clamp to [-1.0, 1.0]
+    fn apply_level<'a>(&self, samples: &'a [f32]) -> Cow<'a, [f32]> {
+        let Some(factor) = self.level_factor() else {
+            return Cow::Borrowed(samples);
+        };
+        Cow::Owned(samples.iter().map(|s| (s * factor).clamp(-1.0, 1.0)).collect())
+    }
 
-        Ok(cursor.into_inner())
+    /// Factor encoded samples are scaled by; `None` leaves them unchanged
+    fn level_factor(&self) -> Option<f32> {
+        match self.normalize_peak_dbfs {
+            Some(_) if self.recording_peak <= 0.0 => None,
+            Some(target_dbfs) => Some(db_to_amplitude(target_dbfs) / self.recording_peak),
+            None if self.gain_db == 0.0 => None,
+            None => Some(db_to_amplitude(self.gain_db)),
+        }
     }
 
     /// Save samples directly to a WAV file
@@ -409,29 +818,300 @@ impl AudioRecorder {
     /// - WAV encoding fails
     /// - File writing fails
     pub fn save_samples_to_file(&self, samples: &[f32], path: &std::path::Path) -> Result<()> {
-        let spec = hound::WavSpec {
-            channels: 1,
-            sample_rate: self.sample_rate,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
+        let file = std::fs::File::create(path).map_err(|e| AudioError::WavEncodingFailed(e.to_string()))?;
+        write_wav(
+            std::io::BufWriter::new(file),
+            &self.apply_level(samples),
+            self.sample_rate,
+            self.output_format,
+        )
+    }
+}
+
+/// Encode mono samples as 16-bit PCM WAV data, e.g. speech samples at
+/// [`VAD_SAMPLE_RATE`]
+///
+/// # Errors
+///
+/// Returns an error if WAV encoding fails
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
+    encode_wav_as(samples, sample_rate, WavFormat::Int16)
+}
+
+/// Encode mono samples as WAV data in `format`
+fn encode_wav_as(samples: &[f32], sample_rate: u32, format: WavFormat) -> Result<Vec<u8>> {
+    let mut cursor = Cursor::new(Vec::new());
+    write_wav(&mut cursor, samples, sample_rate, format)?;
+    Ok(cursor.into_inner())
+}
+
+/// Write mono samples to `writer` as WAV data in `format`
+fn write_wav<W: std::io::Write + std::io::Seek>(
+    writer: W, samples: &[f32], sample_rate: u32, format: WavFormat,
+) -> Result<()> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: match format {
+            WavFormat::Int16 => 16,
+            WavFormat::Float32 => 32,
+        },
+        sample_format: match format {
+            WavFormat::Int16 => hound::SampleFormat::Int,
+            WavFormat::Float32 => hound::SampleFormat::Float,
+        },
+    };
 
-        let mut writer =
-            hound::WavWriter::create(path, spec).map_err(|e| AudioError::WavEncodingFailed(e.to_string()))?;
+    let mut writer = hound::WavWriter::new(writer, spec).map_err(|e| AudioError::WavEncodingFailed(e.to_string()))?;
 
-        for sample in samples {
-            // Proper conversion from f32 audio sample [-1.0, 1.0] to int16 with clamping
-            #[allow(clippy::cast_possible_truncation)]
-            let amplitude = (sample.clamp(-1.0, 1.0) * 32767.0).round().clamp(-32768.0, 32767.0) as i16;
-            writer
-                .write_sample(amplitude)
-                .map_err(|e| AudioError::WavEncodingFailed(e.to_string()))?;
+    for sample in samples {
+        let sample = sample.clamp(-1.0, 1.0);
+        match format {
+            WavFormat::Int16 => {
+                // Proper conversion from f32 audio sample [-1.0, 1.0] to int16 with clamping
+                #[allow(clippy::cast_possible_truncation)]
+                let amplitude = (sample * 32767.0).round().clamp(-32768.0, 32767.0) as i16;
+                writer.write_sample(amplitude)
+            }
+            WavFormat::Float32 => writer.write_sample(sample),
         }
+        .map_err(|e| AudioError::WavEncodingFailed(e.to_string()))?;
+    }
 
-        writer
-            .finalize()
-            .map_err(|e| AudioError::WavEncodingFailed(e.to_string()))?;
+    writer
+        .finalize()
+        .map_err(|e| AudioError::WavEncodingFailed(e.to_string()))
+}
 
-        Ok(())
+/// Largest absolute sample value
+fn peak(samples: &[f32]) -> f32 {
+    samples.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()))
+}
+
+/// Convert a level in dB to a linear amplitude factor
+fn db_to_amplitude(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+/// Number of mono samples in `ms` milliseconds at `sample_rate`
+fn samples_from_ms(ms: u32, sample_rate: u32) -> usize {
+    usize::try_from(u64::from(ms) * u64::from(sample_rate) / 1000).unwrap_or(usize::MAX)
+}
+
+/// Duration of `samples` mono samples at `sample_rate` in milliseconds
+fn samples_to_ms(samples: usize, sample_rate: u32) -> u64 {
+    (samples as u64).saturating_mul(1000) / u64::from(sample_rate)
+}
+
+/// Duration of `samples` mono samples at `sample_rate`
+fn samples_to_duration(samples: usize, sample_rate: u32) -> Duration {
+    Duration::from_millis(samples_to_ms(samples, sample_rate))
+}
+
+/// Read every sample currently available from the ring buffer
+fn drain_consumer(consumer: Option<&mut Consumer<f32>>) -> Vec<f32> {
+    let mut samples = Vec::new();
+    if let Some(consumer) = consumer {
+        while let Ok(chunk) = consumer.read_chunk(consumer.slots()) {
+            if chunk.is_empty() {
+                break;
+            }
+            // Copy data from the chunk to our samples Vec
+            let (first_slice, second_slice) = chunk.as_slices();
+            samples.extend_from_slice(first_slice);
+            samples.extend_from_slice(second_slice);
+            chunk.commit_all();
+        }
+    }
+    samples
+}
+
+/// Average each interleaved frame of `channels` samples into one mono sample.
+///
+/// Mono input is returned unchanged; a trailing partial frame is averaged over
+/// the samples it has.
+fn downmix_to_mono(samples: Vec<f32>, channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples;
+    }
+
+    samples
+        .chunks(channels)
+        .map(|frame| {
+            #[allow(clippy::cast_precision_loss)]
+            let len = frame.len() as f32;
+            frame.iter().sum::<f32>() / len
+        })
+        .collect()
+}
+
+/// `samples` at [`VAD_SAMPLE_RATE`] followed by `pad_ms` milliseconds of
+/// silence
+fn pad_with_silence(samples: &[f32], pad_ms: u32) -> Cow<'_, [f32]> {
+    if pad_ms == 0 {
+        return Cow::Borrowed(samples);
+    }
+    let mut padded = samples.to_vec();
+    padded.resize(samples.len() + samples_from_ms(pad_ms, VAD_SAMPLE_RATE), 0.0);
+    Cow::Owned(padded)
+}
+
+/// Whether a backend error says the device is held by another application:
+/// `AUDCLNT_E_DEVICE_IN_USE` on Windows, `EBUSY` elsewhere
+fn is_device_busy(err: &cpal::BackendSpecificError) -> bool {
+    let description = err.description.to_ascii_lowercase();
+    ["8889000a", "in use", "busy"]
+        .iter()
+        .any(|needle| description.contains(needle))
+}
+
+fn build_stream_error(err: cpal::BuildStreamError) -> AudioError {
+    match err {
+        cpal::BuildStreamError::DeviceNotAvailable => AudioError::DeviceBusy,
+        cpal::BuildStreamError::BackendSpecific { err } if is_device_busy(&err) => AudioError::DeviceBusy,
+        err => AudioError::StreamCreationFailed(err.to_string()),
+    }
+}
+
+fn play_stream_error(err: cpal::PlayStreamError) -> AudioError {
+    match err {
+        cpal::PlayStreamError::DeviceNotAvailable => AudioError::DeviceBusy,
+        cpal::PlayStreamError::BackendSpecific { err } if is_device_busy(&err) => AudioError::DeviceBusy,
+        err @ cpal::PlayStreamError::BackendSpecific { .. } => AudioError::StreamCreationFailed(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downmix_stereo_to_mono() {
+        // Interleaved L/R frames
+        let stereo = vec![1.0, 0.0, 0.5, 0.5, -1.0, 1.0, 0.2, 0.4];
+
+        let mono = downmix_to_mono(stereo.clone(), 2);
+
+        assert_eq!(mono.len(), stereo.len() / 2);
+        let expected = [0.5, 0.5, 0.0, 0.3];
+        for (actual, expected) in mono.iter().zip(expected) {
+            assert!((actual - expected).abs() < f32::EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_gain_scales_and_clamps() {
+        let mut recorder = AudioRecorder::new_without_vad();
+        recorder.set_gain(20.0);
+
+        let scaled = recorder.apply_level(&[0.01, -0.05, 0.5]);
+
+        assert!((scaled[0] - 0.1).abs() < 1e-5);
+        assert!((scaled[1] + 0.5).abs() < 1e-5);
+        assert!((scaled[2] - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_normalize_uses_whole_recording_peak() {
+        let mut recorder = AudioRecorder::new_without_vad();
+        recorder.set_normalize(Some(-6.0));
+        recorder.recording_peak = peak(&[0.1, -0.2, 0.05]);
+
+        let target = db_to_amplitude(-6.0);
+        let normalized = recorder.apply_level(&[0.1, -0.2, 0.05]);
+        assert!((normalized[1] + target).abs() < 1e-5);
+        assert!((normalized[0] - target / 2.0).abs() < 1e-5);
+
+        // A quiet segment keeps its level relative to the loudest part
+        let segment = recorder.apply_level(&[0.05]);
+        assert!((segment[0] - target / 4.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_builder_sizes_ring_buffer_once() {
+        let recorder = AudioRecorder::builder()
+            .vad(false)
+            .sample_rate(48_000)
+            .max_duration(Duration::from_secs(10))
+            .build();
+
+        assert!(!recorder.use_vad);
+        assert_eq!(recorder.max_duration_seconds, 10);
+        assert_eq!(recorder.ring_buffer_capacity, 480_000);
+        assert_eq!(
+            recorder.ring_buffer_producer.as_ref().map(Producer::slots),
+            Some(480_000)
+        );
+    }
+
+    #[test]
+    fn test_ring_buffer_sized_for_device_rate() {
+        let mut recorder = AudioRecorder::new();
+        recorder.sample_rate = 48_000;
+        recorder.resize_ring_buffer();
+
+        assert_eq!(recorder.ring_buffer_capacity, 300 * 48_000);
+        assert_eq!(
+            recorder.ring_buffer_producer.as_ref().map(Producer::slots),
+            Some(300 * 48_000)
+        );
+    }
+
+    #[test]
+    fn test_peak_dbfs() {
+        let stats = RecordingStats::measure(&[0.0, -0.5, 0.25], 16_000);
+        assert!((stats.peak_dbfs() - -6.0206).abs() < 1e-3);
+        assert!(RecordingStats::measure(&[0.0; 4], 16_000).peak_dbfs() < NO_SIGNAL_DBFS);
+    }
+
+    #[test]
+    fn test_downmix_mono_is_unchanged() {
+        let mono = vec![0.1, -0.2, 0.3];
+        assert_eq!(downmix_to_mono(mono.clone(), 1), mono);
+    }
+
+    #[test]
+    fn test_segment_tail_pad() {
+        let segment = vec![0.5; 1000];
+
+        let padded = pad_with_silence(&segment, 200);
+
+        assert_eq!(padded.len(), segment.len() + 3200);
+        assert_eq!(padded[..segment.len()], segment[..]);
+        assert!(padded[segment.len()..].iter().all(|&sample| sample == 0.0));
+        assert_eq!(pad_with_silence(&segment, 0).len(), segment.len());
+    }
+
+    #[test]
+    fn test_failed_start_restores_producer() {
+        let mut recorder = AudioRecorder::new_without_vad();
+
+        let result: Result<()> = recorder.with_producer(|_| Err(AudioError::DeviceBusy));
+
+        assert!(matches!(result, Err(AudioError::DeviceBusy)));
+        assert!(recorder.ring_buffer_producer.is_some());
+        assert!(recorder.ring_buffer_consumer.is_some());
+    }
+
+    #[test]
+    fn test_busy_device_errors_are_detected() {
+        let backend = |description: &str| cpal::BuildStreamError::BackendSpecific {
+            err: cpal::BackendSpecificError {
+                description: description.into(),
+            },
+        };
+
+        assert!(matches!(
+            build_stream_error(cpal::BuildStreamError::DeviceNotAvailable),
+            AudioError::DeviceBusy
+        ));
+        assert!(matches!(
+            build_stream_error(backend("Error 0x8889000A: device in use")),
+            AudioError::DeviceBusy
+        ));
+        assert!(matches!(
+            build_stream_error(backend("Unsupported sample rate")),
+            AudioError::StreamCreationFailed(_)
+        ));
     }
 }
@@ -0,0 +1,136 @@
+//! Sample rate conversion
+
+use rubato::{Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction};
+
+use crate::error::{AudioError, Result};
+
+/// Input frames passed to the resampler per call
+pub const RESAMPLER_CHUNK_SIZE: usize = 1024;
+
+/// Create a mono resampler from `from` to `to` Hz, taking
+/// [`RESAMPLER_CHUNK_SIZE`] input frames per call
+///
+/// # Errors
+///
+/// Returns an error if the rates can't be converted between.
+pub fn new_resampler(from: u32, to: u32) -> Result<SincFixedIn<f32>> {
+    let params = SincInterpolationParameters {
+        sinc_len: 256,
+        f_cutoff: 0.95,
+        interpolation: SincInterpolationType::Linear,
+        oversampling_factor: 256,
+        window: WindowFunction::BlackmanHarris2,
+    };
+
+    SincFixedIn::<f32>::new(f64::from(to) / f64::from(from), 2.0, params, RESAMPLER_CHUNK_SIZE, 1)
+        .map_err(|e| AudioError::StreamCreationFailed(format!("Failed to create resampler: {e}")))
+}
+
+/// Resample mono `samples` from `from` to `to` Hz.
+///
+/// The input is processed in [`RESAMPLER_CHUNK_SIZE`] chunks; a partial last
+/// chunk is zero-padded and only the output for its real samples is kept, so
+/// the result is about `samples.len() * to / from` long. The sinc filter
+/// delays its output by the resampler's `output_delay()` frames, which are
+/// missing from the end.
+///
+/// # Errors
+///
+/// Returns an error if the resampler can't be created or fails.
+pub fn resample(samples: &[f32], from: u32, to: u32) -> Result<Vec<f32>> {
+    if from == to {
+        return Ok(samples.to_vec());
+    }
+
+    let mut resampler = new_resampler(from, to)?;
+    let mut output = Vec::new();
+    for chunk in samples.chunks(RESAMPLER_CHUNK_SIZE) {
+        output.extend(process_chunk(&mut resampler, chunk, from, to)?);
+    }
+    Ok(output)
+}
+
+/// Resample one chunk of at most [`RESAMPLER_CHUNK_SIZE`] samples, padding a
+/// partial chunk and trimming its output to match
+pub(crate) fn process_chunk(resampler: &mut SincFixedIn<f32>, chunk: &[f32], from: u32, to: u32) -> Result<Vec<f32>> {
+    let mut padded;
+    let input = if chunk.len() == RESAMPLER_CHUNK_SIZE {
+        chunk
+    } else {
+        padded = chunk.to_vec();
+        padded.resize(RESAMPLER_CHUNK_SIZE, 0.0);
+        &padded
+    };
+
+    let waves_out = resampler
+        .process(&[input], None)
+        .map_err(|e| AudioError::StreamCreationFailed(format!("Resampling failed: {e}")))?;
+    let Some(mut out_chunk) = waves_out.into_iter().next() else {
+        return Ok(Vec::new());
+    };
+    if chunk.len() < RESAMPLER_CHUNK_SIZE {
+        out_chunk.truncate(resampled_len(chunk.len(), from, to));
+    }
+    Ok(out_chunk)
+}
+
+/// Number of output samples corresponding to `input_len` input samples
+fn resampled_len(input_len: usize, from: u32, to: u32) -> usize {
+    // Chunk lengths are far below the range where f64 loses precision
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    let len = (input_len as f64 * f64::from(to) / f64::from(from)) as usize;
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(len: usize, frequency: f32, sample_rate: u32) -> Vec<f32> {
+        #[allow(clippy::cast_precision_loss)]
+        (0..len)
+            .map(|i| (std::f32::consts::TAU * frequency * i as f32 / sample_rate as f32).sin() * 0.5)
+            .collect()
+    }
+
+    /// Asserts `output` is `expected` samples long, less the filter delay and
+    /// per-chunk rounding
+    fn assert_resampled_len(output: &[f32], expected: usize, from: u32, to: u32) {
+        let delay = new_resampler(from, to).unwrap().output_delay();
+        let shortfall = expected.saturating_sub(output.len());
+        assert!(
+            output.len() <= expected && shortfall <= delay + 10,
+            "expected about {expected} samples, got {}",
+            output.len()
+        );
+    }
+
+    #[test]
+    fn test_resample_preserves_length_ratio() {
+        let input = sine(44_100, 440.0, 44_100);
+
+        let output = resample(&input, 44_100, 16_000).unwrap();
+
+        assert_resampled_len(&output, 16_000, 44_100, 16_000);
+        assert!(output.iter().all(|sample| sample.is_finite() && sample.abs() <= 1.0));
+    }
+
+    #[test]
+    fn test_resample_partial_chunks() {
+        // Shorter than one chunk: only the output for the real samples is kept
+        let output = resample(&sine(100, 440.0, 44_100), 44_100, 16_000).unwrap();
+        assert_eq!(output.len(), resampled_len(100, 44_100, 16_000));
+
+        // Whole chunks plus a partial one
+        let len = RESAMPLER_CHUNK_SIZE * 2 + 300;
+        let output = resample(&sine(len, 440.0, 48_000), 48_000, 16_000).unwrap();
+        assert_resampled_len(&output, len / 3, 48_000, 16_000);
+
+        assert!(resample(&[], 44_100, 16_000).unwrap().is_empty());
+        assert_eq!(resample(&[0.1, 0.2], 16_000, 16_000).unwrap(), vec![0.1, 0.2]);
+    }
+}
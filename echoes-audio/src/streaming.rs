@@ -0,0 +1,90 @@
+use echoes_config::VadConfig;
+use rubato::SincFixedIn;
+use tracing::debug;
+
+use crate::{
+    error::Result,
+    resample::{new_resampler, process_chunk, RESAMPLER_CHUNK_SIZE},
+    vad::{SpeechEvent, SpeechSegment, VadProcessor, VAD_SAMPLE_RATE},
+};
+
+/// Runs VAD on audio while it is being captured, so finished speech segments
+/// can be handed out before the recording stops.
+///
+/// Input is buffered until a whole resampler chunk is available; a partial
+/// chunk is only zero-padded once, in [`StreamingVad::finish`].
+pub struct StreamingVad {
+    vad: VadProcessor,
+    sample_rate: u32,
+    /// `None` when the input is already at the VAD rate
+    resampler: Option<SincFixedIn<f32>>,
+    /// Captured samples not yet resampled (less than one resampler chunk)
+    pending_input: Vec<f32>,
+}
+
+impl StreamingVad {
+    pub fn new(sample_rate: u32, vad_config: &VadConfig) -> Result<Self> {
+        let resampler = if sample_rate == VAD_SAMPLE_RATE {
+            None
+        } else {
+            debug!(
+                "Streaming VAD resamples from {}Hz to {}Hz",
+                sample_rate, VAD_SAMPLE_RATE
+            );
+            Some(new_resampler(sample_rate, VAD_SAMPLE_RATE)?)
+        };
+
+        Ok(Self {
+            vad: VadProcessor::with_config(vad_config)?,
+            sample_rate,
+            resampler,
+            pending_input: Vec::new(),
+        })
+    }
+
+    /// Feed newly captured samples and return the speech segments (at the VAD
+    /// rate) that were finalized by them
+    pub fn push(&mut self, samples: &[f32]) -> Result<Vec<SpeechSegment>> {
+        let resampled = match &mut self.resampler {
+            None => samples.to_vec(),
+            Some(resampler) => {
+                self.pending_input.extend_from_slice(samples);
+                let whole = self.pending_input.len() - self.pending_input.len() % RESAMPLER_CHUNK_SIZE;
+
+                let mut output = Vec::new();
+                for chunk in self.pending_input[..whole].chunks_exact(RESAMPLER_CHUNK_SIZE) {
+                    output.extend(process_chunk(resampler, chunk, self.sample_rate, VAD_SAMPLE_RATE)?);
+                }
+                self.pending_input.drain(..whole);
+                output
+            }
+        };
+
+        Ok(ended_segments(self.vad.push(&resampled)))
+    }
+
+    /// Flush the buffered tail and return the remaining speech segments
+    pub fn finish(mut self) -> Result<Vec<SpeechSegment>> {
+        let mut events = Vec::new();
+        if let Some(resampler) = &mut self.resampler {
+            if !self.pending_input.is_empty() {
+                let tail = process_chunk(resampler, &self.pending_input, self.sample_rate, VAD_SAMPLE_RATE)?;
+                events = self.vad.push(&tail);
+            }
+        }
+
+        events.extend(self.vad.flush());
+        Ok(ended_segments(events))
+    }
+}
+
+/// The segments of the `SpeechEnd` events
+fn ended_segments(events: Vec<SpeechEvent>) -> Vec<SpeechSegment> {
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            SpeechEvent::SpeechEnd(segment) => Some(segment),
+            _ => None,
+        })
+        .collect()
+}
@@ -1,11 +1,137 @@
+use std::ops::Range;
+
+use echoes_config::VadConfig;
 use tracing::debug;
 use voice_activity_detector::VoiceActivityDetector;
 
 use crate::error::{AudioError, Result};
 
+/// Sample rate the VAD model runs at; speech segments are returned at this rate
+pub const VAD_SAMPLE_RATE: u32 = 16000;
+
+/// Chunk sizes the VAD model was trained on at `sample_rate`; it only
+/// supports 8kHz and 16kHz
+#[must_use]
+pub const fn supported_chunk_sizes(sample_rate: u32) -> &'static [usize] {
+    match sample_rate {
+        8000 => &[256, 512, 768],
+        16000 => &[512, 1024, 1536],
+        _ => &[],
+    }
+}
+
+/// Checks that the VAD model accepts chunks of `chunk_size` samples at
+/// `sample_rate`
+///
+/// # Errors
+///
+/// Returns `AudioError::UnsupportedVadChunkSize` for any other size
+pub fn validate_chunk_size(chunk_size: usize, sample_rate: u32) -> Result<()> {
+    if supported_chunk_sizes(sample_rate).contains(&chunk_size) {
+        Ok(())
+    } else {
+        Err(AudioError::UnsupportedVadChunkSize(chunk_size, sample_rate))
+    }
+}
+
+/// Amplitude below which a sample counts as silence when trimming
+pub const DEFAULT_SILENCE_THRESHOLD: f32 = echoes_config::DEFAULT_TRIM_THRESHOLD;
+
+/// Trim samples quieter than `threshold` from the beginning and end.
+///
+/// Returns an empty vector if no sample exceeds the threshold.
+#[must_use]
+pub fn trim_silence(samples: &[f32], threshold: f32) -> Vec<f32> {
+    samples[trim_silence_range(samples, threshold)].to_vec()
+}
+
+/// Range of `samples` left after [`trim_silence`]
+fn trim_silence_range(samples: &[f32], threshold: f32) -> Range<usize> {
+    let start = samples.iter().position(|&s| s.abs() > threshold).unwrap_or(0);
+
+    let end = samples
+        .iter()
+        .rposition(|&s| s.abs() > threshold)
+        .map_or(samples.len(), |pos| pos + 1);
+
+    if start < end {
+        start..end
+    } else {
+        0..0
+    }
+}
+
+/// A detected speech segment and where it sits in the processed audio
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeechSegment {
+    /// Mono samples at [`VAD_SAMPLE_RATE`]
+    pub samples: Vec<f32>,
+    /// Offset of the first sample from the start of the processed audio
+    pub start_sample: usize,
+    /// Offset one past the last sample
+    pub end_sample: usize,
+}
+
+impl SpeechSegment {
+    /// Trim samples quieter than `threshold` from the samples at `offset` and
+    /// record their position
+    fn trimmed(samples: &[f32], offset: usize, threshold: f32) -> Self {
+        let range = trim_silence_range(samples, threshold);
+        Self {
+            samples: samples[range.clone()].to_vec(),
+            start_sample: offset + range.start,
+            end_sample: offset + range.end,
+        }
+    }
+}
+
+/// Merge segments separated by fewer than `max_gap` samples into one.
+///
+/// `audio` is the audio the segments' offsets point into; the pause between
+/// merged segments is taken from it, or filled with silence where it isn't
+/// covered. A `max_gap` of 0 returns the segments unchanged.
+#[must_use]
+pub fn merge_segments(segments: Vec<SpeechSegment>, max_gap: usize, audio: &[f32]) -> Vec<SpeechSegment> {
+    if max_gap == 0 {
+        return segments;
+    }
+
+    let mut merged: Vec<SpeechSegment> = Vec::with_capacity(segments.len());
+    for segment in segments {
+        match merged.last_mut() {
+            Some(previous) if segment.start_sample.saturating_sub(previous.end_sample) < max_gap => {
+                let gap = previous.end_sample..segment.start_sample.max(previous.end_sample);
+                match audio.get(gap.clone()) {
+                    Some(pause) => previous.samples.extend_from_slice(pause),
+                    None => previous.samples.resize(previous.samples.len() + gap.len(), 0.0),
+                }
+                previous.samples.extend_from_slice(&segment.samples);
+                previous.end_sample = segment.end_sample;
+            }
+            _ => merged.push(segment),
+        }
+    }
+    merged
+}
+
+/// Speech activity reported by [`VadProcessor::push`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpeechEvent {
+    /// Speech began at this offset in the processed audio
+    SpeechStart { start_sample: usize },
+    /// Speech ended; the segment is trimmed of leading/trailing silence
+    SpeechEnd(SpeechSegment),
+    /// Speech ended but was shorter than the minimum segment length
+    SpeechDiscarded,
+}
+
 /// Voice Activity Detector wrapper for audio processing
 pub struct VadProcessor {
     detector: VoiceActivityDetector,
+    /// Number of samples scored at a time
+    chunk_size: usize,
+    /// Speech probability above which a chunk counts as speech
+    threshold: f32,
     /// Number of consecutive frames to wait before switching states
     hangover_frames: usize,
     /// Counter for hangover mechanism
@@ -14,8 +140,16 @@ pub struct VadProcessor {
     is_speaking: bool,
     /// Minimum speech duration in samples (to avoid very short segments)
     min_speech_samples: usize,
+    /// Amplitude below which segment edges are trimmed as silence
+    trim_threshold: f32,
     /// Speech segment buffer
     current_segment: Vec<f32>,
+    /// Offset of `current_segment` in the processed audio
+    segment_start: usize,
+    /// Number of samples processed so far
+    processed_samples: usize,
+    /// Pushed samples that don't fill a whole chunk yet
+    pending: Vec<f32>,
 }
 
 impl VadProcessor {
@@ -25,85 +159,121 @@ impl VadProcessor {
     ///
     /// Returns an error if the VAD detector cannot be initialized.
     pub fn new() -> Result<Self> {
+        Self::with_config(&VadConfig::default())
+    }
+
+    /// Creates a VAD processor with the given tuning, e.g. from a
+    /// `VadSensitivity` preset.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chunk size isn't supported or the VAD detector
+    /// cannot be initialized.
+    pub fn with_config(config: &VadConfig) -> Result<Self> {
+        validate_chunk_size(config.chunk_size, VAD_SAMPLE_RATE)?;
         let detector = VoiceActivityDetector::builder()
-            .sample_rate(16000)
-            .chunk_size(512usize)
+            .sample_rate(VAD_SAMPLE_RATE)
+            .chunk_size(config.chunk_size)
             .build()
             .map_err(|e| AudioError::StreamCreationFailed(format!("Failed to build VAD detector: {e}")))?;
 
         Ok(Self {
             detector,
-            hangover_frames: 10,
+            chunk_size: config.chunk_size,
+            threshold: config.threshold,
+            hangover_frames: config.hangover_frames,
             silence_counter: 0,
             is_speaking: false,
-            min_speech_samples: 4800,
+            min_speech_samples: config.min_speech_samples,
+            trim_threshold: config.trim_threshold,
             current_segment: Vec::new(),
+            segment_start: 0,
+            processed_samples: 0,
+            pending: Vec::new(),
         })
     }
 
-    /// Process audio samples and extract speech segments
+    /// Feed mono samples at [`VAD_SAMPLE_RATE`] and return the speech events
+    /// they complete. Samples are scored in chunks of the configured size; a
+    /// trailing partial chunk is kept until more samples arrive or
+    /// [`VadProcessor::flush`] is called.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<SpeechEvent> {
+        let mut events = Vec::new();
+        let mut samples = samples;
+
+        if !self.pending.is_empty() {
+            let needed = (self.chunk_size - self.pending.len()).min(samples.len());
+            self.pending.extend_from_slice(&samples[..needed]);
+            samples = &samples[needed..];
+            if self.pending.len() < self.chunk_size {
+                return events;
+            }
+            let chunk = std::mem::take(&mut self.pending);
+            self.process_chunk(&chunk, &mut events);
+        }
+
+        let mut chunks = samples.chunks_exact(self.chunk_size);
+        for chunk in &mut chunks {
+            self.process_chunk(chunk, &mut events);
+        }
+        self.pending.extend_from_slice(chunks.remainder());
+
+        events
+    }
+
+    /// Score any buffered partial chunk (padded with silence) and end the
+    /// current speech, returning the resulting events. The processor can be
+    /// reused afterwards; offsets keep counting.
+    pub fn flush(&mut self) -> Vec<SpeechEvent> {
+        let mut events = Vec::new();
+        self.process_pending(&mut events);
+
+        if self.is_speaking {
+            self.is_speaking = false;
+            if self.current_segment.len() >= self.min_speech_samples {
+                events.push(SpeechEvent::SpeechEnd(SpeechSegment::trimmed(
+                    &self.current_segment,
+                    self.segment_start,
+                    self.trim_threshold,
+                )));
+            } else {
+                events.push(SpeechEvent::SpeechDiscarded);
+            }
+        }
+        self.current_segment.clear();
+        self.silence_counter = 0;
+
+        events
+    }
+
+    /// Process audio samples and extract speech segments. Segment offsets
+    /// count from the first sample passed to this processor. A trailing
+    /// partial chunk is padded with silence and scored immediately.
     ///
     /// # Errors
     ///
     /// Returns an error if the VAD processing fails.
-    pub fn process_audio(&mut self, samples: &[f32]) -> Result<Vec<Vec<f32>>> {
-        let mut speech_segments = Vec::new();
+    pub fn process_audio(&mut self, samples: &[f32]) -> Result<Vec<SpeechSegment>> {
         debug!("Processing {} samples with VAD", samples.len());
+        // e.g. a tap so short nothing was captured; there is no RMS to log
+        if samples.is_empty() {
+            return Ok(Vec::new());
+        }
 
         #[allow(clippy::cast_precision_loss)]
         let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
         debug!("Audio RMS level: {:.6}", rms);
 
-        for (chunk_idx, chunk) in samples.chunks(512).enumerate() {
-            let mut chunk_vec = chunk.to_vec();
-            if chunk_vec.len() < 512 {
-                chunk_vec.resize(512, 0.0);
-            }
-
-            let probability = self.detector.predict(chunk_vec.clone());
-
-            let is_speech = probability > 0.5;
-
-            if chunk_idx % 10 == 0 {
-                debug!(
-                    "Chunk {}: probability = {:.3}, is_speech = {}",
-                    chunk_idx, probability, is_speech
-                );
-            }
+        let mut events = self.push(samples);
+        self.process_pending(&mut events);
 
-            match (self.is_speaking, is_speech) {
-                (false, true) => {
-                    self.is_speaking = true;
-                    self.silence_counter = 0;
-                    self.current_segment.extend_from_slice(chunk);
-                }
-                (true, true) => {
-                    self.silence_counter = 0;
-                    self.current_segment.extend_from_slice(chunk);
-                }
-                (true, false) => {
-                    self.silence_counter += 1;
-                    self.current_segment.extend_from_slice(chunk);
-
-                    if self.silence_counter >= self.hangover_frames {
-                        self.is_speaking = false;
-
-                        if self.current_segment.len() >= self.min_speech_samples {
-                            let segment = Self::trim_silence_static(&self.current_segment);
-                            if !segment.is_empty() {
-                                speech_segments.push(segment);
-                            }
-                        }
-
-                        self.current_segment.clear();
-                        self.silence_counter = 0;
-                    }
-                }
-                (false, false) => {
-                    self.silence_counter = 0;
-                }
-            }
-        }
+        let speech_segments: Vec<_> = events
+            .into_iter()
+            .filter_map(|event| match event {
+                SpeechEvent::SpeechEnd(segment) => Some(segment),
+                _ => None,
+            })
+            .collect();
 
         debug!(
             "VAD processing complete: found {} speech segments",
@@ -114,36 +284,83 @@ impl VadProcessor {
 
     /// Get any remaining speech segment (call when recording stops)
     #[must_use]
-    pub fn finish(self) -> Option<Vec<f32>> {
-        if self.is_speaking && self.current_segment.len() >= self.min_speech_samples {
-            let segment = self.current_segment;
-            Some(Self::trim_silence_static(&segment))
-        } else {
-            None
+    pub fn finish(mut self) -> Option<SpeechSegment> {
+        self.flush().into_iter().find_map(|event| match event {
+            SpeechEvent::SpeechEnd(segment) => Some(segment),
+            _ => None,
+        })
+    }
+
+    /// Score the buffered partial chunk, padded with silence
+    fn process_pending(&mut self, events: &mut Vec<SpeechEvent>) {
+        if !self.pending.is_empty() {
+            let chunk = std::mem::take(&mut self.pending);
+            self.process_chunk(&chunk, events);
         }
     }
 
-    /// Trim silence from the beginning and end of a segment (static version)
-    fn trim_silence_static(segment: &[f32]) -> Vec<f32> {
-        const SILENCE_THRESHOLD: f32 = 0.01;
+    /// Score one chunk of at most `chunk_size` samples and advance the speech
+    /// state machine
+    fn process_chunk(&mut self, chunk: &[f32], events: &mut Vec<SpeechEvent>) {
+        let mut chunk_vec = chunk.to_vec();
+        if chunk_vec.len() < self.chunk_size {
+            chunk_vec.resize(self.chunk_size, 0.0);
+        }
 
-        let start = segment.iter().position(|&s| s.abs() > SILENCE_THRESHOLD).unwrap_or(0);
+        let probability = self.detector.predict(chunk_vec);
 
-        let end = segment
-            .iter()
-            .rposition(|&s| s.abs() > SILENCE_THRESHOLD)
-            .map_or(segment.len(), |pos| pos + 1);
+        let is_speech = probability > self.threshold;
 
-        if start < end {
-            segment[start..end].to_vec()
-        } else {
-            Vec::new()
+        let chunk_idx = self.processed_samples / self.chunk_size;
+        if chunk_idx.is_multiple_of(10) {
+            debug!(
+                "Chunk {}: probability = {:.3}, is_speech = {}",
+                chunk_idx, probability, is_speech
+            );
         }
+
+        match (self.is_speaking, is_speech) {
+            (false, true) => {
+                self.is_speaking = true;
+                self.silence_counter = 0;
+                self.segment_start = self.processed_samples;
+                self.current_segment.extend_from_slice(chunk);
+                events.push(SpeechEvent::SpeechStart {
+                    start_sample: self.segment_start,
+                });
+            }
+            (true, true) => {
+                self.silence_counter = 0;
+                self.current_segment.extend_from_slice(chunk);
+            }
+            (true, false) => {
+                self.silence_counter += 1;
+                self.current_segment.extend_from_slice(chunk);
+
+                if self.silence_counter >= self.hangover_frames {
+                    self.is_speaking = false;
+
+                    let segment = (self.current_segment.len() >= self.min_speech_samples)
+                        .then(|| SpeechSegment::trimmed(&self.current_segment, self.segment_start, self.trim_threshold))
+                        .filter(|segment| !segment.samples.is_empty());
+                    events.push(segment.map_or(SpeechEvent::SpeechDiscarded, SpeechEvent::SpeechEnd));
+
+                    self.current_segment.clear();
+                    self.silence_counter = 0;
+                }
+            }
+            (false, false) => {
+                self.silence_counter = 0;
+            }
+        }
+        self.processed_samples += chunk.len();
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use echoes_config::DEFAULT_VAD_CHUNK_SIZE;
+
     use super::*;
 
     #[test]
@@ -163,4 +380,93 @@ mod tests {
         assert!(segments.is_empty(), "Should not detect speech in silence");
         Ok(())
     }
+
+    #[test]
+    fn test_empty_input() -> Result<()> {
+        let mut vad = VadProcessor::new()?;
+
+        assert!(vad.process_audio(&[])?.is_empty());
+        assert!(vad.finish().is_none());
+        assert!(trim_silence(&[], DEFAULT_SILENCE_THRESHOLD).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_segments_closer_than_gap() {
+        let audio: Vec<f32> = (0..1000u16).map(|i| f32::from(i) / 1000.0).collect();
+        let segment = |range: Range<usize>| SpeechSegment {
+            samples: audio[range.clone()].to_vec(),
+            start_sample: range.start,
+            end_sample: range.end,
+        };
+        let segments = vec![segment(100..200), segment(300..400), segment(800..900)];
+
+        // 100 samples apart, then 400: only the first two are merged
+        let merged = merge_segments(segments.clone(), 200, &audio);
+        assert_eq!(merged, vec![segment(100..400), segment(800..900)]);
+
+        assert_eq!(merge_segments(segments.clone(), 100, &audio), segments);
+        assert_eq!(merge_segments(segments.clone(), 0, &audio), segments);
+    }
+
+    #[test]
+    fn test_lower_trim_threshold_keeps_soft_onset() -> Result<()> {
+        let samples = [0.0, 0.005, 0.4, 0.3, 0.0];
+
+        let vad = VadProcessor::new()?;
+        let trimmed = SpeechSegment::trimmed(&samples, 100, vad.trim_threshold);
+        assert_eq!(trimmed.samples, [0.4, 0.3]);
+        assert_eq!(trimmed.start_sample, 102);
+
+        let config = VadConfig {
+            trim_threshold: 0.001,
+            ..VadConfig::default()
+        };
+        let vad = VadProcessor::with_config(&config)?;
+        let trimmed = SpeechSegment::trimmed(&samples, 100, vad.trim_threshold);
+        assert_eq!(trimmed.samples, [0.005, 0.4, 0.3]);
+        assert_eq!(trimmed.start_sample, 101);
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_buffers_partial_chunks() -> Result<()> {
+        let mut vad = VadProcessor::new()?;
+
+        assert!(vad.push(&[0.0; 100]).is_empty());
+        assert_eq!(vad.pending.len(), 100);
+        assert_eq!(vad.processed_samples, 0);
+
+        assert!(vad.push(&[0.0; DEFAULT_VAD_CHUNK_SIZE]).is_empty());
+        assert_eq!(vad.pending.len(), 100);
+        assert_eq!(vad.processed_samples, DEFAULT_VAD_CHUNK_SIZE);
+
+        assert!(vad.flush().is_empty());
+        assert!(vad.pending.is_empty());
+        assert_eq!(vad.processed_samples, DEFAULT_VAD_CHUNK_SIZE + 100);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_chunk_size_rejected() -> Result<()> {
+        // 256 samples is only a supported chunk at 8kHz
+        assert!(validate_chunk_size(256, 8000).is_ok());
+        let config = VadConfig {
+            chunk_size: 256,
+            ..VadConfig::default()
+        };
+        assert!(matches!(
+            VadProcessor::with_config(&config),
+            Err(AudioError::UnsupportedVadChunkSize(256, VAD_SAMPLE_RATE))
+        ));
+
+        let config = VadConfig {
+            chunk_size: 1024,
+            ..VadConfig::default()
+        };
+        let mut vad = VadProcessor::with_config(&config)?;
+        vad.push(&[0.0; 1024]);
+        assert_eq!(vad.processed_samples, 1024);
+        Ok(())
+    }
 }
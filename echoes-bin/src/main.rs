@@ -1,6 +1,128 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, ValueEnum};
+use echoes_config::{Config, ConfigError};
 use echoes_core::run;
+use echoes_stt::{ConfiguredStt, SttProvider, Transcript, subtitles};
+
+/// Echoes dictation app
+#[derive(Parser)]
+#[command(name = "echoes", version, about)]
+struct Cli {
+    /// Validate the config file and exit without opening the UI
+    #[arg(long)]
+    check_config: bool,
+
+    /// Config file to check instead of the default one
+    #[arg(long, value_name = "PATH", requires = "check_config")]
+    config: Option<PathBuf>,
+
+    /// Transcribe a WAV file with the configured provider, print the
+    /// transcript and exit without opening the UI
+    #[arg(long, value_name = "PATH", conflicts_with = "check_config")]
+    transcribe: Option<PathBuf>,
+
+    /// How `--transcribe` prints the transcript
+    #[arg(long, value_enum, default_value_t, requires = "transcribe")]
+    format: OutputFormat,
+}
+
+/// Output formats of `--transcribe`
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum OutputFormat {
+    /// The plain text
+    #[default]
+    Text,
+    /// The text, detected language, duration and timed segments
+    Json,
+    /// `SubRip` subtitles of the timed segments
+    Srt,
+    /// `WebVTT` subtitles of the timed segments
+    Vtt,
+}
+
+impl OutputFormat {
+    fn render(self, transcript: &Transcript) -> anyhow::Result<String> {
+        Ok(match self {
+            Self::Text => transcript.text.clone(),
+            Self::Json => serde_json::to_string_pretty(transcript)?,
+            Self::Srt => subtitles::to_srt(&transcript.segments),
+            Self::Vtt => subtitles::to_vtt(&transcript.segments),
+        })
+    }
+}
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    run().await.map_err(|e| anyhow::anyhow!("{}", e))
+async fn main() -> anyhow::Result<ExitCode> {
+    let cli = Cli::parse();
+
+    if cli.check_config {
+        return Ok(check_config(cli.config));
+    }
+    if let Some(path) = cli.transcribe {
+        return Ok(transcribe_file(&path, cli.format).await);
+    }
+
+    run().await.map_err(|e| anyhow::anyhow!("{}", e))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Loads and validates a config file without side effects, printing the
+/// outcome
+fn check_config(path: Option<PathBuf>) -> ExitCode {
+    let path = match path.map_or_else(Config::config_path, Ok) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = Config::load_from_path(&path).and_then(|config| {
+        echoes_config::load_custom_conflicts(&config.custom_conflict_rules);
+        if let Some(mismatch) = config.post_processing.model_mismatch() {
+            eprintln!("{}: warning: {mismatch}", path.display());
+        }
+        if let Some(issue) = config.task_issue() {
+            eprintln!("{}: warning: {issue}", path.display());
+        }
+        config.validate()?;
+        // The config crate can't see the languages local Whisper supports
+        echoes_stt::validate_language(&config.local_whisper.language)
+            .map_err(|e| ConfigError::ValidationError(format!("local_whisper.language: {e}")))
+    });
+    match result {
+        Ok(()) => {
+            println!("{}: OK", path.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}: {e}", path.display());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Transcribes a WAV file and prints the transcript to stdout in `format`
+async fn transcribe_file(path: &Path, format: OutputFormat) -> ExitCode {
+    let result = async {
+        let config = Config::load()?;
+        let transcript = ConfiguredStt::fallback_chain(&config).transcribe_file(path).await?;
+        format.render(&transcript)
+    }
+    .await;
+
+    match result {
+        Ok(output) => {
+            println!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Transcription failed: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
 }
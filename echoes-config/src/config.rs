@@ -1,48 +1,504 @@
 //! Main configuration structures and management
 
-use std::path::PathBuf;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 
-use crate::{shortcuts::RecordingShortcut, ConfigError, Result};
+use crate::{
+    conflict::{
+        load_custom_conflicts, register_custom_conflict, same_key_combination, ConflictInfo, CustomConflictRule,
+    },
+    secrets::{load_from_keychain, remove_from_keychain, store_in_keychain, ApiKeyProvider, KEYCHAIN_REFERENCE},
+    shortcuts::RecordingShortcut,
+    watch::remember_content,
+    ConfigError, Result,
+};
+
+/// Environment variable naming a directory to keep `config.toml` in instead
+/// of the platform config directory, e.g. for portable installs or tests
+pub const CONFIG_DIR_ENV: &str = "ECHOES_CONFIG_DIR";
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     pub stt_provider: SttProvider,
 
+    /// Providers tried in order when `stt_provider` fails, e.g. a cloud
+    /// provider behind local Whisper
+    #[serde(default)]
+    pub fallback_stt_providers: Vec<SttProvider>,
+
     pub openai_api_key: Option<String>,
     pub groq_api_key: Option<String>,
+    /// Used for post-processing with Gemini
+    #[serde(default)]
+    pub gemini_api_key: Option<String>,
+
+    /// Keep API keys in the config file instead of the OS keychain
+    #[serde(default)]
+    pub plaintext_api_keys: bool,
 
     pub openai_base_url: Option<String>,
     pub groq_base_url: Option<String>,
+    /// Headers sent with every request to `openai_base_url`, e.g. for API
+    /// gateways in front of a self-hosted OpenAI-compatible server
+    #[serde(default)]
+    pub openai_extra_headers: Vec<(String, String)>,
 
     pub openai_stt_model: Option<String>,
     pub openai_stt_prompt: Option<String>,
     pub groq_stt_model: Option<String>,
     pub groq_stt_prompt: Option<String>,
 
+    /// Whether providers transcribe speech as spoken or translate it to
+    /// English
+    #[serde(default)]
+    pub task: TranscriptionTask,
+
+    /// Retries of cloud STT requests that were rate limited or hit a server
+    /// error
+    #[serde(default)]
+    pub stt_retry: RetryConfig,
+
     pub local_whisper: LocalWhisperConfig,
 
     pub recording_shortcut: RecordingShortcut,
 
+    /// Shortcut that aborts any recording in progress, e.g. when it got stuck
+    #[serde(default)]
+    pub cancel_shortcut: Option<RecordingShortcut>,
+
+    /// Shortcut that outputs the most recent transcript again, e.g. after it
+    /// landed in the wrong window
+    #[serde(default)]
+    pub retype_last_shortcut: Option<RecordingShortcut>,
+
+    /// Tidy transcripts without an LLM: collapse spaces, capitalize the
+    /// first letter and add a final period. Runs before post-processing.
+    #[serde(default)]
+    pub basic_cleanup: bool,
+
     pub post_processing: PostProcessingConfig,
+
+    #[serde(default)]
+    pub output_method: OutputMethod,
+
+    /// Whether output should reliably replace selected text
+    #[serde(default)]
+    pub output_placement: OutputPlacement,
+
+    /// Put the previous clipboard text back after pasting output. It is
+    /// always restored when pasting fails.
+    #[serde(default = "default_true")]
+    pub restore_clipboard: bool,
+
+    /// Longest wait, in milliseconds, for the app being dictated into to get
+    /// focus back before output. Where focus can't be queried this is a
+    /// fixed delay.
+    #[serde(default = "default_type_delay_ms")]
+    pub type_delay_ms: u64,
+
+    /// Pause in milliseconds between typed characters, for apps that drop
+    /// keystrokes arriving too fast; 0 types the text at once
+    #[serde(default)]
+    pub type_char_delay_ms: u64,
+
+    /// Post a desktop notification with a preview of each transcript while
+    /// the main window isn't focused
+    #[serde(default)]
+    pub notify_on_complete: bool,
+
+    /// Post a desktop notification when a recording contained no speech
+    #[serde(default)]
+    pub notify_on_no_speech: bool,
+
+    /// Don't output a transcript identical to the previous one finished
+    /// within `duplicate_window_secs`, e.g. after the shortcut fired twice
+    #[serde(default)]
+    pub suppress_duplicates: bool,
+
+    /// Seconds after a transcript during which an identical one counts as a
+    /// duplicate
+    #[serde(default = "default_duplicate_window_secs")]
+    pub duplicate_window_secs: u64,
+
+    /// Recordings where VAD found speech in less than this fraction
+    /// (0.0-1.0) of the audio are reported as containing no speech instead of
+    /// being transcribed
+    #[serde(default = "default_min_speech_ratio")]
+    pub min_speech_ratio: f32,
+
+    /// Play a short beep when recording starts
+    #[serde(default)]
+    pub sound_on_start: bool,
+    /// Play a short beep when recording stops
+    #[serde(default)]
+    pub sound_on_stop: bool,
+
+    /// Record shortcuts by physical key position (scan code) rather than by
+    /// the character the layout assigns to the key
+    #[serde(default)]
+    pub match_scan_codes: bool,
+
+    /// In hold mode, releases shorter than this discard the recording
+    #[serde(default)]
+    pub min_hold_ms: u64,
+
+    /// Keep the key press that triggers the recording shortcut from reaching
+    /// the focused app. Uses event grabbing, which only macOS and Windows
+    /// support; elsewhere the key is still delivered. Applied on restart.
+    #[serde(default)]
+    pub consume_trigger: bool,
+
+    /// Save the WAV of each recording and its speech segments
+    #[serde(default = "default_true")]
+    pub save_recordings: bool,
+
+    /// Save the full recording when `save_recordings` is on
+    #[serde(default = "default_true")]
+    pub save_raw: bool,
+
+    /// Save the speech segments VAD found when `save_recordings` is on
+    #[serde(default = "default_true")]
+    pub save_segments: bool,
+
+    /// Directory recordings are saved in; `recordings` in the data directory
+    /// when unset. Relative paths are taken relative to the data directory.
+    #[serde(default)]
+    pub recording_output_dir: Option<PathBuf>,
+
+    /// File name of saved recordings, without extension. `{date}`, `{time}`
+    /// and `{kind}` (`raw` or `segment_<n>`) are replaced.
+    #[serde(default = "default_recording_filename_template")]
+    pub recording_filename_template: String,
+
+    /// Transcribe speech segments as VAD detects them during recording instead
+    /// of transcribing the whole recording after it stops
+    #[serde(default)]
+    pub streaming_transcription: bool,
+
+    /// Transcribe each VAD speech segment of a long recording separately and
+    /// join their text, instead of transcribing the whole recording at once
+    #[serde(default)]
+    pub segment_transcription: bool,
+
+    /// Gain in dB applied to the recording before transcription
+    #[serde(default)]
+    pub input_gain_db: f32,
+
+    /// How eagerly voice activity detection treats audio as speech
+    #[serde(default)]
+    pub vad_sensitivity: VadSensitivity,
+
+    /// Milliseconds of silence appended to each speech segment before it is
+    /// transcribed, so Whisper doesn't drop a final word cut off abruptly;
+    /// 0 disables it
+    #[serde(default)]
+    pub segment_tail_pad_ms: u32,
+
+    /// Normalize recordings so their loudest sample reaches this level in
+    /// dBFS (e.g. -3.0); overrides `input_gain_db`
+    #[serde(default)]
+    pub normalize_peak_dbfs: Option<f32>,
+
+    #[serde(default)]
+    pub custom_conflict_rules: Vec<CustomConflictRule>,
+
+    /// Named setups to switch between, e.g. for dictating prose or code
+    /// comments
+    #[serde(default)]
+    pub profiles: Vec<ShortcutProfile>,
+
+    /// Name of the profile last switched to, whose settings are applied
+    #[serde(default)]
+    pub active_profile: Option<String>,
+
+    /// `[target, level]` pairs raising or lowering the log level of single
+    /// crates, e.g. `["echoes_audio", "trace"]`. `RUST_LOG` overrides them.
+    #[serde(default)]
+    pub log_directives: Vec<(String, String)>,
+
+    #[serde(default)]
+    pub theme: Theme,
+
+    /// Start with the main window hidden in the tray, or minimized when there
+    /// is no tray icon
+    #[serde(default)]
+    pub start_minimized: bool,
+
+    /// Register the app to start when the user logs in
+    #[serde(default)]
+    pub launch_at_login: bool,
+
+    /// Unix socket, or named pipe on Windows, on which scripts can send
+    /// commands such as starting a recording; `None` disables it. Read at
+    /// startup.
+    #[serde(default)]
+    pub control_socket: Option<PathBuf>,
+
+    /// Keep the main window above other windows while recording, so the
+    /// status stays visible when dictating into another app
+    #[serde(default)]
+    pub always_on_top_while_recording: bool,
+
+    /// Show a small floating window with the elapsed time and input level
+    /// while recording
+    #[serde(default)]
+    pub recording_overlay: bool,
+
+    /// Main window geometry from the last session
+    #[serde(default)]
+    pub window: Option<WindowState>,
+
+    /// File values of API keys replaced by environment overrides, restored on
+    /// save so overrides are never persisted
+    #[serde(skip)]
+    env_overrides: HashMap<ApiKeyProvider, Option<String>>,
+
+    /// Keys referenced in the config file that couldn't be read from the
+    /// keychain, e.g. while it is locked. Their reference is written back on
+    /// save instead of deleting the stored key.
+    #[serde(skip)]
+    unavailable_api_keys: HashSet<ApiKeyProvider>,
 }
 
 /// Available STT providers
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum SttProvider {
     OpenAI,
     Groq,
     LocalWhisper,
 }
 
+/// How transcribed text is delivered to the focused application
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum OutputMethod {
+    /// Simulate typing character by character
+    #[default]
+    Type,
+    /// Put the text on the clipboard and send the platform paste shortcut
+    Paste,
+}
+
+/// How output treats text selected in the focused application
+///
+/// Pasting replaces a selection in every application. Synthesized typing
+/// replaces it in most, but some (terminals, some X11 and Electron apps)
+/// ignore the selection and insert the typed text next to it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum OutputPlacement {
+    /// Deliver the text with the output method as is; a selection is replaced
+    /// wherever the application replaces selections on input
+    #[default]
+    Insert,
+    /// Always paste, even with the `Type` output method, so a selection is
+    /// reliably replaced. With nothing selected, the text is inserted at the
+    /// cursor.
+    ReplaceSelection,
+}
+
+/// UI color scheme
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum Theme {
+    /// Follow the operating system preference
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+/// Main window size and position in logical points
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct WindowState {
+    pub width: f32,
+    pub height: f32,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Retry behaviour for cloud STT requests
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Total attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each further retry
+    pub base_delay_ms: u64,
+    /// Upper bound of the random delay added to each retry
+    pub jitter_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 500,
+            jitter_ms: 250,
+        }
+    }
+}
+
+/// Amplitude below which samples at the edges of a speech segment are
+/// trimmed as silence
+pub const DEFAULT_TRIM_THRESHOLD: f32 = 0.01;
+
+/// Samples the VAD scores at a time by default, 32ms at 16kHz
+pub const DEFAULT_VAD_CHUNK_SIZE: usize = 512;
+
+/// Voice activity detection tuning
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct VadConfig {
+    /// Speech probability (0.0-1.0) above which a chunk counts as speech
+    pub threshold: f32,
+    /// Silent chunks to wait before a speech segment ends
+    pub hangover_frames: usize,
+    /// Shortest speech segment kept, in samples at 16kHz
+    pub min_speech_samples: usize,
+    /// Segments separated by a pause shorter than this many milliseconds are
+    /// merged into one, so pauses between words don't split an utterance;
+    /// 0 never merges
+    #[serde(default)]
+    pub merge_gap_ms: u32,
+    /// Amplitude (0.0-1.0) below which samples at the start and end of a
+    /// segment are trimmed as silence; lower it if soft speech onsets are
+    /// clipped
+    #[serde(default = "default_trim_threshold")]
+    pub trim_threshold: f32,
+    /// Samples at 16kHz scored at a time: 512, 1024 or 1536. Smaller chunks
+    /// react sooner, larger ones detect more smoothly; `hangover_frames`
+    /// counts these chunks.
+    #[serde(default = "default_vad_chunk_size")]
+    pub chunk_size: usize,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        VadSensitivity::Medium.vad_config()
+    }
+}
+
+/// Voice activity detection presets
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum VadSensitivity {
+    /// Only clear speech counts; suits noisy rooms
+    Low,
+    #[default]
+    Medium,
+    /// Quiet speech counts too; suits quiet rooms
+    High,
+    Custom(VadConfig),
+}
+
+impl VadSensitivity {
+    /// The VAD tuning this preset stands for
+    #[must_use]
+    pub const fn vad_config(self) -> VadConfig {
+        match self {
+            Self::Low => VadConfig {
+                threshold: 0.7,
+                hangover_frames: 8,
+                min_speech_samples: 6400,
+                merge_gap_ms: 0,
+                trim_threshold: DEFAULT_TRIM_THRESHOLD,
+                chunk_size: DEFAULT_VAD_CHUNK_SIZE,
+            },
+            Self::Medium => VadConfig {
+                threshold: 0.5,
+                hangover_frames: 10,
+                min_speech_samples: 4800,
+                merge_gap_ms: 0,
+                trim_threshold: DEFAULT_TRIM_THRESHOLD,
+                chunk_size: DEFAULT_VAD_CHUNK_SIZE,
+            },
+            Self::High => VadConfig {
+                threshold: 0.3,
+                hangover_frames: 15,
+                min_speech_samples: 3200,
+                merge_gap_ms: 0,
+                trim_threshold: DEFAULT_TRIM_THRESHOLD,
+                chunk_size: DEFAULT_VAD_CHUNK_SIZE,
+            },
+            Self::Custom(config) => config,
+        }
+    }
+}
+
 /// Local Whisper configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalWhisperConfig {
     pub model: WhisperModel,
     pub model_path: Option<PathBuf>,
+    #[serde(default)]
+    pub params: WhisperParams,
+    /// Run inference on the GPU when the app was built with the `metal`,
+    /// `cuda` or `vulkan` feature of `echoes-stt`; falls back to the CPU if
+    /// the GPU can't be initialized
+    #[serde(default = "default_true")]
+    pub use_gpu: bool,
+    /// Run the encoder on the Apple Neural Engine (macOS). Needs the
+    /// `coreml` feature of `echoes-stt` and a `<model>-encoder.mlmodelc` next
+    /// to the model file; a `coreml` build uses that file whenever present.
+    #[serde(default)]
+    pub use_coreml: bool,
+    /// Code of the spoken language, e.g. `en`, or `auto` to let Whisper
+    /// detect it. Only multilingual models can transcribe other languages.
+    #[serde(default = "default_whisper_language")]
+    pub language: String,
+}
+
+/// Decoding settings for local Whisper
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct WhisperParams {
+    pub sampling: WhisperSampling,
+    /// Sampling temperature; 0.0 always picks the most likely token
+    pub temperature: f32,
+    /// Don't feed the text of earlier windows back in as a prompt
+    pub no_context: bool,
+    /// CPU threads used for inference; whisper.cpp picks when unset
+    pub threads: Option<u32>,
+}
+
+impl Default for WhisperParams {
+    fn default() -> Self {
+        Self {
+            sampling: WhisperSampling::default(),
+            temperature: 0.0,
+            no_context: true,
+            threads: None,
+        }
+    }
+}
+
+/// How local Whisper chooses tokens
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum WhisperSampling {
+    /// Keep the best of `best_of` greedy decodings; fastest
+    Greedy { best_of: u32 },
+    /// Beam search over `beam_size` candidates; slower but more accurate
+    BeamSearch { beam_size: u32 },
+}
+
+impl Default for WhisperSampling {
+    fn default() -> Self {
+        Self::Greedy { best_of: 1 }
+    }
+}
+
+/// What STT providers produce from the recorded speech
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TranscriptionTask {
+    /// Text in the language that was spoken
+    #[default]
+    Transcribe,
+    /// English text, whatever language was spoken. Whisper only translates
+    /// into English, so there is no target language to choose.
+    Translate,
 }
 
 /// Available Whisper models
@@ -61,6 +517,29 @@ pub enum WhisperModel {
     LargeV3,
 }
 
+impl WhisperModel {
+    /// Every model, smallest first
+    pub const ALL: [Self; 11] = [
+        Self::Tiny,
+        Self::TinyEn,
+        Self::Base,
+        Self::BaseEn,
+        Self::Small,
+        Self::SmallEn,
+        Self::Medium,
+        Self::MediumEn,
+        Self::LargeV1,
+        Self::LargeV2,
+        Self::LargeV3,
+    ];
+
+    /// Whether the model only knows English (the `.en` models)
+    #[must_use]
+    pub const fn is_english_only(&self) -> bool {
+        matches!(self, Self::TinyEn | Self::BaseEn | Self::SmallEn | Self::MediumEn)
+    }
+}
+
 /// Post-processing configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PostProcessingConfig {
@@ -70,31 +549,120 @@ pub struct PostProcessingConfig {
     pub prompt: String,
 }
 
+/// A named recording shortcut and post-processing prompt, applied together
+/// with [`Config::apply_profile`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShortcutProfile {
+    pub name: String,
+    pub recording_shortcut: RecordingShortcut,
+    /// Replaces `post_processing.prompt` while the profile is active
+    pub post_processing_prompt: String,
+}
+
 /// Available LLM providers for post-processing
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum LlmProvider {
     OpenAI,
     Groq,
     Gemini,
 }
 
+impl LlmProvider {
+    /// The provider a model name is specific to, if it follows a naming
+    /// scheme only one provider uses
+    fn owning_model(model: &str) -> Option<Self> {
+        let model = model.trim().to_ascii_lowercase();
+        if model.starts_with("gemini") {
+            Some(Self::Gemini)
+        } else if model.starts_with("gpt-") || model.starts_with("chatgpt") {
+            Some(Self::OpenAI)
+        } else {
+            None
+        }
+    }
+}
+
+impl PostProcessingConfig {
+    /// Describes why `model` is unlikely to work with `provider`, e.g. a
+    /// `gpt-*` model with Gemini
+    #[must_use]
+    pub fn model_mismatch(&self) -> Option<String> {
+        if self.model.trim().is_empty() {
+            return Some(format!("No post-processing model set for {:?}", self.provider));
+        }
+        LlmProvider::owning_model(&self.model)
+            .filter(|owner| *owner != self.provider)
+            .map(|owner| {
+                format!(
+                    "Post-processing model '{}' looks like a {owner:?} model but the provider is {:?}",
+                    self.model, self.provider
+                )
+            })
+    }
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+const fn default_trim_threshold() -> f32 {
+    DEFAULT_TRIM_THRESHOLD
+}
+
+const fn default_vad_chunk_size() -> usize {
+    DEFAULT_VAD_CHUNK_SIZE
+}
+
+fn default_whisper_language() -> String {
+    "en".into()
+}
+
+const fn default_type_delay_ms() -> u64 {
+    100
+}
+
+const fn default_duplicate_window_secs() -> u64 {
+    10
+}
+
+const fn default_min_speech_ratio() -> f32 {
+    0.02
+}
+
+fn default_recording_filename_template() -> String {
+    "recording_{date}_{time}_{kind}".into()
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             stt_provider: SttProvider::OpenAI,
+            fallback_stt_providers: Vec::new(),
             openai_api_key: None,
             groq_api_key: None,
+            gemini_api_key: None,
+            plaintext_api_keys: false,
             openai_base_url: Some("https://api.openai.com/v1".into()),
             groq_base_url: Some("https://api.groq.com/openai/v1".into()),
+            openai_extra_headers: Vec::new(),
             openai_stt_model: Some("whisper-1".into()),
             openai_stt_prompt: None,
             groq_stt_model: Some("whisper-large-v3".into()),
             groq_stt_prompt: None,
+            task: TranscriptionTask::default(),
+            stt_retry: RetryConfig::default(),
             local_whisper: LocalWhisperConfig {
                 model: WhisperModel::Base,
                 model_path: None,
+                params: WhisperParams::default(),
+                use_gpu: true,
+                use_coreml: false,
+                language: default_whisper_language(),
             },
             recording_shortcut: RecordingShortcut::default(),
+            cancel_shortcut: None,
+            retype_last_shortcut: None,
+            basic_cleanup: false,
             post_processing: PostProcessingConfig {
                 enabled: false,
                 provider: LlmProvider::OpenAI,
@@ -103,12 +671,54 @@ impl Default for Config {
                          the original meaning:\n\n{transcript}"
                     .into(),
             },
+            output_method: OutputMethod::default(),
+            output_placement: OutputPlacement::default(),
+            restore_clipboard: true,
+            type_delay_ms: default_type_delay_ms(),
+            type_char_delay_ms: 0,
+            notify_on_complete: false,
+            notify_on_no_speech: false,
+            suppress_duplicates: false,
+            duplicate_window_secs: default_duplicate_window_secs(),
+            min_speech_ratio: default_min_speech_ratio(),
+            sound_on_start: false,
+            sound_on_stop: false,
+            match_scan_codes: false,
+            min_hold_ms: 0,
+            consume_trigger: false,
+            save_recordings: true,
+            save_raw: true,
+            save_segments: true,
+            recording_output_dir: None,
+            recording_filename_template: default_recording_filename_template(),
+            streaming_transcription: false,
+            segment_transcription: false,
+            input_gain_db: 0.0,
+            normalize_peak_dbfs: None,
+            vad_sensitivity: VadSensitivity::default(),
+            segment_tail_pad_ms: 0,
+            custom_conflict_rules: Vec::new(),
+            profiles: Vec::new(),
+            active_profile: None,
+            log_directives: Vec::new(),
+            theme: Theme::default(),
+            start_minimized: false,
+            launch_at_login: false,
+            control_socket: None,
+            always_on_top_while_recording: false,
+            recording_overlay: false,
+            window: None,
+            env_overrides: HashMap::new(),
+            unavailable_api_keys: HashSet::new(),
         }
     }
 }
 
 impl Config {
-    /// Load configuration from file or create default
+    /// Load configuration from file or create default.
+    ///
+    /// API keys from the `ECHOES_*_API_KEY` environment variables take
+    /// precedence over the file; see [`Config::apply_env_overrides`].
     ///
     /// # Errors
     ///
@@ -120,17 +730,44 @@ impl Config {
         if config_path.exists() {
             let content = std::fs::read_to_string(&config_path)
                 .map_err(|e| ConfigError::LoadFailed(format!("Failed to read config file: {e}")))?;
-            let config: Self =
+            let mut config: Self =
                 toml::from_str(&content).map_err(|e| ConfigError::ParseError(format!("Invalid config format: {e}")))?;
+            remember_content(&content);
+            load_custom_conflicts(&config.custom_conflict_rules);
+
+            // Move any plaintext keys into the keychain. Without a keychain the
+            // file stays as it is, and a failed rewrite keeps the loaded config.
+            if config.resolve_api_keys() && config.migrate_api_keys() {
+                if let Err(e) = config.save() {
+                    tracing::warn!("Failed to save config after moving API keys to the keychain: {e}");
+                }
+            }
+            config.apply_env_overrides();
             Ok(config)
         } else {
-            let config = Self::default();
+            let mut config = Self::default();
             config.save()?;
+            config.apply_env_overrides();
             Ok(config)
         }
     }
 
-    /// Save configuration to file
+    /// Read and parse the config file at `path` without creating, migrating or
+    /// saving anything, e.g. to lint a config before deploying it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load_from_path(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::LoadFailed(format!("Failed to read {}: {e}", path.display())))?;
+        toml::from_str(&content).map_err(|e| ConfigError::ParseError(format!("Invalid config format: {e}")))
+    }
+
+    /// Save configuration to file.
+    ///
+    /// The file is replaced atomically and the previous version is kept as
+    /// `config.toml.bak`.
     ///
     /// # Errors
     ///
@@ -138,18 +775,11 @@ impl Config {
     /// file cannot be written.
     pub fn save(&self) -> Result<()> {
         let config_path = Self::config_path()?;
-
-        if let Some(parent) = config_path.parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| ConfigError::SaveFailed(format!("Failed to create config directory: {e}")))?;
-        }
-
-        let content = toml::to_string_pretty(self)
+        let content = toml::to_string_pretty(&self.to_storage())
             .map_err(|e| ConfigError::SaveFailed(format!("Failed to serialize config: {e}")))?;
-        std::fs::write(&config_path, content)
-            .map_err(|e| ConfigError::SaveFailed(format!("Failed to write config file: {e}")))?;
+        remember_content(&content);
 
-        Ok(())
+        write_atomic(&config_path, &content)
     }
 
     /// Async version of save to avoid blocking the UI thread
@@ -160,35 +790,292 @@ impl Config {
     /// created, or file cannot be written.
     pub async fn save_async(&self) -> Result<()> {
         let config_path = Self::config_path()?;
-        let content = toml::to_string_pretty(self)
+        let content = toml::to_string_pretty(&self.to_storage())
             .map_err(|e| ConfigError::SaveFailed(format!("Failed to serialize config: {e}")))?;
+        remember_content(&content);
+
+        tokio::task::spawn_blocking(move || write_atomic(&config_path, &content))
+            .await
+            .map_err(|e| ConfigError::SaveFailed(format!("Task join error: {e}")))?
+    }
 
-        let config_path = config_path.clone();
-        let content = content.clone();
+    /// Restore the configuration from `config.toml.bak`, making it the active
+    /// configuration again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no backup, it cannot be parsed, or it cannot
+    /// be written back as the current config.
+    pub fn restore_backup() -> Result<Self> {
+        let config_path = Self::config_path()?;
+        let content = std::fs::read_to_string(backup_path(&config_path))
+            .map_err(|e| ConfigError::LoadFailed(format!("Failed to read config backup: {e}")))?;
+        let mut config: Self =
+            toml::from_str(&content).map_err(|e| ConfigError::ParseError(format!("Invalid config backup: {e}")))?;
+
+        remember_content(&content);
+        write_atomic(&config_path, &content)?;
 
-        tokio::task::spawn_blocking(move || {
-            if let Some(parent) = config_path.parent() {
-                std::fs::create_dir_all(parent)
-                    .map_err(|e| ConfigError::SaveFailed(format!("Failed to create config directory: {e}")))?;
+        config.resolve_api_keys();
+        load_custom_conflicts(&config.custom_conflict_rules);
+        config.apply_env_overrides();
+        Ok(config)
+    }
+
+    /// Export configuration to an arbitrary TOML file.
+    ///
+    /// API keys and `openai_extra_headers`, which often carry credentials,
+    /// are omitted unless `include_secrets` is set, in which case they are
+    /// written in plaintext. Environment overrides are never exported.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the config cannot be serialized or the file cannot be
+    /// written.
+    pub fn export_to(&self, path: &Path, include_secrets: bool) -> Result<()> {
+        let mut exported = self.clone();
+        for (provider, original) in &self.env_overrides {
+            exported.api_key_field_mut(*provider).clone_from(original);
+        }
+        if !include_secrets {
+            for provider in ApiKeyProvider::ALL {
+                *exported.api_key_field_mut(provider) = None;
             }
+            exported.openai_extra_headers.clear();
+        }
 
-            std::fs::write(&config_path, content)
-                .map_err(|e| ConfigError::SaveFailed(format!("Failed to write config file: {e}")))?;
+        let content = toml::to_string_pretty(&exported)
+            .map_err(|e| ConfigError::SaveFailed(format!("Failed to serialize config: {e}")))?;
+        std::fs::write(path, content)
+            .map_err(|e| ConfigError::SaveFailed(format!("Failed to write {}: {e}", path.display())))?;
 
-            Ok::<(), ConfigError>(())
+        Ok(())
+    }
+
+    /// Import configuration from an arbitrary TOML file.
+    ///
+    /// The imported config is validated but not saved; call [`Config::save`]
+    /// to make it the active configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or if the imported
+    /// configuration is invalid.
+    pub fn import_from(path: &Path) -> Result<Self> {
+        let mut config = Self::load_from_path(path)?;
+        config.resolve_api_keys();
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// STT providers in the order they are tried: `stt_provider`, then the
+    /// fallbacks not already listed
+    #[must_use]
+    pub fn stt_providers(&self) -> Vec<SttProvider> {
+        let mut providers = vec![self.stt_provider.clone()];
+        for provider in &self.fallback_stt_providers {
+            if !providers.contains(provider) {
+                providers.push(provider.clone());
+            }
+        }
+        providers
+    }
+
+    /// Describes why `task` can't be carried out as configured, e.g.
+    /// translating with an English-only Whisper model, which can only
+    /// transcribe English
+    #[must_use]
+    pub fn task_issue(&self) -> Option<String> {
+        let translates_locally = self.task == TranscriptionTask::Translate
+            && self.stt_providers().contains(&SttProvider::LocalWhisper)
+            && self.local_whisper.model_path.is_none()
+            && self.local_whisper.model.is_english_only();
+        translates_locally.then(|| {
+            format!(
+                "Whisper model {:?} is English-only and can't translate; choose a multilingual model",
+                self.local_whisper.model
+            )
         })
-        .await
-        .map_err(|e| ConfigError::SaveFailed(format!("Task join error: {e}")))?
     }
 
-    /// Get the configuration file path
-    fn config_path() -> Result<PathBuf> {
+    /// Get the API key for a provider, wherever it is stored
+    #[must_use]
+    pub fn get_api_key(&self, provider: ApiKeyProvider) -> Option<&str> {
+        match provider {
+            ApiKeyProvider::OpenAI => self.openai_api_key.as_deref(),
+            ApiKeyProvider::Groq => self.groq_api_key.as_deref(),
+            ApiKeyProvider::Gemini => self.gemini_api_key.as_deref(),
+        }
+    }
+
+    /// Set the API key for a provider; an empty key clears it.
+    ///
+    /// The key is written to the OS keychain on the next save, falling back to
+    /// the config file when no keychain is available.
+    pub fn set_api_key(&mut self, provider: ApiKeyProvider, key: Option<String>) {
+        *self.api_key_field_mut(provider) = key.filter(|k| !k.is_empty());
+        self.unavailable_api_keys.remove(&provider);
+    }
+
+    /// Override API keys with `ECHOES_OPENAI_API_KEY` / `ECHOES_GROQ_API_KEY` /
+    /// `ECHOES_GEMINI_API_KEY` when set and non-empty.
+    ///
+    /// Precedence is environment, then config file, then none. Overridden keys
+    /// are not written back by [`Config::save`].
+    pub fn apply_env_overrides(&mut self) {
+        self.apply_env_overrides_from(|name| std::env::var(name).ok());
+    }
+
+    /// [`Config::apply_env_overrides`] reading variables through `var`
+    fn apply_env_overrides_from(&mut self, var: impl Fn(&str) -> Option<String>) {
+        for provider in ApiKeyProvider::ALL {
+            let Some(key) = var(provider.env_var()).filter(|k| !k.is_empty()) else {
+                continue;
+            };
+            let field = self.api_key_field_mut(provider);
+            let original = field.replace(key);
+            self.env_overrides.entry(provider).or_insert(original);
+        }
+    }
+
+    const fn api_key_field_mut(&mut self, provider: ApiKeyProvider) -> &mut Option<String> {
+        match provider {
+            ApiKeyProvider::OpenAI => &mut self.openai_api_key,
+            ApiKeyProvider::Groq => &mut self.groq_api_key,
+            ApiKeyProvider::Gemini => &mut self.gemini_api_key,
+        }
+    }
+
+    /// Replace keychain references with the stored keys. Returns `true` if
+    /// plaintext keys were found that should be migrated to the keychain.
+    pub(crate) fn resolve_api_keys(&mut self) -> bool {
+        let plaintext = self.plaintext_api_keys;
+        let mut needs_migration = false;
+
+        for provider in ApiKeyProvider::ALL {
+            let field = self.api_key_field_mut(provider);
+            match field.as_deref() {
+                Some(KEYCHAIN_REFERENCE) => {
+                    *field = load_from_keychain(provider);
+                    if field.is_none() {
+                        tracing::warn!("API key for {provider:?} could not be read from the keychain");
+                        self.unavailable_api_keys.insert(provider);
+                    }
+                }
+                Some(_) if !plaintext => needs_migration = true,
+                _ => {}
+            }
+        }
+
+        needs_migration
+    }
+
+    /// Store plaintext keys in the keychain. Returns `true` if any of them is
+    /// now held there, so the config file should be rewritten.
+    fn migrate_api_keys(&self) -> bool {
+        let mut migrated = false;
+        for provider in ApiKeyProvider::ALL {
+            if let Some(key) = self.get_api_key(provider) {
+                migrated |= store_in_keychain(provider, key);
+            }
+        }
+        migrated
+    }
+
+    /// Copy of the config as it should be written to disk, with API keys moved
+    /// into the keychain where possible
+    fn to_storage(&self) -> Self {
+        let mut stored = self.clone();
+        for (provider, original) in &self.env_overrides {
+            stored.api_key_field_mut(*provider).clone_from(original);
+        }
+        for provider in &self.unavailable_api_keys {
+            stored
+                .api_key_field_mut(*provider)
+                .get_or_insert_with(|| KEYCHAIN_REFERENCE.into());
+        }
+
+        if self.plaintext_api_keys {
+            return stored;
+        }
+
+        for provider in ApiKeyProvider::ALL {
+            let field = stored.api_key_field_mut(provider);
+            match field.as_deref() {
+                Some(KEYCHAIN_REFERENCE) => {}
+                Some(key) => {
+                    if store_in_keychain(provider, key) {
+                        *field = Some(KEYCHAIN_REFERENCE.into());
+                    }
+                }
+                None => remove_from_keychain(provider),
+            }
+        }
+
+        stored
+    }
+
+    /// Add a user-defined conflict rule and register it with the conflict
+    /// detection system, replacing any rule for the same key combination. Call
+    /// [`Config::save`] to persist it.
+    pub fn add_custom_conflict(&mut self, shortcut: RecordingShortcut, info: ConflictInfo) {
+        self.custom_conflict_rules
+            .retain(|rule| !same_key_combination(&rule.shortcut, &shortcut));
+        register_custom_conflict(shortcut.clone(), info.clone());
+        self.custom_conflict_rules.push(CustomConflictRule { shortcut, info });
+    }
+
+    /// Switch to the profile named `name`, copying its recording shortcut and
+    /// post-processing prompt into the config. Call [`Config::save`] to
+    /// persist it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no profile with that name.
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let profile = self
+            .profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| ConfigError::ValidationError(format!("No profile named {name:?}")))?;
+
+        self.recording_shortcut = profile.recording_shortcut.clone();
+        self.post_processing.prompt = profile.post_processing_prompt.clone();
+        self.active_profile = Some(profile.name.clone());
+        Ok(())
+    }
+
+    /// Get the configuration file path: `config.toml` in the directory named
+    /// by [`CONFIG_DIR_ENV`] when it is set, in the platform config
+    /// directory otherwise
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform config directory cannot be determined.
+    pub fn config_path() -> Result<PathBuf> {
+        Self::config_path_in(std::env::var_os(CONFIG_DIR_ENV).map(PathBuf::from))
+    }
+
+    /// [`Config::config_path`] with `dir` standing in for [`CONFIG_DIR_ENV`]
+    fn config_path_in(dir: Option<PathBuf>) -> Result<PathBuf> {
+        if let Some(dir) = dir.filter(|dir| !dir.as_os_str().is_empty()) {
+            return Ok(dir.join("config.toml"));
+        }
+
         let proj_dirs = ProjectDirs::from("com", "echoes", "echoes")
             .ok_or_else(|| ConfigError::LoadFailed("Failed to determine config directory".into()))?;
 
         Ok(proj_dirs.config_dir().join("config.toml"))
     }
 
+    /// A commented config file setting every option, for users to copy and
+    /// edit. It parses as a valid `Config`.
+    #[must_use]
+    pub fn schema_example() -> String {
+        include_str!("../config.example.toml").to_string()
+    }
+
     /// Validate the entire configuration
     ///
     /// # Errors
@@ -199,7 +1086,193 @@ impl Config {
         self.recording_shortcut
             .validate()
             .map_err(|e| ConfigError::ValidationError(e.to_string()))?;
+        for profile in &self.profiles {
+            profile
+                .recording_shortcut
+                .validate()
+                .map_err(|e| ConfigError::ValidationError(format!("Profile {:?}: {e}", profile.name)))?;
+        }
+        if let Some(active) = &self.active_profile {
+            if !self.profiles.iter().any(|profile| &profile.name == active) {
+                tracing::warn!("Active profile {active:?} is not defined");
+            }
+        }
+
+        // A mismatched model only fails once post-processing runs, so don't
+        // refuse to load over it
+        if let Some(mismatch) = self.post_processing.model_mismatch() {
+            tracing::warn!("{mismatch}");
+        }
+        if let Some(issue) = self.task_issue() {
+            tracing::warn!("{issue}");
+        }
 
         Ok(())
     }
 }
+
+/// Path of the backup kept alongside the config file
+fn backup_path(config_path: &Path) -> PathBuf {
+    config_path.with_extension("toml.bak")
+}
+
+/// Write the config via a temp file and rename so a crash mid-write can't
+/// truncate it, backing up the previous version if it still parses
+fn write_atomic(config_path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ConfigError::SaveFailed(format!("Failed to create config directory: {e}")))?;
+    }
+
+    let tmp_path = config_path.with_extension("toml.tmp");
+    std::fs::write(&tmp_path, content)
+        .map_err(|e| ConfigError::SaveFailed(format!("Failed to write config file: {e}")))?;
+
+    if let Ok(previous) = std::fs::read_to_string(config_path) {
+        if previous != content && toml::from_str::<Config>(&previous).is_ok() {
+            std::fs::write(backup_path(config_path), previous)
+                .map_err(|e| ConfigError::SaveFailed(format!("Failed to write config backup: {e}")))?;
+        }
+    }
+
+    std::fs::rename(&tmp_path, config_path)
+        .map_err(|e| ConfigError::SaveFailed(format!("Failed to replace config file: {e}")))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_override_not_persisted() {
+        let mut config = Config {
+            groq_api_key: Some("file-key".into()),
+            plaintext_api_keys: true,
+            ..Config::default()
+        };
+        config.apply_env_overrides_from(|name| (name == ApiKeyProvider::Groq.env_var()).then(|| "env-key".into()));
+
+        assert_eq!(config.get_api_key(ApiKeyProvider::Groq), Some("env-key"));
+        assert_eq!(config.to_storage().groq_api_key.as_deref(), Some("file-key"));
+    }
+
+    #[test]
+    fn test_unavailable_keychain_key_is_kept() {
+        let mut config = Config {
+            plaintext_api_keys: true,
+            ..Config::default()
+        };
+        config.unavailable_api_keys.insert(ApiKeyProvider::OpenAI);
+
+        assert_eq!(config.get_api_key(ApiKeyProvider::OpenAI), None);
+        assert_eq!(config.to_storage().openai_api_key.as_deref(), Some(KEYCHAIN_REFERENCE));
+
+        config.set_api_key(ApiKeyProvider::OpenAI, None);
+        assert_eq!(config.to_storage().openai_api_key, None);
+    }
+
+    #[test]
+    fn test_export_without_secrets() {
+        let config = Config {
+            openai_api_key: Some("sk-secret-key".into()),
+            openai_extra_headers: vec![("X-Gateway-Token".into(), "gateway-secret".into())],
+            ..Config::default()
+        };
+        let path = std::env::temp_dir().join(format!("echoes-export-test-{}.toml", std::process::id()));
+
+        config.export_to(&path, false).unwrap();
+        let exported = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(!exported.contains("sk-secret-key"));
+        assert!(!exported.contains("gateway-secret"));
+    }
+
+    #[test]
+    fn test_config_dir_override() {
+        let dir = std::env::temp_dir().join("echoes-config-dir-test");
+        let path = Config::config_path_in(Some(dir.clone()));
+
+        assert_eq!(path.unwrap(), dir.join("config.toml"));
+    }
+
+    /// `Config` has no `PartialEq`, so configs are compared by their
+    /// serialized form
+    fn assert_round_trips(config: &Config) {
+        let serialized = toml::to_string(config).unwrap();
+        let parsed: Config = toml::from_str(&serialized).unwrap();
+        assert_eq!(toml::to_string(&parsed).unwrap(), serialized);
+    }
+
+    #[test]
+    fn test_default_config_round_trips() {
+        assert_round_trips(&Config::default());
+    }
+
+    #[test]
+    fn test_schema_example_parses() {
+        let config: Config = toml::from_str(&Config::schema_example()).unwrap();
+        config.validate().unwrap();
+        // Only defaults are set; the alternatives are commented out
+        assert_eq!(
+            toml::to_string(&config).unwrap(),
+            toml::to_string(&Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_custom_conflict_replaces_same_combination() {
+        use crate::{check_shortcut_conflicts, ConflictSeverity, KeyCode};
+
+        let mut config = Config::default();
+        let shortcut = |modifiers| RecordingShortcut {
+            key: KeyCode::F9,
+            modifiers,
+            ..RecordingShortcut::default()
+        };
+        let info = |description: &str| ConflictInfo {
+            severity: ConflictSeverity::Warning,
+            description: description.into(),
+            suggestion: None,
+        };
+
+        config.add_custom_conflict(
+            shortcut(vec![KeyCode::ShiftLeft, KeyCode::ControlLeft]),
+            info("Old rule"),
+        );
+        config.add_custom_conflict(
+            shortcut(vec![KeyCode::ControlRight, KeyCode::ShiftRight]),
+            info("New rule"),
+        );
+
+        assert_eq!(config.custom_conflict_rules.len(), 1);
+        assert_eq!(config.custom_conflict_rules[0].info, info("New rule"));
+        let conflicts = check_shortcut_conflicts(&shortcut(vec![KeyCode::ControlLeft, KeyCode::ShiftLeft]));
+        assert!(conflicts.contains(&info("New rule")));
+        assert!(!conflicts.contains(&info("Old rule")));
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let mut config = Config::default();
+        let shortcut = RecordingShortcut {
+            key: crate::KeyCode::AltGr,
+            ..RecordingShortcut::default()
+        };
+        config.profiles.push(ShortcutProfile {
+            name: "Code comments".into(),
+            recording_shortcut: shortcut.clone(),
+            post_processing_prompt: "Comment: {transcript}".into(),
+        });
+
+        config.apply_profile("Code comments").unwrap();
+
+        assert_eq!(config.recording_shortcut, shortcut);
+        assert_eq!(config.post_processing.prompt, "Comment: {transcript}");
+        assert_eq!(config.active_profile.as_deref(), Some("Code comments"));
+        assert!(config.apply_profile("Missing").is_err());
+        assert_round_trips(&config);
+    }
+}
@@ -5,10 +5,12 @@ use std::{
     sync::{LazyLock, Mutex},
 };
 
-use crate::shortcuts::{KeyCode, RecordingShortcut};
+use serde::{Deserialize, Serialize};
+
+use crate::shortcuts::{modifier_sort_key, normalize_modifier, KeyCode, RecordingShortcut};
 
 /// Severity level for shortcut conflicts
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ConflictSeverity {
     Error,
     Warning,
@@ -16,13 +18,20 @@ pub enum ConflictSeverity {
 }
 
 /// Information about a shortcut conflict
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ConflictInfo {
     pub severity: ConflictSeverity,
     pub description: String,
     pub suggestion: Option<String>,
 }
 
+/// User-defined conflict rule persisted in the config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomConflictRule {
+    pub shortcut: RecordingShortcut,
+    pub info: ConflictInfo,
+}
+
 /// Pattern for matching shortcuts in lookup tables
 #[derive(Hash, Eq, PartialEq, Clone)]
 struct ShortcutPattern {
@@ -31,6 +40,39 @@ struct ShortcutPattern {
     platform: Option<&'static str>,
 }
 
+impl ShortcutPattern {
+    /// Pattern matching `shortcut`, with left and right modifier variants
+    /// treated alike and modifiers in a fixed order, so lookups match however
+    /// the shortcut was pressed
+    fn new(shortcut: &RecordingShortcut, platform: Option<&'static str>) -> Self {
+        let mut modifiers = Vec::new();
+        for modifier in shortcut.modifiers.iter().map(normalize_modifier) {
+            if !modifiers.contains(&modifier) {
+                modifiers.push(modifier);
+            }
+        }
+        modifiers.sort_by_key(|&key| modifier_sort_key(key));
+
+        Self {
+            key: normalize_modifier(&shortcut.key),
+            modifiers,
+            platform,
+        }
+    }
+}
+
+impl From<RecordingShortcut> for ShortcutPattern {
+    fn from(shortcut: RecordingShortcut) -> Self {
+        Self::new(&shortcut, None)
+    }
+}
+
+/// Whether `a` and `b` are the same key combination, however their modifiers
+/// were pressed or listed
+pub(crate) fn same_key_combination(a: &RecordingShortcut, b: &RecordingShortcut) -> bool {
+    ShortcutPattern::new(a, None) == ShortcutPattern::new(b, None)
+}
+
 /// Trait for conflict detection strategies
 trait ConflictDetector: Send + Sync {
     /// Check if the given shortcut conflicts with this detector's domain
@@ -43,6 +85,8 @@ trait ConflictDetector: Send + Sync {
 /// System shortcut conflict detector that checks against OS-level shortcuts
 struct SystemConflictDetector {
     shortcuts: &'static HashMap<ShortcutPattern, &'static str>,
+    /// Platform the shortcuts' patterns are registered for
+    platform: &'static str,
 }
 
 /// Application shortcut conflict detector that checks against common app
@@ -63,6 +107,7 @@ struct ConflictCache {
 /// Main conflict detection system that coordinates multiple detectors
 pub struct ConflictDetectionSystem {
     detectors: Vec<Box<dyn ConflictDetector>>,
+    custom: HashMap<ShortcutPattern, ConflictInfo>,
     cache: ConflictCache,
 }
 
@@ -129,6 +174,54 @@ static WINDOWS_SYSTEM_SHORTCUTS: LazyLock<HashMap<ShortcutPattern, &'static str>
     map
 });
 
+#[allow(dead_code)]
+static LINUX_SYSTEM_SHORTCUTS: LazyLock<HashMap<ShortcutPattern, &'static str>> = LazyLock::new(|| {
+    let mut map = HashMap::new();
+
+    // Super+key shortcuts (GNOME/KDE)
+    let super_shortcuts = [
+        (KeyCode::L, "Super+L locks the screen"),
+        (KeyCode::D, "Super+D shows desktop"),
+        (KeyCode::A, "Super+A opens the application grid"),
+    ];
+
+    for (key, desc) in super_shortcuts {
+        map.insert(
+            ShortcutPattern {
+                key,
+                modifiers: vec![KeyCode::MetaLeft],
+                platform: Some("linux"),
+            },
+            desc,
+        );
+    }
+
+    // Ctrl+Alt shortcuts
+    let ctrl_alt_shortcuts = [
+        (KeyCode::T, "Ctrl+Alt+T opens a terminal"),
+        (KeyCode::F1, "Ctrl+Alt+F1 switches virtual terminal"),
+        (KeyCode::F2, "Ctrl+Alt+F2 switches virtual terminal"),
+        (KeyCode::F3, "Ctrl+Alt+F3 switches virtual terminal"),
+        (KeyCode::F4, "Ctrl+Alt+F4 switches virtual terminal"),
+        (KeyCode::F5, "Ctrl+Alt+F5 switches virtual terminal"),
+        (KeyCode::F6, "Ctrl+Alt+F6 switches virtual terminal"),
+        (KeyCode::F7, "Ctrl+Alt+F7 switches virtual terminal"),
+    ];
+
+    for (key, desc) in ctrl_alt_shortcuts {
+        map.insert(
+            ShortcutPattern {
+                key,
+                modifiers: vec![KeyCode::ControlLeft, KeyCode::Alt],
+                platform: Some("linux"),
+            },
+            desc,
+        );
+    }
+
+    map
+});
+
 static APPLICATION_SHORTCUTS: LazyLock<HashMap<ShortcutPattern, ConflictInfo>> = LazyLock::new(|| {
     let mut map = HashMap::new();
 
@@ -174,23 +267,24 @@ impl SystemConflictDetector {
         let shortcuts = &*MACOS_SYSTEM_SHORTCUTS;
         #[cfg(target_os = "windows")]
         let shortcuts = &*WINDOWS_SYSTEM_SHORTCUTS;
-        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        #[cfg(target_os = "linux")]
+        let shortcuts = &*LINUX_SYSTEM_SHORTCUTS;
+        #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
         let shortcuts = {
             static EMPTY: LazyLock<HashMap<ShortcutPattern, &'static str>> = LazyLock::new(HashMap::new);
             &*EMPTY
         };
 
-        Self { shortcuts }
+        Self {
+            shortcuts,
+            platform: std::env::consts::OS,
+        }
     }
 }
 
 impl ConflictDetector for SystemConflictDetector {
     fn check(&self, shortcut: &RecordingShortcut) -> Option<ConflictInfo> {
-        let pattern = ShortcutPattern {
-            key: shortcut.key,
-            modifiers: shortcut.modifiers.clone(),
-            platform: None,
-        };
+        let pattern = ShortcutPattern::new(shortcut, Some(self.platform));
 
         self.shortcuts.get(&pattern).map(|desc| ConflictInfo {
             severity: ConflictSeverity::Error,
@@ -214,11 +308,7 @@ impl ApplicationConflictDetector {
 
 impl ConflictDetector for ApplicationConflictDetector {
     fn check(&self, shortcut: &RecordingShortcut) -> Option<ConflictInfo> {
-        let pattern = ShortcutPattern {
-            key: shortcut.key,
-            modifiers: shortcut.modifiers.clone(),
-            platform: None,
-        };
+        let pattern = ShortcutPattern::new(shortcut, None);
 
         self.shortcuts.get(&pattern).cloned()
     }
@@ -256,10 +346,24 @@ impl ConflictDetectionSystem {
 
         Self {
             detectors,
+            custom: HashMap::new(),
             cache: ConflictCache::default(),
         }
     }
 
+    /// Register a user-defined conflict rule, replacing any existing rule for
+    /// the same key combination
+    pub fn add_custom_detector(&mut self, shortcut: RecordingShortcut, info: ConflictInfo) {
+        self.custom.insert(ShortcutPattern::from(shortcut), info);
+        self.clear_cache();
+    }
+
+    /// Remove all user-defined conflict rules
+    pub fn clear_custom_detectors(&mut self) {
+        self.custom.clear();
+        self.clear_cache();
+    }
+
     /// Check for conflicts with caching for performance
     pub fn check_conflicts(&mut self, shortcut: &RecordingShortcut) -> Vec<ConflictInfo> {
         // Check cache first
@@ -275,6 +379,10 @@ impl ConflictDetectionSystem {
             }
         }
 
+        if let Some(conflict) = self.custom.get(&ShortcutPattern::new(shortcut, None)) {
+            conflicts.push(conflict.clone());
+        }
+
         // Cache the result
         self.cache.cache.insert(shortcut.clone(), conflicts.clone());
 
@@ -303,6 +411,23 @@ pub fn check_shortcut_conflicts(shortcut: &RecordingShortcut) -> Vec<ConflictInf
         .map_or_else(|_| Vec::new(), |mut system| system.check_conflicts(shortcut))
 }
 
+/// Register a user-defined conflict rule with the global detection system
+pub fn register_custom_conflict(shortcut: RecordingShortcut, info: ConflictInfo) {
+    if let Ok(mut system) = CONFLICT_SYSTEM.lock() {
+        system.add_custom_detector(shortcut, info);
+    }
+}
+
+/// Replace all user-defined conflict rules in the global detection system
+pub fn load_custom_conflicts(rules: &[CustomConflictRule]) {
+    if let Ok(mut system) = CONFLICT_SYSTEM.lock() {
+        system.clear_custom_detectors();
+        for rule in rules {
+            system.add_custom_detector(rule.shortcut.clone(), rule.info.clone());
+        }
+    }
+}
+
 /// Check for accessibility concerns with a shortcut
 fn check_accessibility_concerns(shortcut: &RecordingShortcut) -> Option<ConflictInfo> {
     // Check if shortcut is difficult to press with one hand
@@ -404,3 +529,58 @@ const fn is_universal_modifier(key: KeyCode) -> bool {
         KeyCode::Alt | KeyCode::AltGr | KeyCode::MetaLeft | KeyCode::MetaRight
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shortcuts::ShortcutMode;
+
+    fn shortcut(key: KeyCode, modifiers: Vec<KeyCode>) -> RecordingShortcut {
+        RecordingShortcut {
+            mode: ShortcutMode::Hold,
+            key,
+            modifiers,
+            scan_code: None,
+        }
+    }
+
+    #[test]
+    fn test_linux_system_shortcuts_match_any_modifier_variant() {
+        let detector = SystemConflictDetector {
+            shortcuts: &LINUX_SYSTEM_SHORTCUTS,
+            platform: "linux",
+        };
+
+        for modifiers in [vec![KeyCode::MetaLeft], vec![KeyCode::MetaRight]] {
+            assert!(detector.check(&shortcut(KeyCode::L, modifiers)).is_some());
+        }
+        for modifiers in [
+            vec![KeyCode::ControlLeft, KeyCode::Alt],
+            vec![KeyCode::Alt, KeyCode::ControlLeft],
+            vec![KeyCode::ControlRight, KeyCode::Alt],
+            vec![KeyCode::Alt, KeyCode::ControlRight],
+        ] {
+            assert!(detector.check(&shortcut(KeyCode::F2, modifiers)).is_some());
+        }
+        assert!(detector.check(&shortcut(KeyCode::F2, vec![KeyCode::Alt])).is_none());
+    }
+
+    #[test]
+    fn test_custom_rule_invalidates_cached_lookup() {
+        let mut system = ConflictDetectionSystem::new();
+        let pressed = shortcut(KeyCode::J, vec![KeyCode::ControlRight, KeyCode::Alt]);
+        let info = ConflictInfo {
+            severity: ConflictSeverity::Warning,
+            description: "Used by my editor".into(),
+            suggestion: None,
+        };
+
+        assert!(!system.check_conflicts(&pressed).contains(&info));
+
+        system.add_custom_detector(
+            shortcut(KeyCode::J, vec![KeyCode::Alt, KeyCode::ControlLeft]),
+            info.clone(),
+        );
+        assert!(system.check_conflicts(&pressed).contains(&info));
+    }
+}
@@ -0,0 +1,82 @@
+//! Describing what changed between two configurations, for the session log
+
+use toml::{Table, Value};
+
+use crate::config::Config;
+
+/// Shown instead of the old and new value of settings holding secrets, such
+/// as API keys and the values of extra request headers
+const REDACTED: &str = "changed (redacted)";
+
+impl Config {
+    /// Lists the settings that differ in `other`, one `path: old -> new` line
+    /// each, with nested settings as dotted paths. Secrets are redacted.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        let (Ok(Value::Table(old)), Ok(Value::Table(new))) = (Value::try_from(self), Value::try_from(other)) else {
+            return Vec::new();
+        };
+
+        let mut changes = Vec::new();
+        diff_tables("", &old, &new, &mut changes);
+        changes
+    }
+}
+
+/// Appends the differences between `old` and `new`, whose keys are prefixed
+/// with `prefix`, to `changes`
+fn diff_tables(prefix: &str, old: &Table, new: &Table, changes: &mut Vec<String>) {
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        let path = format!("{prefix}{key}");
+        match (old.get(key), new.get(key)) {
+            (Some(Value::Table(old)), Some(Value::Table(new))) => {
+                diff_tables(&format!("{path}."), old, new, changes);
+            }
+            (old, new) if old == new => {}
+            _ if is_secret(key) => changes.push(format!("{path}: {REDACTED}")),
+            (old, new) => changes.push(format!("{path}: {} -> {}", describe(old), describe(new))),
+        }
+    }
+}
+
+fn is_secret(key: &str) -> bool {
+    key.ends_with("_api_key") || key.ends_with("_extra_headers")
+}
+
+fn describe(value: Option<&Value>) -> String {
+    value.map_or_else(|| "unset".to_string(), ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lists_changes_and_redacts_keys() {
+        let old = Config::default();
+        let mut new = old.clone();
+        assert!(old.diff(&new).is_empty());
+
+        new.openai_api_key = Some("sk-secret".into());
+        new.stt_retry.max_attempts += 1;
+        new.min_hold_ms = 250;
+
+        let changes = old.diff(&new);
+        assert_eq!(
+            changes,
+            vec![
+                "min_hold_ms: 0 -> 250".to_string(),
+                format!("openai_api_key: {REDACTED}"),
+                format!(
+                    "stt_retry.max_attempts: {} -> {}",
+                    old.stt_retry.max_attempts, new.stt_retry.max_attempts
+                ),
+            ]
+        );
+        assert!(!changes.concat().contains("sk-secret"));
+    }
+}
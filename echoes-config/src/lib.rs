@@ -5,17 +5,25 @@
 //! - Shortcut conflict detection system
 //! - Platform-specific shortcut validation
 //! - Configuration persistence
+//! - API key storage in the OS keychain
+//! - Hot-reloading on external edits
+//! - Describing changes between configurations
 
 pub mod config;
 pub mod conflict;
+pub mod diff;
+pub mod secrets;
 pub mod shortcuts;
 pub mod validation;
+pub mod watch;
 
 // Re-export main types for convenience
 pub use config::*;
 pub use conflict::*;
+pub use secrets::*;
 pub use shortcuts::*;
 pub use validation::*;
+pub use watch::*;
 
 /// Result type for this crate
 pub type Result<T> = std::result::Result<T, ConfigError>;
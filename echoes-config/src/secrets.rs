@@ -0,0 +1,88 @@
+//! API key storage in the OS keychain with a plaintext fallback
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Keychain service name under which API keys are stored
+const KEYRING_SERVICE: &str = "echoes";
+
+/// Value written to the config file in place of a key held in the keychain
+pub const KEYCHAIN_REFERENCE: &str = "<keychain>";
+
+/// Providers whose API keys are managed by [`crate::Config::set_api_key`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ApiKeyProvider {
+    OpenAI,
+    Groq,
+    Gemini,
+}
+
+impl ApiKeyProvider {
+    /// All providers with API keys
+    pub const ALL: [Self; 3] = [Self::OpenAI, Self::Groq, Self::Gemini];
+
+    /// Environment variable that overrides this provider's key
+    #[must_use]
+    pub const fn env_var(self) -> &'static str {
+        match self {
+            Self::OpenAI => "ECHOES_OPENAI_API_KEY",
+            Self::Groq => "ECHOES_GROQ_API_KEY",
+            Self::Gemini => "ECHOES_GEMINI_API_KEY",
+        }
+    }
+
+    /// Keychain account name for this provider
+    const fn account(self) -> &'static str {
+        match self {
+            Self::OpenAI => "openai_api_key",
+            Self::Groq => "groq_api_key",
+            Self::Gemini => "gemini_api_key",
+        }
+    }
+}
+
+/// Keys known to be in the keychain, so saves don't rewrite unchanged keys
+static KEYCHAIN_CACHE: LazyLock<Mutex<HashMap<ApiKeyProvider, String>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn entry(provider: ApiKeyProvider) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, provider.account())
+}
+
+/// Store a key in the keychain, returning `true` if it is held there
+pub(crate) fn store_in_keychain(provider: ApiKeyProvider, key: &str) -> bool {
+    let Ok(mut cache) = KEYCHAIN_CACHE.lock() else {
+        return false;
+    };
+    if cache.get(&provider).is_some_and(|cached| cached == key) {
+        return true;
+    }
+
+    let stored = entry(provider).and_then(|entry| entry.set_password(key)).is_ok();
+    if stored {
+        cache.insert(provider, key.to_string());
+    }
+    stored
+}
+
+/// Read a key from the keychain
+pub(crate) fn load_from_keychain(provider: ApiKeyProvider) -> Option<String> {
+    let key = entry(provider).and_then(|entry| entry.get_password()).ok()?;
+    if let Ok(mut cache) = KEYCHAIN_CACHE.lock() {
+        cache.insert(provider, key.clone());
+    }
+    Some(key)
+}
+
+/// Delete a key previously stored in the keychain
+pub(crate) fn remove_from_keychain(provider: ApiKeyProvider) {
+    let Ok(mut cache) = KEYCHAIN_CACHE.lock() else {
+        return;
+    };
+    if cache.remove(&provider).is_some() {
+        let _ = entry(provider).and_then(|entry| entry.delete_credential());
+    }
+}
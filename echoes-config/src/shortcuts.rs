@@ -103,6 +103,123 @@ pub enum KeyCode {
     BackQuote,
 }
 
+impl KeyCode {
+    /// Every key, modifiers first and left variants before right ones
+    pub const ALL: [Self; 83] = [
+        Self::ControlLeft,
+        Self::ControlRight,
+        Self::ShiftLeft,
+        Self::ShiftRight,
+        Self::Alt,
+        Self::AltGr,
+        Self::MetaLeft,
+        Self::MetaRight,
+        Self::Space,
+        Self::Tab,
+        Self::Return,
+        Self::Escape,
+        Self::Backspace,
+        Self::Delete,
+        Self::Insert,
+        Self::Home,
+        Self::End,
+        Self::PageUp,
+        Self::PageDown,
+        Self::CapsLock,
+        Self::UpArrow,
+        Self::DownArrow,
+        Self::LeftArrow,
+        Self::RightArrow,
+        Self::F1,
+        Self::F2,
+        Self::F3,
+        Self::F4,
+        Self::F5,
+        Self::F6,
+        Self::F7,
+        Self::F8,
+        Self::F9,
+        Self::F10,
+        Self::F11,
+        Self::F12,
+        Self::A,
+        Self::B,
+        Self::C,
+        Self::D,
+        Self::E,
+        Self::F,
+        Self::G,
+        Self::H,
+        Self::I,
+        Self::J,
+        Self::K,
+        Self::L,
+        Self::M,
+        Self::N,
+        Self::O,
+        Self::P,
+        Self::Q,
+        Self::R,
+        Self::S,
+        Self::T,
+        Self::U,
+        Self::V,
+        Self::W,
+        Self::X,
+        Self::Y,
+        Self::Z,
+        Self::Num0,
+        Self::Num1,
+        Self::Num2,
+        Self::Num3,
+        Self::Num4,
+        Self::Num5,
+        Self::Num6,
+        Self::Num7,
+        Self::Num8,
+        Self::Num9,
+        Self::Slash,
+        Self::BackSlash,
+        Self::Equal,
+        Self::Minus,
+        Self::Comma,
+        Self::Dot,
+        Self::SemiColon,
+        Self::Quote,
+        Self::LeftBracket,
+        Self::RightBracket,
+        Self::BackQuote,
+    ];
+
+    /// Parses a key name as produced by [`format_keycode`], ignoring case.
+    ///
+    /// Common alternative names are accepted too: `Control`, `Option`,
+    /// `Cmd`/`Win`/`Meta`/`Super` on any platform, `Return`, `Escape` and
+    /// `Up`/`Down`/`Left`/`Right`. Names shared by a left and right key
+    /// resolve to the left one.
+    #[must_use]
+    pub fn from_display(name: &str) -> Option<Self> {
+        let name = name.trim();
+        let alias = match name.to_ascii_lowercase().as_str() {
+            "control" => Some(Self::ControlLeft),
+            "option" => Some(Self::Alt),
+            "cmd" | "command" | "win" | "windows" | "meta" | "super" => Some(Self::MetaLeft),
+            "return" => Some(Self::Return),
+            "escape" => Some(Self::Escape),
+            "up" => Some(Self::UpArrow),
+            "down" => Some(Self::DownArrow),
+            "left" => Some(Self::LeftArrow),
+            "right" => Some(Self::RightArrow),
+            _ => None,
+        };
+        alias.or_else(|| {
+            Self::ALL
+                .into_iter()
+                .find(|key| format_keycode(key).eq_ignore_ascii_case(name))
+        })
+    }
+}
+
 /// Shortcut mode for recording
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum ShortcutMode {
@@ -116,13 +233,22 @@ pub struct RecordingShortcut {
     pub mode: ShortcutMode,
     pub key: KeyCode,            // The main key
     pub modifiers: Vec<KeyCode>, // Additional modifier keys
+    /// Physical scan code of the main key; when set, the main key is matched
+    /// by position instead of by `key`, so the shortcut survives layout changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_code: Option<u32>,
 }
 
 impl RecordingShortcut {
     /// Create a new recording shortcut
     #[must_use]
     pub const fn new(mode: ShortcutMode, key: KeyCode, modifiers: Vec<KeyCode>) -> Self {
-        Self { mode, key, modifiers }
+        Self {
+            mode,
+            key,
+            modifiers,
+            scan_code: None,
+        }
     }
 
     /// Format shortcut for display
@@ -161,12 +287,36 @@ impl RecordingShortcut {
     }
 }
 
+impl std::str::FromStr for RecordingShortcut {
+    type Err = crate::validation::ValidationError;
+
+    /// Parses a shortcut like `Ctrl + Shift + Space` or `Ctrl+/`, the inverse
+    /// of [`RecordingShortcut::format_display`]. The last key is the main key
+    /// and the rest are modifiers; the mode is `Hold`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use crate::validation::ValidationError;
+
+        if s.trim().is_empty() {
+            return Err(ValidationError::EmptyShortcut);
+        }
+        let mut keys = s
+            .split('+')
+            .map(|name| KeyCode::from_display(name).ok_or_else(|| ValidationError::UnknownKey(name.trim().to_string())))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let key = keys.pop().ok_or(ValidationError::EmptyShortcut)?;
+        let modifiers = keys.iter().map(normalize_modifier).collect();
+        Ok(Self::new(ShortcutMode::Hold, key, modifiers))
+    }
+}
+
 impl Default for RecordingShortcut {
     fn default() -> Self {
         Self {
             mode: ShortcutMode::Hold,
             key: KeyCode::ControlLeft,
             modifiers: vec![],
+            scan_code: None,
         }
     }
 }
@@ -199,7 +349,7 @@ pub const fn normalize_modifier(key: &KeyCode) -> KeyCode {
 }
 
 /// Get sort key for modifier ordering
-const fn modifier_sort_key(key: KeyCode) -> u8 {
+pub(crate) const fn modifier_sort_key(key: KeyCode) -> u8 {
     match normalize_modifier(&key) {
         KeyCode::ControlLeft => 1,
         KeyCode::ShiftLeft => 2,
@@ -348,3 +498,30 @@ pub fn extract_shortcut_from_keys(keys: &[KeyCode]) -> (Option<KeyCode>, Vec<Key
 
     (main_key, modifiers)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_key_round_trips() {
+        for key in KeyCode::ALL {
+            let parsed = KeyCode::from_display(&format_keycode(&key));
+            assert_eq!(parsed, Some(normalize_modifier(&key)), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_shortcut() {
+        let shortcut: RecordingShortcut = "Control + shift+/".parse().unwrap();
+        assert_eq!(shortcut.key, KeyCode::Slash);
+        assert_eq!(shortcut.modifiers, vec![KeyCode::ControlLeft, KeyCode::ShiftLeft]);
+        assert_eq!(shortcut.mode, ShortcutMode::Hold);
+        assert_eq!(shortcut.format_display().parse::<RecordingShortcut>(), Ok(shortcut));
+
+        assert_eq!(
+            "Ctrl+Hyper".parse::<RecordingShortcut>(),
+            Err(crate::validation::ValidationError::UnknownKey("Hyper".to_string()))
+        );
+    }
+}
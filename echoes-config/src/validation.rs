@@ -8,6 +8,8 @@ use crate::shortcuts::{is_modifier_key, normalize_modifier, RecordingShortcut};
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ValidationError {
     EmptyShortcut,
+    /// A key name that isn't recognized when parsing a shortcut
+    UnknownKey(String),
     ModifierOnly,
     DuplicateModifiers,
     SystemConflict(String),
@@ -18,6 +20,7 @@ impl std::fmt::Display for ValidationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::EmptyShortcut => write!(f, "Shortcut cannot be empty"),
+            Self::UnknownKey(name) => write!(f, "Unknown key: {name:?}"),
             Self::ModifierOnly => write!(f, "Shortcut cannot be only a modifier key"),
             Self::DuplicateModifiers => write!(f, "Duplicate modifier keys detected"),
             Self::SystemConflict(desc) => {
@@ -0,0 +1,111 @@
+//! Config file watching with debounced hot-reload
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{mpsc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{config::Config, ConfigError, Result};
+
+/// How long the file must stay quiet before a change is reloaded
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Hash of the config content we last read or wrote ourselves, used to ignore
+/// file events caused by our own `save()`
+static LAST_CONTENT_HASH: Mutex<Option<u64>> = Mutex::new(None);
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Record content as already known so the watcher doesn't reload it
+pub(crate) fn remember_content(content: &str) {
+    if let Ok(mut last) = LAST_CONTENT_HASH.lock() {
+        *last = Some(content_hash(content));
+    }
+}
+
+/// Returns `true` if the content differs from what we last saw, recording it
+fn is_new_content(content: &str) -> bool {
+    let hash = content_hash(content);
+    LAST_CONTENT_HASH.lock().map_or(true, |mut last| {
+        let is_new = *last != Some(hash);
+        *last = Some(hash);
+        is_new
+    })
+}
+
+/// Handle to a running config watcher; watching stops when it is dropped
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl Config {
+    /// Watch the config file and invoke `on_change` with the reparsed config
+    /// whenever it is edited externally.
+    ///
+    /// Bursts of file events are debounced, writes made by [`Config::save`]
+    /// are ignored by comparing content hashes, and files that fail to parse
+    /// (e.g. mid-edit) are skipped until the next change.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the config directory cannot be determined, created,
+    /// or watched.
+    pub fn watch(on_change: impl Fn(Self) + Send + 'static) -> Result<ConfigWatcher> {
+        let config_path = Self::config_path()?;
+        let config_dir = config_path
+            .parent()
+            .ok_or_else(|| ConfigError::LoadFailed("Config path has no parent directory".into()))?
+            .to_path_buf();
+        std::fs::create_dir_all(&config_dir)
+            .map_err(|e| ConfigError::LoadFailed(format!("Failed to create config directory: {e}")))?;
+
+        let (tx, rx) = mpsc::channel();
+        let watched_path = config_path.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if event.paths.iter().any(|p| p == &watched_path) {
+                    let _ = tx.send(());
+                }
+            }
+        })
+        .map_err(|e| ConfigError::LoadFailed(format!("Failed to create config watcher: {e}")))?;
+
+        watcher
+            .watch(&config_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| ConfigError::LoadFailed(format!("Failed to watch config directory: {e}")))?;
+
+        // The thread exits once the watcher (and with it the sender) is dropped
+        thread::Builder::new()
+            .name("echoes-config-watch".into())
+            .spawn(move || {
+                let _span = tracing::info_span!("config_watch").entered();
+                while rx.recv().is_ok() {
+                    while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+                    let Ok(content) = std::fs::read_to_string(&config_path) else {
+                        continue;
+                    };
+                    if !is_new_content(&content) {
+                        continue;
+                    }
+                    if let Ok(mut config) = toml::from_str::<Self>(&content) {
+                        config.resolve_api_keys();
+                        config.apply_env_overrides();
+                        crate::conflict::load_custom_conflicts(&config.custom_conflict_rules);
+                        on_change(config);
+                    }
+                }
+            })
+            .map_err(|e| ConfigError::LoadFailed(format!("Failed to start config watcher thread: {e}")))?;
+
+        Ok(ConfigWatcher { _watcher: watcher })
+    }
+}
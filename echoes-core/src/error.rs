@@ -27,6 +27,8 @@ pub enum EchoesError {
 #[allow(dead_code)]
 pub enum AudioError {
     NoInputDevice,
+    /// The microphone is held by another application
+    DeviceBusy,
     UnsupportedFormat(String),
     StreamCreationFailed(String),
     RecordingFailed(String),
@@ -58,6 +60,7 @@ pub enum KeyboardError {
 #[allow(dead_code)]
 pub enum SttError {
     ApiKeyMissing(String),
+    AuthenticationFailed(String),
     NetworkError(String),
     InvalidResponse(String),
     ServiceUnavailable(String),
@@ -111,6 +114,7 @@ impl fmt::Display for AudioError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::NoInputDevice => write!(f, "No audio input device available"),
+            Self::DeviceBusy => write!(f, "Microphone is in use by another application"),
             Self::UnsupportedFormat(fmt) => write!(f, "Unsupported audio format: {fmt}"),
             Self::StreamCreationFailed(msg) => {
                 write!(f, "Failed to create audio stream: {msg}")
@@ -156,6 +160,7 @@ impl fmt::Display for SttError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::ApiKeyMissing(provider) => write!(f, "API key missing for {provider}"),
+            Self::AuthenticationFailed(provider) => write!(f, "{provider} rejected the API key"),
             Self::NetworkError(msg) => write!(f, "Network error: {msg}"),
             Self::InvalidResponse(msg) => {
                 write!(f, "Invalid response from STT service: {msg}")
@@ -235,6 +240,26 @@ impl From<AudioError> for EchoesError {
     }
 }
 
+impl From<echoes_audio::AudioError> for AudioError {
+    fn from(err: echoes_audio::AudioError) -> Self {
+        match err {
+            echoes_audio::AudioError::NoInputDevice => Self::NoInputDevice,
+            echoes_audio::AudioError::DeviceBusy => Self::DeviceBusy,
+            echoes_audio::AudioError::StreamCreationFailed(msg) => Self::StreamCreationFailed(msg),
+            echoes_audio::AudioError::UnsupportedFormat(format) => Self::UnsupportedFormat(format),
+            echoes_audio::AudioError::WavEncodingFailed(msg) => Self::WavEncodingFailed(msg),
+            echoes_audio::AudioError::MutexPoisoned => Self::MutexPoisoned,
+            err => Self::RecordingFailed(err.to_string()),
+        }
+    }
+}
+
+impl From<echoes_audio::AudioError> for EchoesError {
+    fn from(err: echoes_audio::AudioError) -> Self {
+        Self::Audio(err.into())
+    }
+}
+
 impl From<ConfigError> for EchoesError {
     fn from(err: ConfigError) -> Self {
         Self::Config(err)
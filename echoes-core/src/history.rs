@@ -0,0 +1,209 @@
+//! Persistent transcription history stored as JSON lines
+
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{EchoesError, Result};
+
+/// Bytes read at a time when scanning the history file backwards
+const READ_CHUNK_BYTES: u64 = 8 * 1024;
+
+/// A single transcription record
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Local>,
+    pub text: String,
+    /// STT provider that produced the text
+    pub provider: String,
+    /// Length of the recorded audio
+    pub duration_ms: u64,
+    /// Saved recording, if it was written to disk
+    pub audio_path: Option<PathBuf>,
+}
+
+/// Append-only transcription history backed by a JSON-lines file
+#[derive(Debug, Clone)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    /// Use the history file at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Path of the history file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry to the history file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry cannot be serialized or the file cannot be
+    /// written.
+    pub fn add(&self, entry: &HistoryEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let line = serde_json::to_string(entry)
+            .map_err(|e| EchoesError::Other(format!("Failed to serialize history entry: {e}")))?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{line}")?;
+
+        Ok(())
+    }
+
+    /// The `n` most recent entries, newest first. Malformed lines are skipped.
+    ///
+    /// The file is read backwards from its end, so the cost depends on `n`
+    /// rather than on the size of the history.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history file exists but cannot be read.
+    pub fn recent(&self, n: usize) -> Result<Vec<HistoryEntry>> {
+        let mut file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        let mut pos = file.seek(SeekFrom::End(0))?;
+        // Start of the earliest line read so far, which may be incomplete
+        let mut partial = Vec::new();
+        while pos > 0 && entries.len() < n {
+            let len = READ_CHUNK_BYTES.min(pos);
+            pos -= len;
+            file.seek(SeekFrom::Start(pos))?;
+            let mut chunk = vec![0; usize::try_from(len).unwrap_or(usize::MAX)];
+            file.read_exact(&mut chunk)?;
+            chunk.append(&mut partial);
+
+            // Lines after the first newline are complete; at the start of the
+            // file so is the first one
+            let complete_from = match (pos, chunk.iter().position(|&b| b == b'\n')) {
+                (0, _) => 0,
+                (_, Some(newline)) => newline + 1,
+                (_, None) => {
+                    partial = chunk;
+                    continue;
+                }
+            };
+            for line in chunk[complete_from..].split(|&b| b == b'\n').rev() {
+                if entries.len() == n {
+                    break;
+                }
+                if let Ok(entry) = serde_json::from_slice::<HistoryEntry>(line) {
+                    entries.push(entry);
+                }
+            }
+            chunk.truncate(complete_from.saturating_sub(1));
+            partial = chunk;
+        }
+
+        Ok(entries)
+    }
+
+    /// Remove all entries
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history file exists but cannot be removed.
+    pub fn clear(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        let data_dir = directories::ProjectDirs::from("com", "echoes", "echoes")
+            .map_or_else(|| PathBuf::from("."), |dirs| dirs.data_dir().to_path_buf());
+        Self::new(data_dir.join("history.jsonl"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_history(name: &str) -> History {
+        let path = std::env::temp_dir().join(format!("echoes-history-{name}-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        History::new(path)
+    }
+
+    fn entry(text: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: Local::now(),
+            text: text.into(),
+            provider: "OpenAI".into(),
+            duration_ms: 1000,
+            audio_path: None,
+        }
+    }
+
+    fn texts(entries: &[HistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_add_recent_clear() {
+        let history = temp_history("add");
+        assert!(history.recent(5).unwrap().is_empty());
+
+        for text in ["one", "two", "three"] {
+            history.add(&entry(text)).unwrap();
+        }
+        assert_eq!(texts(&history.recent(2).unwrap()), ["three", "two"]);
+        assert_eq!(texts(&history.recent(10).unwrap()), ["three", "two", "one"]);
+        assert!(history.recent(0).unwrap().is_empty());
+
+        history.clear().unwrap();
+        assert!(history.recent(5).unwrap().is_empty());
+        history.clear().unwrap();
+    }
+
+    #[test]
+    fn test_recent_skips_malformed_lines() {
+        let history = temp_history("malformed");
+        history.add(&entry("one")).unwrap();
+        let mut file = OpenOptions::new().append(true).open(history.path()).unwrap();
+        writeln!(file, "{{not json").unwrap();
+        writeln!(file).unwrap();
+        drop(file);
+        history.add(&entry("two")).unwrap();
+
+        assert_eq!(texts(&history.recent(5).unwrap()), ["two", "one"]);
+        history.clear().unwrap();
+    }
+
+    #[test]
+    fn test_recent_across_read_chunks() {
+        let history = temp_history("chunks");
+        let long_text = "word ".repeat(500);
+        for i in 0..20 {
+            history.add(&entry(&format!("{i} {long_text}"))).unwrap();
+        }
+
+        let recent = history.recent(20).unwrap();
+        assert_eq!(recent.len(), 20);
+        assert!(recent[0].text.starts_with("19 "));
+        assert!(recent[19].text.starts_with("0 "));
+        history.clear().unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+//! Checking API keys with the provider before they are needed
+
+use echoes_config::ApiKeyProvider;
+
+use crate::{
+    error::{Result, SttError},
+    post_processing::response_json,
+};
+
+/// Checks an `OpenAI` API key against `base_url`
+///
+/// # Errors
+///
+/// Returns `SttError::ApiKeyMissing` for an empty key,
+/// `SttError::AuthenticationFailed` if the key is rejected, or a network or
+/// service error if the provider can't be reached.
+pub async fn validate_openai_key(key: &str, base_url: &str) -> Result<()> {
+    validate_key(ApiKeyProvider::OpenAI, key, base_url).await
+}
+
+/// Checks a Groq API key against `base_url`
+///
+/// # Errors
+///
+/// Same as [`validate_openai_key`]
+pub async fn validate_groq_key(key: &str, base_url: &str) -> Result<()> {
+    validate_key(ApiKeyProvider::Groq, key, base_url).await
+}
+
+/// Lists the models of an `OpenAI`-compatible API, the cheapest request that
+/// needs a valid key
+async fn validate_key(provider: ApiKeyProvider, key: &str, base_url: &str) -> Result<()> {
+    if key.trim().is_empty() {
+        return Err(SttError::ApiKeyMissing(format!("{provider:?}")).into());
+    }
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/models", base_url.trim_end_matches('/')))
+        .bearer_auth(key.trim())
+        .send()
+        .await
+        .map_err(|e| SttError::NetworkError(e.to_string()))?;
+    response_json(response, provider).await.map(|_| ())
+}
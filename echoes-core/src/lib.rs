@@ -1,11 +1,15 @@
 use echoes_config::Config;
 use eframe::egui;
-use tracing::info;
+use tracing::{info, warn};
 
 pub mod error;
+pub mod history;
+pub mod key_check;
+pub mod post_processing;
+pub mod recordings;
 pub mod ui;
 
-use echoes_logging::{TracingConfig, init_tracing, setup_panic_handler};
+use echoes_logging::{TracingConfig, init_tracing, set_log_level, setup_panic_handler};
 use error::{EchoesError, Result, UiError};
 
 /// Runs the main application loop
@@ -24,11 +28,33 @@ pub async fn run() -> Result<()> {
 
     let config = Config::load().map_err(|e| EchoesError::Other(format!("Failed to load config: {e}")))?;
 
+    // The config is only known once tracing runs, so its directives are
+    // applied afterwards
+    if !config.log_directives.is_empty() {
+        let filter = config
+            .log_directives
+            .iter()
+            .fold(tracing_config, |tracing_config, (target, level)| {
+                tracing_config.with_directive(target, level)
+            })
+            .filter();
+        if let Err(e) = set_log_level(&filter) {
+            warn!("Ignoring log_directives: {e}");
+        }
+    }
+
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size([800.0, 600.0])
+        .with_min_inner_size([600.0, 400.0]);
+    if let Some(window) = config.window {
+        viewport = viewport
+            .with_inner_size([window.width, window.height])
+            .with_position([window.x, window.y]);
+    }
+
     let native_options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([800.0, 600.0])
-            .with_min_inner_size([600.0, 400.0]),
-        centered: true,
+        viewport,
+        centered: config.window.is_none(),
         ..Default::default()
     };
 
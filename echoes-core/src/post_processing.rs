@@ -0,0 +1,128 @@
+//! LLM clean-up of transcripts before they are output
+
+use echoes_config::{ApiKeyProvider, Config, LlmProvider};
+use reqwest::StatusCode;
+use serde_json::json;
+use tracing::debug;
+
+use crate::error::{Result, SttError};
+
+const GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Placeholder in the post-processing prompt replaced by the transcript
+const TRANSCRIPT_PLACEHOLDER: &str = "{transcript}";
+
+/// Runs the transcript through the configured post-processing LLM
+///
+/// # Errors
+///
+/// Returns an error if the provider's API key is missing or rejected, the
+/// quota is exhausted, or the request or its response is invalid.
+pub async fn post_process(config: &Config, transcript: &str) -> Result<String> {
+    let settings = &config.post_processing;
+    let prompt = build_prompt(&settings.prompt, transcript);
+    let client = reqwest::Client::new();
+    debug!("Post-processing with {:?} model {}", settings.provider, settings.model);
+
+    let text = match settings.provider {
+        LlmProvider::OpenAI => {
+            let base_url = config.openai_base_url.as_deref().unwrap_or("https://api.openai.com/v1");
+            chat_completion(&client, config, ApiKeyProvider::OpenAI, base_url, &prompt).await?
+        }
+        LlmProvider::Groq => {
+            let base_url = config
+                .groq_base_url
+                .as_deref()
+                .unwrap_or("https://api.groq.com/openai/v1");
+            chat_completion(&client, config, ApiKeyProvider::Groq, base_url, &prompt).await?
+        }
+        LlmProvider::Gemini => gemini_generate(&client, config, &prompt).await?,
+    };
+
+    Ok(text.trim().to_string())
+}
+
+fn build_prompt(template: &str, transcript: &str) -> String {
+    if template.contains(TRANSCRIPT_PLACEHOLDER) {
+        template.replace(TRANSCRIPT_PLACEHOLDER, transcript)
+    } else {
+        format!("{template}\n\n{transcript}")
+    }
+}
+
+fn api_key(config: &Config, provider: ApiKeyProvider) -> Result<&str> {
+    config
+        .get_api_key(provider)
+        .ok_or_else(|| SttError::ApiKeyMissing(format!("{provider:?}")).into())
+}
+
+/// Sends a prompt to an `OpenAI`-compatible chat completions endpoint
+async fn chat_completion(
+    client: &reqwest::Client, config: &Config, provider: ApiKeyProvider, base_url: &str, prompt: &str,
+) -> Result<String> {
+    let response = client
+        .post(format!("{base_url}/chat/completions"))
+        .bearer_auth(api_key(config, provider)?)
+        .json(&json!({
+            "model": config.post_processing.model,
+            "messages": [{ "role": "user", "content": prompt }],
+        }))
+        .send()
+        .await
+        .map_err(|e| SttError::NetworkError(e.to_string()))?;
+
+    let body = response_json(response, provider).await?;
+    body["choices"][0]["message"]["content"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| SttError::InvalidResponse("Missing message content in response".into()).into())
+}
+
+/// Sends a prompt to the Gemini `generateContent` endpoint
+async fn gemini_generate(client: &reqwest::Client, config: &Config, prompt: &str) -> Result<String> {
+    let url = format!(
+        "{GEMINI_BASE_URL}/models/{}:generateContent",
+        config.post_processing.model
+    );
+    let response = client
+        .post(url)
+        .header("x-goog-api-key", api_key(config, ApiKeyProvider::Gemini)?)
+        .json(&json!({
+            "contents": [{ "parts": [{ "text": prompt }] }],
+        }))
+        .send()
+        .await
+        .map_err(|e| SttError::NetworkError(e.to_string()))?;
+
+    let body = response_json(response, ApiKeyProvider::Gemini).await?;
+    let parts = body["candidates"][0]["content"]["parts"]
+        .as_array()
+        .ok_or_else(|| SttError::InvalidResponse("Missing candidate content in response".into()))?;
+
+    Ok(parts.iter().filter_map(|part| part["text"].as_str()).collect())
+}
+
+/// Parses a successful response, mapping auth and quota failures to their
+/// own errors
+pub(crate) async fn response_json(response: reqwest::Response, provider: ApiKeyProvider) -> Result<serde_json::Value> {
+    let status = response.status();
+    if status.is_success() {
+        return response
+            .json()
+            .await
+            .map_err(|e| SttError::InvalidResponse(e.to_string()).into());
+    }
+
+    let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+    let error = match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => SttError::AuthenticationFailed(format!("{provider:?}")),
+        StatusCode::TOO_MANY_REQUESTS => SttError::RateLimitExceeded,
+        // Gemini reports an invalid key as a bad request
+        StatusCode::BAD_REQUEST if error_text.contains("API_KEY_INVALID") => {
+            SttError::AuthenticationFailed(format!("{provider:?}"))
+        }
+        status if status.is_server_error() => SttError::ServiceUnavailable(format!("{status} - {error_text}")),
+        status => SttError::InvalidResponse(format!("{status} - {error_text}")),
+    };
+    Err(error.into())
+}
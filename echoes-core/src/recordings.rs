@@ -0,0 +1,115 @@
+//! Saving recordings and their subtitles to disk under configurable names
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use echoes_config::Config;
+use echoes_stt::{TranscriptSegment, subtitles};
+
+use crate::error::Result;
+
+/// Names and writes the files of one recording.
+///
+/// File names come from `Config::recording_filename_template`, where `{date}`,
+/// `{time}` and `{kind}` are replaced by the recording's date, its time and
+/// `raw` or `segment_<n>`.
+#[derive(Debug, Clone)]
+pub struct RecordingFiles {
+    dir: PathBuf,
+    template: String,
+    timestamp: DateTime<Local>,
+}
+
+impl RecordingFiles {
+    /// Files for a recording made at `timestamp`. A relative
+    /// `recording_output_dir` is taken relative to the data directory, so
+    /// output doesn't depend on where the app was started from.
+    #[must_use]
+    pub fn new(config: &Config, timestamp: DateTime<Local>) -> Self {
+        let data_dir = data_dir();
+        let dir = match &config.recording_output_dir {
+            Some(dir) if dir.is_absolute() => dir.clone(),
+            Some(dir) => data_dir.join(dir),
+            None => data_dir.join("recordings"),
+        };
+
+        Self {
+            dir,
+            template: config.recording_filename_template.clone(),
+            timestamp,
+        }
+    }
+
+    /// Path of the file holding `kind`
+    #[must_use]
+    #[allow(clippy::literal_string_with_formatting_args)]
+    pub fn path(&self, kind: &str) -> PathBuf {
+        let name = self
+            .template
+            .replace("{date}", &self.timestamp.format("%Y%m%d").to_string())
+            .replace("{time}", &self.timestamp.format("%H%M%S").to_string())
+            .replace("{kind}", kind);
+        self.dir.join(format!("{name}.wav"))
+    }
+
+    /// Writes `wav` as `kind`, creating the output directory if needed, and
+    /// returns the absolute path written
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or file cannot be written.
+    pub fn save(&self, kind: &str, wav: &[u8]) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(kind);
+        std::fs::write(&path, wav)?;
+        Ok(std::path::absolute(&path)?)
+    }
+}
+
+/// Subtitle formats a timed transcript can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+        }
+    }
+
+    #[must_use]
+    pub fn render(self, segments: &[TranscriptSegment]) -> String {
+        match self {
+            Self::Srt => subtitles::to_srt(segments),
+            Self::Vtt => subtitles::to_vtt(segments),
+        }
+    }
+}
+
+/// Writes `segments` as subtitles next to `audio_path`, named after it with
+/// the format's extension, and returns the absolute path written
+///
+/// # Errors
+///
+/// Returns an error if the directory or file cannot be written.
+pub fn save_subtitles(audio_path: &Path, format: SubtitleFormat, segments: &[TranscriptSegment]) -> Result<PathBuf> {
+    let path = audio_path.with_extension(format.extension());
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, format.render(segments))?;
+    Ok(std::path::absolute(&path)?)
+}
+
+/// The application data directory, falling back to the home directory
+fn data_dir() -> PathBuf {
+    directories::ProjectDirs::from("com", "echoes", "echoes").map_or_else(
+        || directories::BaseDirs::new().map_or_else(|| PathBuf::from("."), |dirs| dirs.home_dir().join(".echoes")),
+        |dirs| dirs.data_dir().to_path_buf(),
+    )
+}
@@ -1,13 +1,55 @@
-use echoes_audio::AudioRecorder;
-use echoes_config::{Config, RecordingShortcut, ShortcutMode};
+use std::{path::PathBuf, time::Duration};
+
+use echoes_audio::{AudioRecorder, Cue, Segment};
+use echoes_config::{Config, RecordingShortcut, ShortcutMode, SttProvider, WhisperModel};
 use echoes_keyboard::KeyboardEvent;
+use echoes_stt::TranscriptSegment;
+use eframe::egui;
 use tracing::info;
 
+use crate::{
+    error::AudioError,
+    history::{History, HistoryEntry},
+    recordings::{self, RecordingFiles, SubtitleFormat},
+};
+
 use super::{
-    config_manager::ConfigManager, keyboard_manager::KeyboardManager, session_manager::SessionManager,
-    shortcut_manager::ShortcutManager, shortcuts, system_manager::SystemManager,
+    config_manager::ConfigManager,
+    control_server::{ControlCommand, ControlServer},
+    key_check_manager::KeyCheckManager,
+    keyboard_manager::KeyboardManager,
+    mic_test::MicTest,
+    model_manager::ModelManager,
+    output_manager::OutputManager,
+    session_manager::SessionManager,
+    shortcut_manager::{ObservedKey, ShortcutManager},
+    shortcuts,
+    system_manager::SystemManager,
+    transcription_manager::{TranscriptionManager, TranscriptionState},
 };
 
+/// Number of transcripts kept in memory for the history panel
+const RECENT_HISTORY_LEN: usize = 50;
+
+/// Extensions of audio files that can be dropped on the window to transcribe
+/// them. MP3 files are only understood by the cloud providers.
+const DROPPED_FILE_EXTENSIONS: [&str; 2] = ["wav", "mp3"];
+
+/// Characters of the transcript shown in the completion notification
+const NOTIFICATION_PREVIEW_CHARS: usize = 80;
+
+/// Recordings at least this long are transcribed segment by segment when
+/// `segment_transcription` is enabled
+const SEGMENT_TRANSCRIPTION_MIN_SECS: u64 = 60;
+
+/// Timed segments of the latest transcript, kept for exporting subtitles
+pub struct SubtitleSource {
+    pub segments: Vec<TranscriptSegment>,
+    /// The recording or dropped file that was transcribed, if it was saved
+    pub audio_path: Option<PathBuf>,
+    pub finished_at: chrono::DateTime<chrono::Local>,
+}
+
 /// Command trait for handling keyboard events
 trait KeyboardEventCommand {
     fn execute(&self, app_state: &mut AppState) -> bool;
@@ -15,29 +57,43 @@ trait KeyboardEventCommand {
 
 /// Commands for handling specific keyboard events
 struct RecordingKeyPressedCommand;
-struct RecordingKeyReleasedCommand;
+struct RecordingKeyReleasedCommand {
+    to_clipboard: bool,
+}
 struct OtherKeyPressedCommand;
 struct ListenerErrorCommand(String);
 struct ShortcutRecordedCommand(RecordingShortcut);
 struct RecordingCancelledCommand;
+struct RetypeLastCommand;
+struct KeyObservedCommand(ObservedKey);
 
 /// Core application state using composition pattern
 pub struct AppState {
     pub config: Config,
     pub config_manager: ConfigManager,
     pub keyboard_manager: KeyboardManager,
+    pub key_checks: KeyCheckManager,
+    pub mic_test: MicTest,
+    pub model_manager: ModelManager,
     pub session_manager: SessionManager,
     pub shortcut_manager: ShortcutManager,
-    #[allow(dead_code)]
     pub system_manager: SystemManager,
     pub audio_recorder: AudioRecorder,
+    pub transcription_manager: TranscriptionManager,
+    pub output_manager: OutputManager,
+    pub history: History,
+    pub recent_transcripts: Vec<HistoryEntry>,
+    /// `None` unless the latest transcription succeeded with timed segments
+    pub subtitle_source: Option<SubtitleSource>,
+    /// Listening while `config.control_socket` is set, once started
+    pub control_server: Option<ControlServer>,
 }
 
 impl AppState {
     pub fn new(config: Config) -> Self {
         info!("AppState::new called");
         info!("Creating ConfigManager");
-        let config_manager = ConfigManager::new();
+        let config_manager = ConfigManager::new(&config);
         info!("ConfigManager created");
 
         info!("Creating KeyboardManager");
@@ -46,21 +102,36 @@ impl AppState {
 
         info!("Creating other managers");
         let session_manager = SessionManager::new();
+        let model_manager = ModelManager::new();
         let shortcut_manager = ShortcutManager::new();
         let system_manager = SystemManager::new();
         let audio_recorder = AudioRecorder::new();
+        let transcription_manager = TranscriptionManager::new();
+        let history = History::default();
+        let recent_transcripts = history.recent(RECENT_HISTORY_LEN).unwrap_or_default();
         info!("All managers created");
 
         let mut state = Self {
             config,
             config_manager,
             keyboard_manager,
+            key_checks: KeyCheckManager::new(),
+            mic_test: MicTest::new(),
+            model_manager,
             session_manager,
             shortcut_manager,
             system_manager,
             audio_recorder,
+            transcription_manager,
+            output_manager: OutputManager::new(),
+            history,
+            recent_transcripts,
+            subtitle_source: None,
+            control_server: None,
         };
 
+        state.apply_launch_at_login();
+
         info!("About to initialize keyboard listener");
         // Initialize keyboard listener
         state.init_keyboard_listener();
@@ -69,20 +140,65 @@ impl AppState {
     }
 
     pub fn init_keyboard_listener(&mut self) {
-        match self.keyboard_manager.init(self.config.recording_shortcut.clone()) {
+        // X11 capture works without it, so only warn up front and explain on failure
+        let input_group_hint = input_group_hint();
+        if let Some(hint) = &input_group_hint {
+            self.session_manager.add_log(hint.clone());
+        }
+
+        match self
+            .keyboard_manager
+            .init(self.config.recording_shortcut.clone(), self.config.consume_trigger)
+        {
             Ok(()) => {
+                self.keyboard_manager.set_min_hold_ms(self.config.min_hold_ms);
+                self.keyboard_manager
+                    .set_cancel_shortcut(self.config.cancel_shortcut.clone());
+                self.keyboard_manager
+                    .set_retype_shortcut(self.config.retype_last_shortcut.clone());
                 self.session_manager.add_log("Keyboard listener started");
                 self.session_manager.set_error(None);
             }
             Err(e) => {
+                let e = input_group_hint.map_or_else(|| e.clone(), |hint| format!("{e}. {hint}"));
                 self.session_manager.add_log(format!("Keyboard init failed: {e}"));
                 self.session_manager.set_error(Some(e));
             }
         }
     }
 
+    /// Starts the keyboard listener once accessibility permission is granted
+    /// in System Settings, so users don't have to retry by hand. Checks right
+    /// away when the window `gained_focus`, as users come back from System
+    /// Settings, and otherwise every few seconds.
+    pub fn poll_permissions(&mut self, gained_focus: bool) -> bool {
+        if !self.keyboard_manager.permission_newly_granted(gained_focus) {
+            return false;
+        }
+        self.session_manager
+            .add_log("Accessibility permission granted, starting keyboard listener");
+        self.init_keyboard_listener();
+        true
+    }
+
+    /// Registers or unregisters the login item to match
+    /// `config.launch_at_login`, leaving it alone when it already does
+    pub fn apply_launch_at_login(&mut self) {
+        let enabled = self.config.launch_at_login;
+        if echoes_platform::launch_at_login_enabled() == enabled {
+            return;
+        }
+        match echoes_platform::set_launch_at_login(enabled) {
+            Ok(()) if enabled => self.session_manager.add_log("Registered to launch at login"),
+            Ok(()) => self.session_manager.add_log("No longer launching at login"),
+            Err(e) => self
+                .session_manager
+                .add_log(format!("Failed to update launch at login: {e}")),
+        }
+    }
+
     pub fn open_accessibility_settings(&mut self) {
-        match SystemManager::open_accessibility_settings() {
+        match echoes_platform::open_settings() {
             Ok(()) => self.session_manager.add_log("Opened System Settings"),
             Err(e) => self.session_manager.add_log(format!("System settings error: {e}")),
         }
@@ -90,35 +206,450 @@ impl AppState {
 
     pub fn handle_keyboard_events(&mut self) -> bool {
         let events = self.keyboard_manager.try_recv_event();
-        let mut needs_repaint = false;
+        let needs_repaint = !events.is_empty();
 
         for event in events {
-            needs_repaint = true;
-            let command: Box<dyn KeyboardEventCommand> = match event {
-                KeyboardEvent::RecordingKeyPressed => Box::new(RecordingKeyPressedCommand),
-                KeyboardEvent::RecordingKeyReleased => Box::new(RecordingKeyReleasedCommand),
-                KeyboardEvent::OtherKeyPressed => Box::new(OtherKeyPressedCommand),
-                KeyboardEvent::ListenerError(msg) => Box::new(ListenerErrorCommand(msg)),
-                KeyboardEvent::ShortcutRecorded(shortcut) => Box::new(ShortcutRecordedCommand(shortcut)),
-                KeyboardEvent::RecordingCancelled => Box::new(RecordingCancelledCommand),
-            };
+            self.handle_keyboard_event(event);
+        }
+
+        needs_repaint
+    }
+
+    fn handle_keyboard_event(&mut self, event: KeyboardEvent) {
+        let command: Box<dyn KeyboardEventCommand> = match event {
+            KeyboardEvent::RecordingKeyPressed => Box::new(RecordingKeyPressedCommand),
+            KeyboardEvent::RecordingKeyReleased { to_clipboard } => {
+                Box::new(RecordingKeyReleasedCommand { to_clipboard })
+            }
+            KeyboardEvent::OtherKeyPressed => Box::new(OtherKeyPressedCommand),
+            KeyboardEvent::ListenerError(msg) => Box::new(ListenerErrorCommand(msg)),
+            KeyboardEvent::ShortcutRecorded(shortcut) => Box::new(ShortcutRecordedCommand(shortcut)),
+            KeyboardEvent::RecordingCancelled => Box::new(RecordingCancelledCommand),
+            KeyboardEvent::RetypeLast => Box::new(RetypeLastCommand),
+            KeyboardEvent::KeyObserved {
+                code,
+                scan_code,
+                pressed,
+            } => Box::new(KeyObservedCommand(ObservedKey {
+                code,
+                scan_code,
+                pressed,
+            })),
+        };
+
+        command.execute(self);
+    }
+
+    /// Listens on the configured control socket, so scripts can drive the app
+    pub fn start_control_server(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.config.control_socket.clone() else {
+            return;
+        };
+        match ControlServer::start(&path, ctx) {
+            Ok(server) => {
+                self.session_manager
+                    .add_log(format!("Control socket listening on {}", server.path().display()));
+                self.control_server = Some(server);
+            }
+            Err(e) => self.session_manager.add_log(format!("Control socket unavailable: {e}")),
+        }
+    }
+
+    /// Runs the commands received on the control socket. Recording commands
+    /// take the same path as the recording shortcut.
+    pub fn handle_control_commands(&mut self) -> bool {
+        let commands = self
+            .control_server
+            .as_ref()
+            .map(ControlServer::poll)
+            .unwrap_or_default();
+        let needs_repaint = !commands.is_empty();
+
+        for command in commands {
+            match command {
+                ControlCommand::Start => self.handle_keyboard_event(KeyboardEvent::RecordingKeyPressed),
+                ControlCommand::Stop => {
+                    self.handle_keyboard_event(KeyboardEvent::RecordingKeyReleased { to_clipboard: false });
+                }
+                ControlCommand::Toggle => self.toggle_recording(),
+                ControlCommand::Cancel => self.handle_keyboard_event(KeyboardEvent::RecordingCancelled),
+                ControlCommand::TranscribeFile { path } => self.transcribe_file(path),
+            }
+        }
+
+        needs_repaint
+    }
+
+    /// Applies config changes made by editing the config file directly
+    pub fn handle_config_reload(&mut self) -> bool {
+        let Some(config) = self.config_manager.try_recv_reload() else {
+            return false;
+        };
+
+        let shortcut_changed = config.recording_shortcut != self.config.recording_shortcut;
+        let changes = self.config.diff(&config);
+        self.config = config;
+        self.apply_launch_at_login();
+        self.keyboard_manager.set_min_hold_ms(self.config.min_hold_ms);
+        self.keyboard_manager
+            .set_cancel_shortcut(self.config.cancel_shortcut.clone());
+        self.keyboard_manager
+            .set_retype_shortcut(self.config.retype_last_shortcut.clone());
+        if shortcut_changed {
+            self.update_shortcut_listener();
+        }
+        self.session_manager.add_log("Reloaded config from disk");
+        for change in changes {
+            self.session_manager.add_log(format!("Changed {change}"));
+        }
+        true
+    }
+
+    /// Queues the config for saving and logs each setting it changes
+    pub fn save_config(&mut self) {
+        for change in self.config_manager.save_async(self.config.clone()) {
+            self.session_manager.add_log(format!("Changed {change}"));
+        }
+    }
+
+    /// Logs the outcome of text output that finished since the last frame
+    pub fn poll_output(&mut self) -> bool {
+        let was_busy = self.output_manager.is_busy();
+        for line in self.output_manager.poll() {
+            self.session_manager.add_log(line);
+        }
+        was_busy
+    }
+
+    /// Logs model downloads that finished since the last frame
+    pub fn poll_model_downloads(&mut self) -> bool {
+        let finished = self.model_manager.poll();
+        for (model, result) in &finished {
+            match result {
+                Ok(()) => self
+                    .session_manager
+                    .add_log(format!("Downloaded Whisper model {model:?}")),
+                Err(e) => self
+                    .session_manager
+                    .add_log(format!("Failed to download Whisper model {model:?}: {e}")),
+            }
+        }
+        !finished.is_empty()
+    }
+
+    /// Starts downloading a Whisper model in the background
+    pub fn download_model(&mut self, model: WhisperModel) {
+        let message = format!("Downloading Whisper model {model:?}");
+        match self.model_manager.start_download(model) {
+            Ok(()) => self.session_manager.add_log(message),
+            Err(e) => self
+                .session_manager
+                .add_log(format!("Failed to download Whisper model: {e}")),
+        }
+    }
+
+    /// Deletes a downloaded Whisper model
+    pub fn delete_model(&mut self, model: &WhisperModel) {
+        match self.model_manager.delete(model) {
+            Ok(()) => self.session_manager.add_log(format!("Deleted Whisper model {model:?}")),
+            Err(e) => self
+                .session_manager
+                .add_log(format!("Failed to delete Whisper model: {e}")),
+        }
+    }
+
+    /// Handles a finished background transcription: outputs the text,
+    /// notifies about it when the window isn't focused and records it in the
+    /// history. Transcripts copied to the clipboard are only output.
+    pub fn poll_transcription(&mut self, window_focused: bool) -> bool {
+        let Some(result) = self.transcription_manager.poll() else {
+            return false;
+        };
+        self.subtitle_source = None;
+
+        match result {
+            Ok(transcript) if transcript.text.trim().is_empty() => {
+                self.report_no_speech("Transcript is empty");
+            }
+            Ok(transcript) if !transcript.from_file && self.is_duplicate(&transcript.text) => {
+                self.report_duplicate(&transcript.text, window_focused);
+            }
+            Ok(transcript) => {
+                let language = transcript
+                    .detected_language
+                    .as_ref()
+                    .map_or_else(String::new, |language| format!(" of {language} speech"));
+                self.session_manager
+                    .add_log(format!("Transcribed {} characters{language}", transcript.text.len()));
+                if transcript.from_file {
+                    // Shown with a copy button; typing it out would land in
+                    // whatever app has focus
+                } else if self.session_manager.copy_to_clipboard {
+                    match echoes_keyboard::copy_text(&transcript.text) {
+                        Ok(()) => self.session_manager.add_log("Copied transcript to the clipboard"),
+                        Err(e) => self.session_manager.add_log(format!("Failed to copy text: {e}")),
+                    }
+                } else if let Err(e) = self.output_manager.output(&self.config, transcript.text.clone(), None) {
+                    self.session_manager.add_log(format!("Failed to output text: {e}"));
+                }
+                // Clipboard transcripts are usually passwords, so they leave
+                // no trace in notifications, subtitles or the history
+                if !transcript.from_file && self.session_manager.copy_to_clipboard {
+                    return true;
+                }
+                if self.config.notify_on_complete && !window_focused {
+                    self.notify_transcribed(&transcript.text);
+                }
+                if !transcript.segments.is_empty() {
+                    self.subtitle_source = Some(SubtitleSource {
+                        segments: transcript.segments,
+                        audio_path: transcript.audio_path.clone(),
+                        finished_at: chrono::Local::now(),
+                    });
+                }
+                self.record_transcript(
+                    transcript.text,
+                    transcript.provider,
+                    transcript.duration_ms,
+                    transcript.audio_path,
+                );
+            }
+            Err(e) => self.session_manager.add_log(format!("Transcription failed: {e}")),
+        }
+        true
+    }
+
+    /// Transcribes an audio file dropped on the window with the configured
+    /// provider. The result is shown rather than output; unsupported files
+    /// and read errors are shown as a failed transcription, MP3 files too when
+    /// only Local Whisper is configured.
+    pub fn transcribe_file(&mut self, path: PathBuf) {
+        if self.session_manager.recording || self.transcription_manager.is_transcribing() {
+            self.session_manager
+                .add_log("Ignoring dropped file while recording or transcribing");
+            return;
+        }
+
+        let supported = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| {
+                DROPPED_FILE_EXTENSIONS
+                    .into_iter()
+                    .find(|e| e.eq_ignore_ascii_case(extension))
+            });
+        let error = match supported {
+            None => Some(format!("Unsupported file {}: drop a WAV or MP3 file", path.display())),
+            Some("mp3") if !self.can_transcribe_mp3() => Some(format!(
+                "Can't transcribe {}: Local Whisper only reads WAV files",
+                path.display()
+            )),
+            Some(_) => None,
+        };
+
+        if let Some(e) = error {
+            self.session_manager.add_log(e.clone());
+            self.transcription_manager.state = TranscriptionState::Failed(e);
+            return;
+        }
+        self.session_manager.add_log(format!("Transcribing {}", path.display()));
+        self.transcription_manager.start_file(&self.config, path);
+    }
+
+    /// MP3 files are only decoded by the cloud providers, so at least one of
+    /// them has to be in the provider chain
+    fn can_transcribe_mp3(&self) -> bool {
+        self.config
+            .stt_providers()
+            .iter()
+            .any(|provider| *provider != SttProvider::LocalWhisper)
+    }
+
+    /// Writes the latest transcript's timed segments as subtitles next to the
+    /// recording they came from, or in the recordings directory when it
+    /// wasn't saved
+    pub fn export_subtitles(&mut self, format: SubtitleFormat) {
+        let Some(source) = &self.subtitle_source else {
+            return;
+        };
+        let audio_path = source
+            .audio_path
+            .clone()
+            .unwrap_or_else(|| RecordingFiles::new(&self.config, source.finished_at).path("subtitles"));
+        match recordings::save_subtitles(&audio_path, format, &source.segments) {
+            Ok(path) => self
+                .session_manager
+                .add_log(format!("Exported subtitles to {}", path.display())),
+            Err(e) => self.session_manager.add_log(format!("Failed to export subtitles: {e}")),
+        }
+    }
+
+    /// Whether `text` repeats the most recent history entry, finished within
+    /// the configured window, when duplicate suppression is enabled
+    fn is_duplicate(&self, text: &str) -> bool {
+        if !self.config.suppress_duplicates {
+            return false;
+        }
+        let window = Duration::from_secs(self.config.duplicate_window_secs);
+        self.history
+            .recent(1)
+            .ok()
+            .and_then(|mut recent| recent.pop())
+            .is_some_and(|last| {
+                last.text.trim() == text.trim()
+                    && chrono::Local::now()
+                        .signed_duration_since(last.timestamp)
+                        .to_std()
+                        .is_ok_and(|age| age <= window)
+            })
+    }
+
+    /// Logs a transcript skipped as a duplicate, notifying about it like a
+    /// completed one
+    fn report_duplicate(&mut self, text: &str, window_focused: bool) {
+        self.session_manager.add_log("Duplicate transcription suppressed");
+        if !self.config.notify_on_complete || window_focused {
+            return;
+        }
+        if let Err(e) = echoes_platform::notify("Duplicate transcription suppressed", text) {
+            self.session_manager
+                .add_log(format!("Failed to post notification: {e}"));
+        }
+    }
+
+    /// Shows that a recording contained no speech instead of outputting an
+    /// empty transcript, notifying about it if enabled
+    fn report_no_speech(&mut self, detail: &str) {
+        self.transcription_manager.state = TranscriptionState::NoSpeech;
+        self.session_manager.add_log(format!("No speech detected: {detail}"));
+        if !self.config.notify_on_no_speech {
+            return;
+        }
+        if let Err(e) = echoes_platform::notify("No speech detected", "Nothing was transcribed") {
+            self.session_manager
+                .add_log(format!("Failed to post notification: {e}"));
+        }
+    }
 
-            command.execute(self);
+    /// Posts a notification previewing `text`
+    fn notify_transcribed(&mut self, text: &str) {
+        let mut preview: String = text.chars().take(NOTIFICATION_PREVIEW_CHARS).collect();
+        if preview.len() < text.len() {
+            preview.push('…');
         }
+        if let Err(e) = echoes_platform::notify("Transcribed", &preview) {
+            self.session_manager
+                .add_log(format!("Failed to post notification: {e}"));
+        }
+    }
+
+    /// Ends the session's recording when the audio stream isn't running, so
+    /// the UI never shows a recording that failed to start or was stopped
+    /// underneath it
+    pub fn reconcile_recording_state(&mut self) -> bool {
+        if !self.session_manager.recording || self.audio_recorder.is_recording() {
+            return false;
+        }
+
+        self.session_manager.stop_recording();
+        self.transcription_manager.cancel_streaming();
+        self.session_manager
+            .add_log("Audio stream is not running - recording stopped");
+        true
+    }
+
+    /// Stops the recording once the audio buffer is full, so it is
+    /// transcribed up to that point instead of silently losing the rest
+    pub fn check_buffer_overflow(&mut self) -> bool {
+        if !self.session_manager.recording || self.audio_recorder.dropped_samples() == 0 {
+            return false;
+        }
+
+        self.session_manager
+            .add_log("Maximum recording length reached - stopping recording");
+        RecordingKeyReleasedCommand { to_clipboard: false }.execute(self)
+    }
 
+    /// In streaming mode, sends the speech segments finalized since the last
+    /// frame off for transcription
+    pub fn poll_streaming_segments(&mut self) -> bool {
+        if !self.session_manager.recording {
+            return false;
+        }
+
+        let segments = self.audio_recorder.poll_segments();
+        let needs_repaint = !segments.is_empty();
+        for segment in segments {
+            self.transcription_manager.push_segment(segment);
+        }
         needs_repaint
     }
 
+    /// Appends a finished transcription to the history file
+    pub fn record_transcript(
+        &mut self, text: impl Into<String>, provider: impl Into<String>, duration_ms: u64, audio_path: Option<PathBuf>,
+    ) {
+        let entry = HistoryEntry {
+            timestamp: chrono::Local::now(),
+            text: text.into(),
+            provider: provider.into(),
+            duration_ms,
+            audio_path,
+        };
+
+        if let Err(e) = self.history.add(&entry) {
+            self.session_manager
+                .add_log(format!("Failed to save transcript to history: {e}"));
+        }
+        self.recent_transcripts.insert(0, entry);
+        self.recent_transcripts.truncate(RECENT_HISTORY_LEN);
+    }
+
     pub fn apply_shortcut(&mut self, shortcut: RecordingShortcut) {
         let shortcut_str = shortcuts::format_shortcut(&shortcut);
         self.config.recording_shortcut = shortcut;
         self.session_manager
             .add_log(format!("Changed shortcut to {shortcut_str}"));
-        self.config_manager.save_async(self.config.clone());
+        self.save_config();
         self.keyboard_manager
             .update_shortcut(self.config.recording_shortcut.clone());
     }
 
+    /// Names of the configured profiles and the active one, if any
+    pub fn profiles(&self) -> (Vec<String>, Option<String>) {
+        let names = self
+            .config
+            .profiles
+            .iter()
+            .map(|profile| profile.name.clone())
+            .collect();
+        (names, self.config.active_profile.clone())
+    }
+
+    /// Switches to the profile named `name`, applying its shortcut to the
+    /// keyboard listener right away and persisting the choice
+    pub fn set_active_profile(&mut self, name: &str) {
+        if let Err(e) = self.config.apply_profile(name) {
+            self.session_manager.add_log(format!("Failed to switch profile: {e}"));
+            return;
+        }
+        self.session_manager.add_log(format!(
+            "Switched to profile {name} ({})",
+            shortcuts::format_shortcut(&self.config.recording_shortcut)
+        ));
+        self.save_config();
+        self.update_shortcut_listener();
+    }
+
+    /// Starts or stops recording as if the shortcut had been pressed/released
+    pub fn toggle_recording(&mut self) {
+        if self.session_manager.recording {
+            RecordingKeyReleasedCommand { to_clipboard: false }.execute(self);
+        } else {
+            RecordingKeyPressedCommand.execute(self);
+        }
+    }
+
     pub fn update_shortcut_listener(&self) {
         self.keyboard_manager
             .update_shortcut(self.config.recording_shortcut.clone());
@@ -145,10 +676,27 @@ impl AppState {
         self.keyboard_manager.stop_recording_shortcut();
     }
 
+    /// Turns the shortcut tester on or off. While on, the listener reports
+    /// keys instead of triggering recordings.
+    pub fn set_key_tester(&mut self, enabled: bool) {
+        self.shortcut_manager.testing_keys = enabled;
+        self.shortcut_manager.observed_keys.clear();
+        self.keyboard_manager.set_key_observer(enabled);
+        self.session_manager.add_log(if enabled {
+            "Shortcut tester started; the shortcut is paused"
+        } else {
+            "Shortcut tester stopped"
+        });
+    }
+
     pub const fn recording(&self) -> bool {
         self.session_manager.recording
     }
 
+    pub const fn transcribing(&self) -> bool {
+        self.transcription_manager.is_transcribing()
+    }
+
     pub const fn recording_shortcut(&self) -> bool {
         self.session_manager.recording_shortcut
     }
@@ -207,19 +755,69 @@ impl AppState {
     }
 }
 
+/// Explains how to join the `input` group when the user is missing from it on
+/// Linux
+fn input_group_hint() -> Option<String> {
+    matches!(echoes_platform::linux_input_group_status(), Ok(false)).then(|| {
+        format!(
+            "Keyboard capture may need the 'input' group: run `{}` and log in again",
+            echoes_platform::LINUX_INPUT_GROUP_COMMAND
+        )
+    })
+}
+
 /// Command implementations for keyboard events
 impl KeyboardEventCommand for RecordingKeyPressedCommand {
     fn execute(&self, app_state: &mut AppState) -> bool {
         if !app_state.session_manager.recording {
+            match echoes_platform::check_microphone_permission(true) {
+                echoes_platform::PermissionStatus::Granted => {}
+                echoes_platform::PermissionStatus::Denied => {
+                    let msg = "Microphone access denied. Grant access in System Settings > Privacy & Security > \
+                               Microphone, then try again.";
+                    app_state.session_manager.set_error(Some(msg.into()));
+                    app_state.session_manager.add_log(msg);
+                    return true;
+                }
+                echoes_platform::PermissionStatus::NotDetermined => {
+                    app_state
+                        .session_manager
+                        .add_log("Waiting for microphone permission; press the shortcut again once granted");
+                    return true;
+                }
+            }
+
             app_state.session_manager.start_recording();
 
             // Start audio recording
+            let streaming = app_state.config.streaming_transcription;
+            app_state.audio_recorder.set_streaming(streaming);
+            app_state.audio_recorder.set_gain(app_state.config.input_gain_db);
+            app_state
+                .audio_recorder
+                .set_vad_config(app_state.config.vad_sensitivity.vad_config());
+            app_state
+                .audio_recorder
+                .set_segment_tail_pad(app_state.config.segment_tail_pad_ms);
+            app_state
+                .audio_recorder
+                .set_normalize(app_state.config.normalize_peak_dbfs);
             if let Err(e) = app_state.audio_recorder.start_recording() {
+                // Described in the app's terms, e.g. a microphone held by
+                // another application
+                let e = AudioError::from(e);
                 app_state
                     .session_manager
                     .add_log(format!("Failed to start audio recording: {e}"));
+                app_state.transcription_manager.state = TranscriptionState::Failed(e.to_string());
                 app_state.session_manager.stop_recording();
             } else {
+                if app_state.config.sound_on_start {
+                    echoes_audio::play_cue(Cue::Start);
+                }
+                if streaming {
+                    app_state.transcription_manager.start_streaming(&app_state.config);
+                }
                 let msg = app_state.create_recording_message("pressed");
                 app_state.session_manager.add_log(msg);
             }
@@ -231,54 +829,76 @@ impl KeyboardEventCommand for RecordingKeyPressedCommand {
 impl KeyboardEventCommand for RecordingKeyReleasedCommand {
     fn execute(&self, app_state: &mut AppState) -> bool {
         if app_state.session_manager.recording {
-            app_state.session_manager.stop_recording();
-
-            // Stop audio recording and save files
-            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+            let duration = app_state.session_manager.stop_recording().unwrap_or_default();
+            app_state.session_manager.copy_to_clipboard = self.to_clipboard;
 
             // Process recording with VAD
-            match app_state.audio_recorder.stop_recording() {
-                Ok((raw_audio, segments)) => {
-                    // Save raw recording
-                    let filename = format!("recording_{timestamp}_raw.wav");
-                    match std::fs::write(&filename, &raw_audio) {
-                        Ok(()) => {
-                            app_state.session_manager.add_log(format!(
-                                "Saved raw: {} ({} bytes)",
-                                filename,
-                                raw_audio.len()
-                            ));
-                        }
-                        Err(e) => {
-                            app_state
-                                .session_manager
-                                .add_log(format!("Failed to save raw recording: {e}"));
-                        }
-                    }
-
-                    // Save VAD segments
+            let stopped = app_state.audio_recorder.stop_recording_detailed();
+            // Only beep once the microphone is closed so the cue isn't recorded
+            if app_state.config.sound_on_stop {
+                echoes_audio::play_cue(Cue::Stop);
+            }
+            match stopped {
+                Ok(result) => {
+                    let raw_audio = result.raw_wav;
+                    let segments = result.segments;
                     app_state
                         .session_manager
                         .add_log(format!("Found {} speech segments", segments.len()));
-                    for (i, segment_data) in segments.iter().enumerate() {
-                        let filename = format!("recording_{timestamp}_segment_{i}.wav");
-                        match std::fs::write(&filename, segment_data) {
-                            Ok(()) => {
-                                app_state.session_manager.add_log(format!(
-                                    "Saved segment: {} ({} bytes)",
-                                    filename,
-                                    segment_data.len()
-                                ));
-                            }
-                            Err(e) => {
-                                app_state
-                                    .session_manager
-                                    .add_log(format!("Failed to save {filename}: {e}"));
-                            }
+
+                    // The streaming VAD only reports the tail here; its earlier
+                    // segments are already being transcribed
+                    let speech_ratio = result
+                        .stats
+                        .speech_ratio
+                        .filter(|_| !app_state.transcription_manager.is_streaming());
+                    if let Some(ratio) =
+                        speech_ratio.filter(|&ratio| segments.is_empty() || ratio < app_state.config.min_speech_ratio)
+                    {
+                        app_state.report_no_speech(&format!("speech in {:.0}% of the recording", ratio * 100.0));
+                        let msg = app_state.create_recording_message("released");
+                        app_state.session_manager.add_log(msg);
+                        return true;
+                    }
+
+                    let audio_path = if app_state.config.save_recordings {
+                        save_recording(app_state, &raw_audio, &segments)
+                    } else {
+                        None
+                    };
+
+                    // Transcribe in the background; the UI polls for the result
+                    let duration_ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+                    if app_state.transcription_manager.is_streaming() {
+                        // Earlier segments are already being transcribed; add the tail
+                        for segment in segments {
+                            app_state.transcription_manager.push_segment(segment);
                         }
+                        app_state
+                            .transcription_manager
+                            .finish_streaming(duration_ms, audio_path);
+                    } else if app_state.config.segment_transcription
+                        && duration.as_secs() >= SEGMENT_TRANSCRIPTION_MIN_SECS
+                        && !segments.is_empty()
+                    {
+                        app_state
+                            .session_manager
+                            .add_log(format!("Transcribing {} segments separately...", segments.len()));
+                        app_state.transcription_manager.start_segmented(
+                            &app_state.config,
+                            segments,
+                            duration_ms,
+                            audio_path,
+                        );
+                    } else {
+                        app_state
+                            .transcription_manager
+                            .start(&app_state.config, raw_audio, duration_ms, audio_path);
+                        app_state.session_manager.add_log("Transcribing...");
                     }
                 }
                 Err(e) => {
+                    app_state.transcription_manager.cancel_streaming();
                     app_state
                         .session_manager
                         .add_log(format!("Failed to process recording: {e}"));
@@ -292,12 +912,82 @@ impl KeyboardEventCommand for RecordingKeyReleasedCommand {
     }
 }
 
+/// Writes the raw recording and its speech segments to disk as enabled by
+/// `save_raw` and `save_segments`, returning the path of the raw recording if
+/// it was saved
+fn save_recording(app_state: &mut AppState, raw_audio: &[u8], segments: &[Segment]) -> Option<PathBuf> {
+    let files = RecordingFiles::new(&app_state.config, chrono::Local::now());
+
+    let audio_path = if app_state.config.save_raw {
+        match files.save("raw", raw_audio) {
+            Ok(path) => {
+                app_state.session_manager.add_log(format!(
+                    "Saved raw: {} ({:.1}s, {} bytes)",
+                    path.display(),
+                    app_state.audio_recorder.last_recording_duration().as_secs_f32(),
+                    raw_audio.len()
+                ));
+                Some(path)
+            }
+            Err(e) => {
+                app_state
+                    .session_manager
+                    .add_log(format!("Failed to save raw recording: {e}"));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut saved_segments = 0;
+    if app_state.config.save_segments {
+        for (i, segment) in segments.iter().enumerate() {
+            match files.save(&format!("segment_{i}"), &segment.wav) {
+                Ok(path) => {
+                    saved_segments += 1;
+                    app_state.session_manager.add_log(format!(
+                        "Saved segment: {} ({} bytes)",
+                        path.display(),
+                        segment.wav.len()
+                    ));
+                }
+                Err(e) => {
+                    app_state
+                        .session_manager
+                        .add_log(format!("Failed to save segment {i}: {e}"));
+                }
+            }
+        }
+    }
+
+    let mut artifacts = Vec::new();
+    if audio_path.is_some() {
+        artifacts.push("raw recording".to_string());
+    }
+    if app_state.config.save_segments {
+        artifacts.push(format!("{saved_segments} of {} segments", segments.len()));
+    }
+    if !app_state.config.save_raw && !app_state.config.save_segments {
+        app_state
+            .session_manager
+            .add_log("Recording not saved: save_raw and save_segments are both off");
+    } else if !artifacts.is_empty() {
+        app_state
+            .session_manager
+            .add_log(format!("Saved {}", artifacts.join(" and ")));
+    }
+
+    audio_path
+}
+
 impl KeyboardEventCommand for OtherKeyPressedCommand {
     fn execute(&self, app_state: &mut AppState) -> bool {
         if app_state.session_manager.recording {
             app_state.session_manager.stop_recording();
             // Stop recording without saving
             let _ = app_state.audio_recorder.stop_recording();
+            app_state.transcription_manager.cancel_streaming();
             app_state.session_manager.add_log("Recording cancelled");
         }
         true
@@ -306,10 +996,11 @@ impl KeyboardEventCommand for OtherKeyPressedCommand {
 
 impl KeyboardEventCommand for ListenerErrorCommand {
     fn execute(&self, app_state: &mut AppState) -> bool {
-        app_state.session_manager.set_error(Some(self.0.clone()));
+        let error = input_group_hint().map_or_else(|| self.0.clone(), |hint| format!("{}. {hint}", self.0));
+        app_state.session_manager.set_error(Some(error.clone()));
         app_state
             .session_manager
-            .add_log(format!("Keyboard listener error: {}", self.0));
+            .add_log(format!("Keyboard listener error: {error}"));
         app_state.keyboard_manager.clear_receiver();
         true
     }
@@ -327,11 +1018,66 @@ impl KeyboardEventCommand for ShortcutRecordedCommand {
     }
 }
 
+impl KeyboardEventCommand for KeyObservedCommand {
+    fn execute(&self, app_state: &mut AppState) -> bool {
+        app_state.shortcut_manager.observe_key(self.0);
+        true
+    }
+}
+
+impl KeyboardEventCommand for RetypeLastCommand {
+    fn execute(&self, app_state: &mut AppState) -> bool {
+        if app_state.session_manager.recording || app_state.transcription_manager.is_transcribing() {
+            app_state
+                .session_manager
+                .add_log("Ignoring retype while recording or transcribing");
+            return true;
+        }
+
+        let entry = match app_state.history.recent(1) {
+            Ok(entries) => entries.into_iter().next(),
+            Err(e) => {
+                app_state
+                    .session_manager
+                    .add_log(format!("Failed to read transcript history: {e}"));
+                return true;
+            }
+        };
+        let Some(entry) = entry else {
+            app_state.session_manager.add_log("No transcript to retype yet");
+            return true;
+        };
+
+        let done_message = format!("Retyped last transcript ({} characters)", entry.text.len());
+        if let Err(e) = app_state
+            .output_manager
+            .output(&app_state.config, entry.text, Some(done_message))
+        {
+            app_state.session_manager.add_log(format!("Failed to output text: {e}"));
+        }
+        true
+    }
+}
+
 impl KeyboardEventCommand for RecordingCancelledCommand {
     fn execute(&self, app_state: &mut AppState) -> bool {
-        app_state.session_manager.stop_shortcut_recording();
-        app_state.shortcut_manager.clear_recorded();
-        app_state.session_manager.add_log("Shortcut recording cancelled");
+        // Sent for a hold released before `min_hold_ms` and for the cancel
+        // shortcut: drop the recording and stop the audio stream
+        if app_state.session_manager.recording {
+            app_state.session_manager.stop_recording();
+            let _ = app_state.audio_recorder.stop_recording();
+            app_state.transcription_manager.cancel_streaming();
+            app_state
+                .session_manager
+                .add_log("Recording cancelled - recording discarded");
+            return true;
+        }
+
+        if app_state.session_manager.recording_shortcut {
+            app_state.session_manager.stop_shortcut_recording();
+            app_state.shortcut_manager.clear_recorded();
+            app_state.session_manager.add_log("Shortcut recording cancelled");
+        }
         true
     }
 }
@@ -1,6 +1,10 @@
-use echoes_config::{Config, SttProvider};
+use echoes_config::{
+    ApiKeyProvider, Config, OutputMethod, OutputPlacement, SttProvider, Theme, TranscriptionTask, VadSensitivity,
+};
 use eframe::egui;
 
+use super::key_check_manager::{KeyCheckManager, KeyCheckStatus};
+
 /// Configuration field types for form components
 #[derive(Debug, Clone, Copy)]
 struct FieldConfig<'a> {
@@ -45,6 +49,458 @@ pub fn render_stt_provider_config(ui: &mut egui::Ui, config: &mut Config, mut on
                 changed = true;
             }
         });
+
+        ui.horizontal(|ui| {
+            ui.label("Task:");
+            egui::ComboBox::from_id_salt("transcription_task")
+                .selected_text(transcription_task_label(config.task))
+                .show_ui(ui, |ui| {
+                    for task in [TranscriptionTask::Transcribe, TranscriptionTask::Translate] {
+                        if ui
+                            .selectable_value(&mut config.task, task, transcription_task_label(task))
+                            .clicked()
+                        {
+                            on_change(match task {
+                                TranscriptionTask::Transcribe => "Changed task to transcription",
+                                TranscriptionTask::Translate => "Changed task to translation into English",
+                            });
+                            changed = true;
+                        }
+                    }
+                });
+        });
+        if let Some(issue) = config.task_issue() {
+            ui.colored_label(egui::Color32::YELLOW, format!("⚠️ {issue}"));
+        }
+    });
+
+    changed
+}
+
+const fn transcription_task_label(task: TranscriptionTask) -> &'static str {
+    match task {
+        TranscriptionTask::Transcribe => "Transcribe",
+        TranscriptionTask::Translate => "Translate to English",
+    }
+}
+
+/// Renders the text output method selection UI
+pub fn render_output_method_config(ui: &mut egui::Ui, config: &mut Config, mut on_change: impl FnMut(&str)) -> bool {
+    let mut changed = false;
+
+    ui.group(|ui| {
+        ui.label("Text Output:");
+        ui.horizontal(|ui| {
+            if ui
+                .radio_value(&mut config.output_method, OutputMethod::Type, "Type")
+                .clicked()
+            {
+                on_change("Changed output method to Type");
+                changed = true;
+            }
+            if ui
+                .radio_value(&mut config.output_method, OutputMethod::Paste, "Paste")
+                .clicked()
+            {
+                on_change("Changed output method to Paste");
+                changed = true;
+            }
+        });
+        if ui
+            .checkbox(&mut config.restore_clipboard, "Restore the clipboard after pasting")
+            .on_hover_text("Paste is faster for long transcripts; the clipboard is always restored if pasting fails")
+            .changed()
+        {
+            on_change(if config.restore_clipboard {
+                "Clipboard is restored after pasting"
+            } else {
+                "Pasted text stays on the clipboard"
+            });
+            changed = true;
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .radio_value(
+                    &mut config.output_placement,
+                    OutputPlacement::Insert,
+                    "Insert at cursor",
+                )
+                .on_hover_text("Selected text is replaced wherever the application replaces it on input")
+                .clicked()
+            {
+                on_change("Output inserts at the cursor");
+                changed = true;
+            }
+            if ui
+                .radio_value(
+                    &mut config.output_placement,
+                    OutputPlacement::ReplaceSelection,
+                    "Replace selection",
+                )
+                .on_hover_text("Always pastes, which replaces selected text in every application")
+                .clicked()
+            {
+                on_change("Output replaces the selection");
+                changed = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Wait for focus (ms):");
+            if ui
+                .add(
+                    egui::DragValue::new(&mut config.type_delay_ms)
+                        .range(0..=2000)
+                        .speed(10),
+                )
+                .on_hover_text("Longest wait for the app you dictate into to regain focus before output")
+                .changed()
+            {
+                on_change(&format!("Changed the focus wait to {} ms", config.type_delay_ms));
+                changed = true;
+            }
+        });
+        if ui
+            .checkbox(&mut config.basic_cleanup, "Fix capitalization and final punctuation")
+            .changed()
+        {
+            on_change(if config.basic_cleanup {
+                "Enabled basic transcript clean-up"
+            } else {
+                "Disabled basic transcript clean-up"
+            });
+            changed = true;
+        }
+        changed |= render_duplicate_config(ui, config, &mut on_change);
+    });
+
+    changed
+}
+
+/// Renders the toggle and time window for suppressing repeated transcripts
+fn render_duplicate_config(ui: &mut egui::Ui, config: &mut Config, on_change: &mut impl FnMut(&str)) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        if ui
+            .checkbox(&mut config.suppress_duplicates, "Skip repeated transcripts within")
+            .on_hover_text("Keeps a double-fired shortcut from typing the same text twice")
+            .changed()
+        {
+            on_change(if config.suppress_duplicates {
+                "Enabled duplicate suppression"
+            } else {
+                "Disabled duplicate suppression"
+            });
+            changed = true;
+        }
+        if ui
+            .add_enabled(
+                config.suppress_duplicates,
+                egui::DragValue::new(&mut config.duplicate_window_secs)
+                    .range(1..=600)
+                    .suffix(" s"),
+            )
+            .changed()
+        {
+            on_change(&format!(
+                "Changed the duplicate window to {} s",
+                config.duplicate_window_secs
+            ));
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+/// Renders the VAD sensitivity preset selector, with the individual values
+/// editable for the custom preset
+pub fn render_vad_sensitivity_config(ui: &mut egui::Ui, config: &mut Config, mut on_change: impl FnMut(&str)) -> bool {
+    let mut changed = false;
+
+    ui.group(|ui| {
+        ui.label("Speech Detection Sensitivity:");
+        let current = config.vad_sensitivity;
+        egui::ComboBox::from_id_salt("vad_sensitivity")
+            .selected_text(vad_sensitivity_label(current))
+            .show_ui(ui, |ui| {
+                let custom = VadSensitivity::Custom(current.vad_config());
+                for sensitivity in [
+                    VadSensitivity::Low,
+                    VadSensitivity::Medium,
+                    VadSensitivity::High,
+                    custom,
+                ] {
+                    let selected = vad_sensitivity_label(current) == vad_sensitivity_label(sensitivity);
+                    if ui
+                        .selectable_label(selected, vad_sensitivity_label(sensitivity))
+                        .clicked()
+                        && !selected
+                    {
+                        config.vad_sensitivity = sensitivity;
+                        on_change(&format!(
+                            "Changed speech detection sensitivity to {}",
+                            vad_sensitivity_label(sensitivity)
+                        ));
+                        changed = true;
+                    }
+                }
+            });
+
+        if let VadSensitivity::Custom(vad) = &mut config.vad_sensitivity {
+            let mut custom_changed = false;
+            ui.horizontal(|ui| {
+                ui.label("Threshold:");
+                custom_changed |= ui
+                    .add(egui::DragValue::new(&mut vad.threshold).range(0.0..=1.0).speed(0.01))
+                    .changed();
+                ui.label("Hangover chunks:");
+                custom_changed |= ui
+                    .add(egui::DragValue::new(&mut vad.hangover_frames).range(1..=100))
+                    .changed();
+                ui.label("Min speech samples:");
+                custom_changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut vad.min_speech_samples)
+                            .range(0..=160_000)
+                            .speed(100),
+                    )
+                    .changed();
+                ui.label("Merge gap (ms):");
+                custom_changed |= ui
+                    .add(egui::DragValue::new(&mut vad.merge_gap_ms).range(0..=2000).speed(10))
+                    .changed();
+                ui.label("Trim threshold:");
+                custom_changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut vad.trim_threshold)
+                            .range(0.0..=0.1)
+                            .speed(0.001),
+                    )
+                    .on_hover_text("Quieter edges of speech are cut; lower it if soft word onsets get clipped")
+                    .changed();
+                ui.label("Chunk size:");
+                egui::ComboBox::from_id_salt("vad_chunk_size")
+                    .selected_text(vad.chunk_size.to_string())
+                    .show_ui(ui, |ui| {
+                        for &size in echoes_audio::vad::supported_chunk_sizes(echoes_audio::vad::VAD_SAMPLE_RATE) {
+                            custom_changed |= ui
+                                .selectable_value(&mut vad.chunk_size, size, size.to_string())
+                                .changed();
+                        }
+                    })
+                    .response
+                    .on_hover_text("Samples scored at a time; smaller reacts sooner, larger detects more smoothly");
+            });
+            if custom_changed {
+                on_change("Changed custom speech detection settings");
+                changed = true;
+            }
+        }
+        ui.small("High picks up quiet speech in a quiet room; Low ignores background noise");
+    });
+
+    changed
+}
+
+const fn vad_sensitivity_label(sensitivity: VadSensitivity) -> &'static str {
+    match sensitivity {
+        VadSensitivity::Low => "Low",
+        VadSensitivity::Medium => "Medium",
+        VadSensitivity::High => "High",
+        VadSensitivity::Custom(_) => "Custom",
+    }
+}
+
+/// Renders the desktop notification toggles
+pub fn render_notification_config(ui: &mut egui::Ui, config: &mut Config, mut on_change: impl FnMut(&str)) -> bool {
+    let mut changed = false;
+
+    ui.group(|ui| {
+        if ui
+            .checkbox(
+                &mut config.notify_on_complete,
+                "Notify when done while the window is in the background",
+            )
+            .changed()
+        {
+            on_change(if config.notify_on_complete {
+                "Enabled completion notifications"
+            } else {
+                "Disabled completion notifications"
+            });
+            changed = true;
+        }
+        if ui
+            .checkbox(&mut config.notify_on_no_speech, "Notify when no speech was detected")
+            .changed()
+        {
+            on_change(if config.notify_on_no_speech {
+                "Enabled no-speech notifications"
+            } else {
+                "Disabled no-speech notifications"
+            });
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+/// Renders the toggles for the beeps played when recording starts and stops
+pub fn render_sound_config(ui: &mut egui::Ui, config: &mut Config, mut on_change: impl FnMut(&str)) -> bool {
+    let mut changed = false;
+
+    ui.group(|ui| {
+        ui.horizontal(|ui| {
+            ui.label("Beep when recording:");
+            if ui.checkbox(&mut config.sound_on_start, "starts").changed() {
+                on_change(if config.sound_on_start {
+                    "Enabled the recording start beep"
+                } else {
+                    "Disabled the recording start beep"
+                });
+                changed = true;
+            }
+            if ui.checkbox(&mut config.sound_on_stop, "stops").changed() {
+                on_change(if config.sound_on_stop {
+                    "Enabled the recording stop beep"
+                } else {
+                    "Disabled the recording stop beep"
+                });
+                changed = true;
+            }
+        });
+    });
+
+    changed
+}
+
+/// Renders the streaming and segment transcription toggles
+pub fn render_streaming_config(ui: &mut egui::Ui, config: &mut Config, mut on_change: impl FnMut(&str)) -> bool {
+    let mut changed = false;
+
+    ui.group(|ui| {
+        changed = ui
+            .checkbox(&mut config.streaming_transcription, "Transcribe while recording")
+            .changed();
+        if changed {
+            on_change(if config.streaming_transcription {
+                "Enabled streaming transcription"
+            } else {
+                "Disabled streaming transcription"
+            });
+        }
+        ui.small("Speech segments are transcribed as soon as you pause instead of after recording stops");
+
+        if ui
+            .checkbox(
+                &mut config.segment_transcription,
+                "Transcribe long recordings segment by segment",
+            )
+            .changed()
+        {
+            changed = true;
+            on_change(if config.segment_transcription {
+                "Enabled segment transcription"
+            } else {
+                "Disabled segment transcription"
+            });
+        }
+        ui.small("Recordings over a minute are transcribed one speech segment at a time");
+    });
+
+    changed
+}
+
+/// Renders the theme selector
+pub fn render_theme_config(ui: &mut egui::Ui, config: &mut Config, mut on_change: impl FnMut(&str)) -> bool {
+    let mut changed = false;
+
+    ui.group(|ui| {
+        ui.label("Theme:");
+        egui::ComboBox::from_id_salt("theme")
+            .selected_text(format!("{:?}", config.theme))
+            .show_ui(ui, |ui| {
+                for theme in [Theme::System, Theme::Dark, Theme::Light] {
+                    if ui
+                        .selectable_value(&mut config.theme, theme, format!("{theme:?}"))
+                        .clicked()
+                    {
+                        on_change(&format!("Changed theme to {theme:?}"));
+                        changed = true;
+                    }
+                }
+            });
+    });
+
+    changed
+}
+
+/// Renders the window behaviour toggles
+pub fn render_window_config(ui: &mut egui::Ui, config: &mut Config, mut on_change: impl FnMut(&str)) -> bool {
+    let mut changed = false;
+
+    ui.group(|ui| {
+        ui.label("Window:");
+        if ui
+            .checkbox(
+                &mut config.always_on_top_while_recording,
+                "Always on top while recording",
+            )
+            .on_hover_text("Keep the status visible above the app you are dictating into")
+            .changed()
+        {
+            on_change(if config.always_on_top_while_recording {
+                "Enabled always on top while recording"
+            } else {
+                "Disabled always on top while recording"
+            });
+            changed = true;
+        }
+        if ui
+            .checkbox(&mut config.recording_overlay, "Recording overlay")
+            .on_hover_text("Show a small floating indicator with the elapsed time and level while recording")
+            .changed()
+        {
+            on_change(if config.recording_overlay {
+                "Enabled the recording overlay"
+            } else {
+                "Disabled the recording overlay"
+            });
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+/// Renders the start minimized and launch at login toggles
+pub fn render_startup_config(ui: &mut egui::Ui, config: &mut Config, mut on_change: impl FnMut(&str)) -> bool {
+    let mut changed = false;
+
+    ui.group(|ui| {
+        ui.label("Startup:");
+        if ui
+            .checkbox(&mut config.start_minimized, "Start minimized")
+            .on_hover_text("Start hidden in the tray, or minimized when there is no tray icon")
+            .changed()
+        {
+            on_change(if config.start_minimized {
+                "Enabled starting minimized"
+            } else {
+                "Disabled starting minimized"
+            });
+            changed = true;
+        }
+        if ui.checkbox(&mut config.launch_at_login, "Launch at login").changed() {
+            on_change(if config.launch_at_login {
+                "Enabled launching at login"
+            } else {
+                "Disabled launching at login"
+            });
+            changed = true;
+        }
     });
 
     changed
@@ -84,6 +540,36 @@ fn render_optional_text_field(
     .inner
 }
 
+/// Renders a button checking the API key with the provider, followed by the
+/// outcome of the last check
+fn render_key_check(
+    ui: &mut egui::Ui, key_checks: &mut KeyCheckManager, provider: ApiKeyProvider, key: Option<&str>, base_url: &str,
+) {
+    ui.horizontal(|ui| {
+        let checking = matches!(key_checks.status(provider), Some(KeyCheckStatus::Checking(_)));
+        if ui
+            .add_enabled(key.is_some() && !checking, egui::Button::new("Test"))
+            .on_hover_text("Check the API key with the provider")
+            .clicked()
+        {
+            key_checks.start(provider, key.unwrap_or_default().to_string(), base_url.to_string());
+        }
+
+        match key_checks.status(provider) {
+            Some(KeyCheckStatus::Checking(_)) => {
+                ui.spinner();
+            }
+            Some(KeyCheckStatus::Valid) => {
+                ui.colored_label(egui::Color32::GREEN, "✔ Key is valid");
+            }
+            Some(KeyCheckStatus::Invalid(error)) => {
+                ui.colored_label(egui::Color32::RED, format!("✖ {error}"));
+            }
+            None => {}
+        }
+    });
+}
+
 /// Functional component for optional multiline text field
 fn render_optional_multiline_field(
     ui: &mut egui::Ui, config: &FieldConfig, value: &mut Option<String>, rows: usize, mut on_change: impl FnMut(&str),
@@ -116,10 +602,10 @@ fn render_optional_multiline_field(
 }
 
 /// Renders `OpenAI` STT provider configuration using functional components
-fn render_openai_settings(ui: &mut egui::Ui, config: &mut Config, mut on_change: impl FnMut(&str)) -> bool {
-    let mut changed = false;
-
-    changed |= render_optional_text_field(
+fn render_openai_settings(
+    ui: &mut egui::Ui, config: &mut Config, key_checks: &mut KeyCheckManager, mut on_change: impl FnMut(&str),
+) -> bool {
+    let mut changed = render_optional_text_field(
         ui,
         FieldConfig {
             label: "API Key:",
@@ -131,6 +617,16 @@ fn render_openai_settings(ui: &mut egui::Ui, config: &mut Config, mut on_change:
         true,
         &mut on_change,
     );
+    if changed {
+        key_checks.clear(ApiKeyProvider::OpenAI);
+    }
+    render_key_check(
+        ui,
+        key_checks,
+        ApiKeyProvider::OpenAI,
+        config.openai_api_key.as_deref(),
+        config.openai_base_url.as_deref().unwrap_or("https://api.openai.com/v1"),
+    );
 
     changed |= render_optional_text_field(
         ui,
@@ -175,10 +671,10 @@ fn render_openai_settings(ui: &mut egui::Ui, config: &mut Config, mut on_change:
 }
 
 /// Renders Groq STT provider configuration using functional components
-fn render_groq_settings(ui: &mut egui::Ui, config: &mut Config, mut on_change: impl FnMut(&str)) -> bool {
-    let mut changed = false;
-
-    changed |= render_optional_text_field(
+fn render_groq_settings(
+    ui: &mut egui::Ui, config: &mut Config, key_checks: &mut KeyCheckManager, mut on_change: impl FnMut(&str),
+) -> bool {
+    let mut changed = render_optional_text_field(
         ui,
         FieldConfig {
             label: "API Key:",
@@ -190,6 +686,19 @@ fn render_groq_settings(ui: &mut egui::Ui, config: &mut Config, mut on_change: i
         true,
         &mut on_change,
     );
+    if changed {
+        key_checks.clear(ApiKeyProvider::Groq);
+    }
+    render_key_check(
+        ui,
+        key_checks,
+        ApiKeyProvider::Groq,
+        config.groq_api_key.as_deref(),
+        config
+            .groq_base_url
+            .as_deref()
+            .unwrap_or("https://api.groq.com/openai/v1"),
+    );
 
     changed |= render_optional_text_field(
         ui,
@@ -310,7 +819,7 @@ fn render_local_whisper_settings(ui: &mut egui::Ui, config: &mut Config, mut on_
 
     ui.vertical(|ui| {
         ui.label("Model Path (optional):");
-        ui.small("Custom path to a local model file (leave empty to auto-download)");
+        ui.small("Custom path to a local model file (leave empty to use the one from the Models section)");
 
         let temp_path = String::new();
         let path_to_edit = config
@@ -333,17 +842,122 @@ fn render_local_whisper_settings(ui: &mut egui::Ui, config: &mut Config, mut on_
         }
     });
 
+    changed |= render_whisper_language(ui, &mut config.local_whisper, &mut on_change);
+    changed |= render_whisper_acceleration(ui, &mut config.local_whisper, &mut on_change);
+    changed |= render_whisper_params(ui, &mut config.local_whisper.params, &mut on_change);
+
+    changed
+}
+
+/// Renders the spoken language selector, listing the languages Whisper
+/// supports
+fn render_whisper_language(
+    ui: &mut egui::Ui, whisper: &mut echoes_config::LocalWhisperConfig, mut on_change: impl FnMut(&str),
+) -> bool {
+    let mut changed = false;
+    let selected = echoes_stt::language_name(&whisper.language).unwrap_or(whisper.language.as_str());
+
+    egui::ComboBox::from_label("Spoken language")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            let auto = echoes_stt::languages::AUTO_DETECT;
+            changed |= ui
+                .selectable_value(&mut whisper.language, auto.to_string(), "Auto-detect")
+                .changed();
+            for (code, name) in echoes_stt::supported_languages() {
+                changed |= ui
+                    .selectable_value(&mut whisper.language, (*code).to_string(), *name)
+                    .changed();
+            }
+        });
+    if changed {
+        on_change(&format!("Set the Local Whisper language to {}", whisper.language));
+    }
+    if whisper.model.is_english_only() && whisper.language != "en" {
+        ui.small("English-only models only transcribe English; choose a multilingual model");
+    }
+
+    changed
+}
+
+/// Renders the local Whisper GPU and Core ML toggles
+fn render_whisper_acceleration(
+    ui: &mut egui::Ui, whisper: &mut echoes_config::LocalWhisperConfig, mut on_change: impl FnMut(&str),
+) -> bool {
+    let gpu_changed = ui
+        .checkbox(&mut whisper.use_gpu, "Use GPU")
+        .on_hover_text("Needs a build with a GPU backend; falls back to the CPU otherwise")
+        .changed();
+    if gpu_changed {
+        on_change("Updated Local Whisper GPU setting");
+    }
+
+    let coreml_changed = cfg!(target_os = "macos")
+        && ui
+            .checkbox(&mut whisper.use_coreml, "Use Core ML")
+            .on_hover_text("Needs a build with Core ML and the model's converted encoder")
+            .changed();
+    if coreml_changed {
+        on_change("Updated Local Whisper Core ML setting");
+    }
+
+    gpu_changed || coreml_changed
+}
+
+/// Renders the local Whisper decoding settings
+fn render_whisper_params(
+    ui: &mut egui::Ui, params: &mut echoes_config::WhisperParams, mut on_change: impl FnMut(&str),
+) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Threads:");
+        let mut threads = params.threads.unwrap_or(0);
+        if ui
+            .add(egui::DragValue::new(&mut threads).range(0..=64))
+            .on_hover_text("CPU threads used for inference (0 = automatic)")
+            .changed()
+        {
+            params.threads = (threads > 0).then_some(threads);
+            on_change("Updated Local Whisper thread count");
+            changed = true;
+        }
+    });
+
+    ui.horizontal(|ui| {
+        let mut beam_search = matches!(params.sampling, echoes_config::WhisperSampling::BeamSearch { .. });
+        if ui.checkbox(&mut beam_search, "Beam search").changed() {
+            params.sampling = if beam_search {
+                echoes_config::WhisperSampling::BeamSearch { beam_size: 5 }
+            } else {
+                echoes_config::WhisperSampling::default()
+            };
+            on_change("Updated Local Whisper sampling strategy");
+            changed = true;
+        }
+        if let echoes_config::WhisperSampling::BeamSearch { beam_size } = &mut params.sampling {
+            ui.label("Beam size:");
+            if ui.add(egui::DragValue::new(beam_size).range(1..=16)).changed() {
+                on_change("Updated Local Whisper beam size");
+                changed = true;
+            }
+        }
+    });
+    ui.small("Beam search is more accurate but slower");
+
     changed
 }
 
 /// Renders the STT provider-specific configuration UI
-pub fn render_stt_provider_settings(ui: &mut egui::Ui, config: &mut Config, on_change: impl FnMut(&str)) -> bool {
+pub fn render_stt_provider_settings(
+    ui: &mut egui::Ui, config: &mut Config, key_checks: &mut KeyCheckManager, on_change: impl FnMut(&str),
+) -> bool {
     ui.group(|ui| {
         ui.label("STT Provider Settings:");
 
         match config.stt_provider {
-            SttProvider::OpenAI => render_openai_settings(ui, config, on_change),
-            SttProvider::Groq => render_groq_settings(ui, config, on_change),
+            SttProvider::OpenAI => render_openai_settings(ui, config, key_checks, on_change),
+            SttProvider::Groq => render_groq_settings(ui, config, key_checks, on_change),
             SttProvider::LocalWhisper => render_local_whisper_settings(ui, config, on_change),
         }
     })
@@ -1,36 +1,102 @@
-use std::{sync::mpsc, thread};
+use std::{
+    sync::mpsc,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
 
-use echoes_config::Config;
-use echoes_logging::error;
+use echoes_config::{Config, ConfigWatcher};
+use echoes_logging::{error, warn};
 
 use crate::error::Result;
 
-/// Manages config operations without blocking the UI thread
+/// Quiet period after the last save request before the config is written,
+/// so typing into a text field writes the file once
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Manages config operations without blocking the UI thread.
+///
+/// Saves are written by one background thread, debounced by
+/// [`SAVE_DEBOUNCE`]; dropping the manager writes any pending save before it
+/// returns.
 pub struct ConfigManager {
-    save_tx: mpsc::Sender<Config>,
+    /// `None` once dropping, to close the channel
+    save_tx: Option<mpsc::Sender<Config>>,
+    save_thread: Option<JoinHandle<()>>,
+    reload_rx: mpsc::Receiver<Config>,
+    _watcher: Option<ConfigWatcher>,
+    /// Config as last queued for saving or reloaded, to describe what the
+    /// next save changes
+    saved: Config,
 }
 
 impl ConfigManager {
-    pub fn new() -> Self {
+    pub fn new(config: &Config) -> Self {
         let (save_tx, save_rx) = mpsc::channel::<Config>();
 
         // Spawn a background thread to handle config saves
-        thread::spawn(move || {
-            while let Ok(config) = save_rx.recv() {
+        let spawned = thread::Builder::new().name("echoes-config-save".into()).spawn(move || {
+            let _span = tracing::info_span!("config_save").entered();
+            while let Ok(mut config) = save_rx.recv() {
+                // Only write the latest of saves queued in quick succession. A
+                // closed channel ends the wait, so shutdown flushes right away.
+                while let Ok(newer) = save_rx.recv_timeout(SAVE_DEBOUNCE) {
+                    config = newer;
+                }
                 if let Err(e) = config.save() {
                     error!("Failed to save config: {e}");
                 }
             }
         });
+        let save_thread = match spawned {
+            Ok(thread) => Some(thread),
+            Err(e) => {
+                error!("Failed to start config save thread, settings won't be saved: {e}");
+                None
+            }
+        };
+
+        // Watch for external edits to the config file
+        let (reload_tx, reload_rx) = mpsc::channel::<Config>();
+        let watcher = match Config::watch(move |config| {
+            let _ = reload_tx.send(config);
+        }) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                warn!("Config hot-reload unavailable: {e}");
+                None
+            }
+        };
+
+        Self {
+            save_tx: Some(save_tx),
+            save_thread,
+            reload_rx,
+            _watcher: watcher,
+            saved: config.clone(),
+        }
+    }
 
-        Self { save_tx }
+    /// Returns the most recent externally edited config, if any (non-blocking)
+    pub fn try_recv_reload(&mut self) -> Option<Config> {
+        let config = self.reload_rx.try_iter().last()?;
+        self.saved = config.clone();
+        Some(config)
     }
 
-    /// Queue a config save operation (non-blocking)
-    pub fn save_async(&self, config: Config) {
-        if let Err(e) = self.save_tx.send(config) {
-            error!("Failed to queue config save: {e}");
+    /// Queue a config save operation (non-blocking). It is written once no
+    /// other save was queued for [`SAVE_DEBOUNCE`]. Returns the settings
+    /// changed since the previous save, see [`Config::diff`].
+    pub fn save_async(&mut self, config: Config) -> Vec<String> {
+        let changes = self.saved.diff(&config);
+        self.saved = config.clone();
+        if self
+            .save_tx
+            .as_ref()
+            .is_some_and(|save_tx| save_tx.send(config).is_err())
+        {
+            error!("Failed to queue config save: the save thread has stopped");
         }
+        changes
     }
 
     /// Synchronous save for critical operations
@@ -44,6 +110,17 @@ impl ConfigManager {
 
 impl Default for ConfigManager {
     fn default() -> Self {
-        Self::new()
+        Self::new(&Config::default())
+    }
+}
+
+impl Drop for ConfigManager {
+    fn drop(&mut self) {
+        // Closing the channel makes the save thread write a pending save
+        // without waiting out the debounce
+        self.save_tx = None;
+        if self.save_thread.take().is_some_and(|thread| thread.join().is_err()) {
+            error!("Config save thread panicked, the last settings may not be saved");
+        }
     }
 }
@@ -0,0 +1,187 @@
+//! Local control socket through which scripts drive the app
+//!
+//! Clients connect to the `control_socket` path (a Unix socket, or a named
+//! pipe such as `\\.\pipe\echoes` on Windows) and write one JSON command per
+//! line. Each line is answered with `{"ok":true}` once the command is queued,
+//! or `{"ok":false,"error":"..."}` if it couldn't be parsed.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use eframe::egui;
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Commands accepted on the control socket, e.g. `{"cmd":"start"}` or
+/// `{"cmd":"transcribe_file","path":"/tmp/memo.wav"}`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Start recording, like pressing the recording shortcut
+    Start,
+    /// Stop recording and transcribe it, like releasing the shortcut
+    Stop,
+    /// Start recording, or stop it when already recording
+    Toggle,
+    /// Discard the current recording
+    Cancel,
+    /// Transcribe an audio file, like dropping it on the window
+    TranscribeFile { path: PathBuf },
+}
+
+/// Accepts control connections on the tokio runtime and queues their commands
+/// for the UI thread
+pub struct ControlServer {
+    command_rx: mpsc::Receiver<ControlCommand>,
+    task: tokio::task::JoinHandle<()>,
+    path: PathBuf,
+}
+
+impl ControlServer {
+    /// Listens on `path`. Queued commands wake the UI via `ctx`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no async runtime or the socket can't be
+    /// created, e.g. because another instance is listening on it.
+    pub fn start(path: &Path, ctx: &egui::Context) -> Result<Self, String> {
+        let runtime = tokio::runtime::Handle::try_current()
+            .map_err(|_| "No async runtime available for the control socket".to_string())?;
+        let _guard = runtime.enter();
+        let (command_tx, command_rx) = mpsc::channel();
+        let task = listen(path, command_tx, ctx.clone())?;
+
+        Ok(Self {
+            command_rx,
+            task,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Drains the commands received since the last call
+    pub fn poll(&self) -> Vec<ControlCommand> {
+        self.command_rx.try_iter().collect()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.task.abort();
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+fn listen(
+    path: &Path, command_tx: mpsc::Sender<ControlCommand>, ctx: egui::Context,
+) -> Result<tokio::task::JoinHandle<()>, String> {
+    // A socket file left behind by a crashed instance refuses connections
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(format!("{} is already in use by another instance", path.display()));
+        }
+        std::fs::remove_file(path).map_err(|e| format!("Failed to remove stale {}: {e}", path.display()))?;
+    }
+    let listener =
+        tokio::net::UnixListener::bind(path).map_err(|e| format!("Failed to listen on {}: {e}", path.display()))?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve(stream, command_tx.clone(), ctx.clone()));
+                }
+                Err(e) => tracing::warn!("Failed to accept a control connection: {e}"),
+            }
+        }
+    }))
+}
+
+#[cfg(windows)]
+fn listen(
+    path: &Path, command_tx: mpsc::Sender<ControlCommand>, ctx: egui::Context,
+) -> Result<tokio::task::JoinHandle<()>, String> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let name = path.as_os_str().to_os_string();
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&name)
+        .map_err(|e| format!("Failed to create pipe {}: {e}", path.display()))?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            if let Err(e) = server.connect().await {
+                tracing::warn!("Failed to accept a control connection: {e}");
+                continue;
+            }
+            // Create the next instance before serving, so clients never find
+            // the pipe missing
+            let connected = match ServerOptions::new().create(&name) {
+                Ok(next) => std::mem::replace(&mut server, next),
+                Err(e) => {
+                    tracing::error!("Failed to create the next control pipe instance: {e}");
+                    return;
+                }
+            };
+            tokio::spawn(serve(connected, command_tx.clone(), ctx.clone()));
+        }
+    }))
+}
+
+/// Reads commands from one connection until it closes, answering each line
+async fn serve<S>(stream: S, command_tx: mpsc::Sender<ControlCommand>, ctx: egui::Context)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<ControlCommand>(&line) {
+            Ok(command) => {
+                tracing::debug!("Control command: {command:?}");
+                if command_tx.send(command).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+                serde_json::json!({ "ok": true })
+            }
+            Err(e) => serde_json::json!({ "ok": false, "error": e.to_string() }),
+        };
+        if writer.write_all(format!("{reply}\n").as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            serde_json::from_str::<ControlCommand>(r#"{"cmd":"start"}"#).unwrap(),
+            ControlCommand::Start
+        );
+        assert_eq!(
+            serde_json::from_str::<ControlCommand>(r#"{"cmd":"transcribe_file","path":"/tmp/memo.wav"}"#).unwrap(),
+            ControlCommand::TranscribeFile {
+                path: PathBuf::from("/tmp/memo.wav")
+            }
+        );
+        assert!(serde_json::from_str::<ControlCommand>(r#"{"cmd":"explode"}"#).is_err());
+        assert!(serde_json::from_str::<ControlCommand>(r#"{"cmd":"transcribe_file"}"#).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+use eframe::egui;
+
+use crate::history::HistoryEntry;
+
+/// Renders recent transcripts, newest first, each with a copy button
+pub fn render_history(ui: &mut egui::Ui, entries: &[HistoryEntry]) {
+    ui.collapsing("History", |ui| {
+        if entries.is_empty() {
+            ui.small("No transcripts yet");
+            return;
+        }
+
+        egui::ScrollArea::vertical()
+            .id_salt("history_scroll")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for entry in entries {
+                    ui.horizontal(|ui| {
+                        if ui.button("Copy").clicked() {
+                            ui.ctx().copy_text(entry.text.clone());
+                        }
+                        ui.small(format!(
+                            "{} · {}",
+                            entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                            entry.provider
+                        ));
+                    });
+                    ui.label(&entry.text);
+                    ui.separator();
+                }
+            });
+    });
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use echoes_config::ApiKeyProvider;
+use tokio::sync::oneshot::{self, error::TryRecvError};
+
+use crate::{error::EchoesError, key_check};
+
+/// Outcome of the latest key check of a provider
+pub enum KeyCheckStatus {
+    Checking(oneshot::Receiver<Result<(), String>>),
+    Valid,
+    Invalid(String),
+}
+
+/// Checks API keys with their provider off the UI thread
+pub struct KeyCheckManager {
+    checks: HashMap<ApiKeyProvider, KeyCheckStatus>,
+}
+
+impl KeyCheckManager {
+    pub fn new() -> Self {
+        Self { checks: HashMap::new() }
+    }
+
+    pub fn is_checking(&self) -> bool {
+        self.checks
+            .values()
+            .any(|status| matches!(status, KeyCheckStatus::Checking(_)))
+    }
+
+    pub fn status(&self, provider: ApiKeyProvider) -> Option<&KeyCheckStatus> {
+        self.checks.get(&provider)
+    }
+
+    /// Forgets the outcome for `provider`, e.g. after its key was edited
+    pub fn clear(&mut self, provider: ApiKeyProvider) {
+        self.checks.remove(&provider);
+    }
+
+    /// Checks `key` against the `OpenAI`-compatible API at `base_url` on the
+    /// tokio runtime, replacing any earlier outcome for `provider`
+    pub fn start(&mut self, provider: ApiKeyProvider, key: String, base_url: String) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            self.checks.insert(
+                provider,
+                KeyCheckStatus::Invalid("No async runtime available for the check".into()),
+            );
+            return;
+        };
+
+        let (result_tx, result_rx) = oneshot::channel();
+        runtime.spawn(async move {
+            let result = match provider {
+                ApiKeyProvider::OpenAI => key_check::validate_openai_key(&key, &base_url).await,
+                ApiKeyProvider::Groq => key_check::validate_groq_key(&key, &base_url).await,
+                ApiKeyProvider::Gemini => Err(EchoesError::Other("Checking Gemini keys is not supported".into())),
+            };
+            let _ = result_tx.send(result.map_err(|e| e.to_string()));
+        });
+        self.checks.insert(provider, KeyCheckStatus::Checking(result_rx));
+    }
+
+    /// Records the checks that finished since the last call (non-blocking);
+    /// returns whether any did
+    pub fn poll(&mut self) -> bool {
+        let mut finished = false;
+        for status in self.checks.values_mut() {
+            let KeyCheckStatus::Checking(result_rx) = status else {
+                continue;
+            };
+            let result = match result_rx.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => continue,
+                Err(TryRecvError::Closed) => Err("Key check ended unexpectedly".into()),
+            };
+            *status = match result {
+                Ok(()) => KeyCheckStatus::Valid,
+                Err(e) => KeyCheckStatus::Invalid(e),
+            };
+            finished = true;
+        }
+        finished
+    }
+}
+
+impl Default for KeyCheckManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
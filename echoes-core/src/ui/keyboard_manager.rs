@@ -1,13 +1,31 @@
-use std::sync::mpsc;
+use std::{
+    sync::mpsc,
+    time::{Duration, Instant},
+};
 
 use echoes_config::RecordingShortcut;
-use echoes_keyboard::{KeyboardEvent, KeyboardListener};
+use echoes_keyboard::{KeyboardEvent, KeyboardListener, RestartPolicy};
 
-/// Manages keyboard events and listener
+/// Restart policy for transient listener failures before surfacing the error
+const LISTENER_RESTART_POLICY: RestartPolicy = RestartPolicy {
+    max_restarts: 3,
+    backoff: Duration::from_secs(1),
+};
+
+/// How often to check whether accessibility permission was granted while the
+/// app is running without it
+pub const PERMISSION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Manages keyboard events and listener.
+///
+/// At most one listener is active at a time: [`KeyboardManager::init`] stops
+/// the previous listener before starting a new one, and dropping the manager
+/// stops the current one, so re-initializing never produces duplicate events.
 pub struct KeyboardManager {
     pub listener: Option<std::sync::Arc<KeyboardListener>>,
     pub event_rx: Option<mpsc::Receiver<KeyboardEvent>>,
     pub permissions_granted: bool,
+    last_permission_check: Option<Instant>,
 }
 
 impl KeyboardManager {
@@ -16,17 +34,22 @@ impl KeyboardManager {
             listener: None,
             event_rx: None,
             permissions_granted: false,
+            last_permission_check: None,
         }
     }
 
-    pub fn init(&mut self, shortcut: RecordingShortcut) -> Result<(), String> {
+    pub fn init(&mut self, shortcut: RecordingShortcut, consume_trigger: bool) -> Result<(), String> {
         match echoes_platform::ensure_permissions() {
             Ok(true) => {
                 self.permissions_granted = true;
 
+                // Silence any previous listener so reconfiguring doesn't stack duplicates
+                self.stop();
+
                 // Set up keyboard listener
                 let (tx, rx) = mpsc::channel();
-                let listener = KeyboardListener::new(tx, shortcut);
+                let listener = KeyboardListener::with_restart_policy(tx, shortcut, LISTENER_RESTART_POLICY);
+                listener.set_consume_trigger(consume_trigger);
                 let listener_arc = std::sync::Arc::new(listener);
 
                 if let Err(e) = listener_arc.start_listening() {
@@ -48,12 +71,58 @@ impl KeyboardManager {
         }
     }
 
+    /// Re-checks accessibility permission without prompting, at most every
+    /// [`PERMISSION_POLL_INTERVAL`] unless `force` is set. Returns true once
+    /// permission was granted since [`KeyboardManager::init`] failed for the
+    /// lack of it; the listener still needs to be initialized. Does nothing
+    /// while permission is granted.
+    pub fn permission_newly_granted(&mut self, force: bool) -> bool {
+        if self.permissions_granted {
+            return false;
+        }
+        let now = Instant::now();
+        if !force
+            && self
+                .last_permission_check
+                .is_some_and(|last| now.duration_since(last) < PERMISSION_POLL_INTERVAL)
+        {
+            return false;
+        }
+        self.last_permission_check = Some(now);
+        echoes_platform::check_accessibility_permissions(false)
+    }
+
+    /// Stops the current listener, if any
+    pub fn stop(&mut self) {
+        if let Some(listener) = self.listener.take() {
+            listener.stop();
+        }
+    }
+
     pub fn update_shortcut(&self, shortcut: RecordingShortcut) {
         if let Some(listener) = &self.listener {
             listener.update_shortcut(shortcut);
         }
     }
 
+    pub fn set_cancel_shortcut(&self, cancel_shortcut: Option<RecordingShortcut>) {
+        if let Some(listener) = &self.listener {
+            listener.set_cancel_shortcut(cancel_shortcut);
+        }
+    }
+
+    pub fn set_retype_shortcut(&self, retype_shortcut: Option<RecordingShortcut>) {
+        if let Some(listener) = &self.listener {
+            listener.set_retype_shortcut(retype_shortcut);
+        }
+    }
+
+    pub fn set_min_hold_ms(&self, min_hold_ms: u64) {
+        if let Some(listener) = &self.listener {
+            listener.set_min_hold_ms(min_hold_ms);
+        }
+    }
+
     pub fn start_recording_shortcut(&self) {
         if let Some(listener) = &self.listener {
             listener.start_recording_shortcut();
@@ -66,6 +135,12 @@ impl KeyboardManager {
         }
     }
 
+    pub fn set_key_observer(&self, enabled: bool) {
+        if let Some(listener) = &self.listener {
+            listener.set_key_observer(enabled);
+        }
+    }
+
     pub fn try_recv_event(&self) -> Vec<KeyboardEvent> {
         let mut events = Vec::new();
         if let Some(rx) = &self.event_rx {
@@ -86,3 +161,39 @@ impl Default for KeyboardManager {
         Self::new()
     }
 }
+
+impl Drop for KeyboardManager {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// A listener that was never started, so no OS hook is installed
+    fn idle_listener() -> Arc<KeyboardListener> {
+        let (tx, _rx) = mpsc::channel();
+        Arc::new(KeyboardListener::new(tx, RecordingShortcut::default()))
+    }
+
+    #[test]
+    fn test_replacing_or_dropping_stops_the_listener() {
+        let first = idle_listener();
+        let mut manager = KeyboardManager::new();
+        manager.listener = Some(first.clone());
+
+        // What `init` does before starting the next listener
+        manager.stop();
+        assert!(first.is_stopped());
+        assert!(manager.listener.is_none());
+
+        let second = idle_listener();
+        manager.listener = Some(second.clone());
+        drop(manager);
+        assert!(second.is_stopped());
+    }
+}
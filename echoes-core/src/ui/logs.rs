@@ -3,6 +3,10 @@ use eframe::egui;
 /// Renders the logs section UI
 pub fn render_logs(ui: &mut egui::Ui, logs: &[String]) {
     ui.collapsing("Logs", |ui| {
+        if ui.button("Copy").on_hover_text("Copy all log lines").clicked() {
+            ui.ctx().copy_text(logs.join("\n"));
+        }
+
         egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
             for log in logs.iter().rev() {
                 ui.label(log);
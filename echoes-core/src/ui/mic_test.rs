@@ -0,0 +1,72 @@
+use echoes_audio::{AudioRecorder, NO_SIGNAL_DBFS, RecordingStats};
+use tokio::sync::oneshot::{self, error::TryRecvError};
+
+/// How long the microphone test records
+const TEST_DURATION_MS: u32 = 1500;
+
+/// Records a short test capture on a background thread and reports whether
+/// the microphone picked anything up
+pub struct MicTest {
+    result_rx: Option<oneshot::Receiver<Result<RecordingStats, String>>>,
+    /// Outcome of the last finished test, shown next to the button
+    pub outcome: Option<String>,
+}
+
+impl MicTest {
+    pub const fn new() -> Self {
+        Self {
+            result_rx: None,
+            outcome: None,
+        }
+    }
+
+    pub const fn is_running(&self) -> bool {
+        self.result_rx.is_some()
+    }
+
+    /// Starts a test capture unless one is running. It uses its own recorder,
+    /// as the stream of the main one must stay on the UI thread.
+    pub fn start(&mut self) {
+        if self.is_running() {
+            return;
+        }
+        let (result_tx, result_rx) = oneshot::channel();
+        // A failed spawn drops the sender, which `poll` reports as a failure
+        let _ = std::thread::Builder::new()
+            .name("echoes-mic-test".into())
+            .spawn(move || {
+                let _span = tracing::info_span!("mic_test").entered();
+                let result = AudioRecorder::new_without_vad()
+                    .test_capture(TEST_DURATION_MS)
+                    .map_err(|e| e.to_string());
+                let _ = result_tx.send(result);
+            });
+        self.result_rx = Some(result_rx);
+        self.outcome = None;
+    }
+
+    /// Stores the outcome once the test finished; returns whether it did
+    pub fn poll(&mut self) -> bool {
+        let Some(result_rx) = &mut self.result_rx else {
+            return false;
+        };
+        let result = match result_rx.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Closed) => Err("Microphone test ended unexpectedly".into()),
+        };
+        self.result_rx = None;
+        self.outcome = Some(match result {
+            Ok(stats) if stats.peak_dbfs() < NO_SIGNAL_DBFS => "No signal detected.".to_string(),
+            Ok(stats) => format!("Detected input: {:.0} dBFS", stats.peak_dbfs()),
+            Err(e) => format!("Microphone test failed: {e}"),
+        });
+        true
+    }
+}
+
+impl Default for MicTest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
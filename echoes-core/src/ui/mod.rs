@@ -1,4 +1,4 @@
-use echoes_config::Config;
+use echoes_config::{Config, Theme};
 use eframe::egui;
 use tracing::info;
 
@@ -8,28 +8,142 @@ use shortcut_editor::ShortcutEditorAction;
 mod app_state;
 mod config;
 mod config_manager;
+mod control_server;
+mod history;
+mod key_check_manager;
 mod keyboard_manager;
 mod logs;
+mod mic_test;
+mod model_manager;
+mod models;
+mod output_manager;
+mod overlay;
 mod session_manager;
 mod shortcut_manager;
 mod shortcuts;
 mod status;
 mod system_manager;
+mod transcription_manager;
+mod window_state;
 
 use app_state::AppState;
+use system_manager::TrayCommand;
+use window_state::WindowTracker;
 
 pub struct WhispoApp {
     state: AppState,
+    window_tracker: WindowTracker,
+    applied_theme: Option<Theme>,
+    /// Whether the window was last set to always on top
+    on_top: bool,
 }
 
 impl WhispoApp {
     #[must_use]
-    pub fn new(_cc: &eframe::CreationContext<'_>, config: Config) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, config: Config) -> Self {
         info!("WhispoApp::new called");
         info!("About to create AppState");
-        let state = AppState::new(config);
+        let mut state = AppState::new(config);
         info!("AppState created successfully");
-        Self { state }
+
+        if let Err(e) = state.system_manager.init_tray(&cc.egui_ctx) {
+            state.add_log(format!("Tray icon unavailable: {e}"));
+        }
+        state.start_control_server(&cc.egui_ctx);
+        if state.config.start_minimized {
+            // GTK tray events are only pumped from update, which doesn't run
+            // for a hidden window, so Linux minimizes instead
+            if state.system_manager.has_tray() && !cfg!(target_os = "linux") {
+                cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+            } else {
+                cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+            }
+        }
+
+        let mut app = Self {
+            state,
+            window_tracker: WindowTracker::new(),
+            applied_theme: None,
+            on_top: false,
+        };
+        app.apply_theme(&cc.egui_ctx);
+        app
+    }
+
+    /// Applies the configured theme when it changed since the last frame
+    fn apply_theme(&mut self, ctx: &egui::Context) {
+        let theme = self.state.config.theme;
+        if self.applied_theme == Some(theme) {
+            return;
+        }
+        self.applied_theme = Some(theme);
+
+        let visuals = match theme {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+            // Unknown preference falls back to dark
+            Theme::System => match dark_light::detect() {
+                dark_light::Mode::Light => egui::Visuals::light(),
+                dark_light::Mode::Dark | dark_light::Mode::Default => egui::Visuals::dark(),
+            },
+        };
+        ctx.set_visuals(visuals);
+    }
+
+    /// Remembers the window geometry in the config whenever it changes
+    fn track_window(&mut self, ctx: &egui::Context) {
+        if let Some(window) = self.window_tracker.update(ctx, self.state.config.window) {
+            self.state.config.window = Some(window);
+            // Geometry changes on every move; save without logging them
+            let _ = self.state.config_manager.save_async(self.state.config.clone());
+        }
+    }
+
+    /// Raises the window above others while recording when configured, and
+    /// restores the normal level once recording stops
+    fn apply_window_level(&mut self, ctx: &egui::Context) {
+        let on_top = self.state.config.always_on_top_while_recording && self.state.recording();
+        if self.on_top == on_top {
+            return;
+        }
+        self.on_top = on_top;
+
+        let level = if on_top {
+            egui::WindowLevel::AlwaysOnTop
+        } else {
+            egui::WindowLevel::Normal
+        };
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+    }
+
+    /// Transcribes the first audio file dropped on the window
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) -> bool {
+        let path = ctx.input(|i| i.raw.dropped_files.iter().find_map(|file| file.path.clone()));
+        let Some(path) = path else {
+            return false;
+        };
+        self.state.transcribe_file(path);
+        true
+    }
+
+    fn handle_tray_commands(&mut self, ctx: &egui::Context) -> bool {
+        let commands = self.state.system_manager.poll_tray();
+        let needs_repaint = !commands.is_empty();
+
+        for command in commands {
+            match command {
+                TrayCommand::Open => {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                }
+                TrayCommand::ToggleRecording => self.state.toggle_recording(),
+                TrayCommand::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            }
+        }
+
+        self.state.system_manager.update_tray(self.state.recording());
+        needs_repaint
     }
 
     fn handle_shortcut_action(&mut self, action: ShortcutEditorAction) {
@@ -42,19 +156,22 @@ impl WhispoApp {
             }
             ShortcutEditorAction::Reset => {
                 self.state.add_log("Shortcut reset to default (Ctrl)");
-                self.state.config_manager.save_async(self.state.config.clone());
+                self.state.save_config();
             }
             ShortcutEditorAction::None => {}
         }
     }
 
     fn process_recorded_shortcut(&mut self, ui: &mut egui::Ui) {
-        if let Some(recorded) = self.state.recorded_shortcut() {
+        if let Some(mut recorded) = self.state.recorded_shortcut() {
+            if !self.state.config.match_scan_codes {
+                recorded.scan_code = None;
+            }
             match recorded.validate() {
                 Ok(()) => {
                     self.state.config.recording_shortcut = recorded;
                     self.state.add_log("Applied new shortcut");
-                    self.state.config_manager.save_async(self.state.config.clone());
+                    self.state.save_config();
                     self.state.update_shortcut_listener();
                 }
                 Err(err) => {
@@ -70,10 +187,54 @@ impl eframe::App for WhispoApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Handle keyboard events
         let needs_keyboard_repaint = self.state.handle_keyboard_events();
+        let needs_reload_repaint = self.state.handle_config_reload();
+        let needs_tray_repaint = self.handle_tray_commands(ctx);
+        let needs_control_repaint = self.state.handle_control_commands();
+        let needs_drop_repaint = self.handle_dropped_files(ctx);
+        let needs_reconcile_repaint = self.state.reconcile_recording_state();
+        let needs_overflow_repaint = self.state.check_buffer_overflow();
+        let needs_segment_repaint = self.state.poll_streaming_segments();
+        let window_focused = ctx.input(|i| i.focused);
+        let needs_transcription_repaint = self.state.poll_transcription(window_focused);
+        let needs_output_repaint = self.state.poll_output();
+        let gained_focus = ctx.input(|i| i.events.contains(&egui::Event::WindowFocused(true)));
+        let needs_permission_repaint = self.state.poll_permissions(gained_focus);
+        let needs_model_repaint = self.state.poll_model_downloads();
+        let needs_mic_test_repaint = self.state.mic_test.poll();
+        let needs_key_check_repaint = self.state.key_checks.poll();
+        self.apply_theme(ctx);
+        self.apply_window_level(ctx);
+        self.track_window(ctx);
 
         // Only request repaint when recording or there are pending events
-        if self.state.recording() || self.state.recording_shortcut() || needs_keyboard_repaint {
+        if self.state.recording()
+            || self.state.transcribing()
+            || self.state.recording_shortcut()
+            || needs_keyboard_repaint
+            || needs_reload_repaint
+            || needs_tray_repaint
+            || needs_control_repaint
+            || needs_drop_repaint
+            || needs_reconcile_repaint
+            || needs_overflow_repaint
+            || needs_segment_repaint
+            || needs_transcription_repaint
+            || needs_output_repaint
+            || needs_permission_repaint
+            || needs_model_repaint
+            || self.state.model_manager.is_downloading()
+            || needs_mic_test_repaint
+            || self.state.mic_test.is_running()
+            || needs_key_check_repaint
+            || self.state.key_checks.is_checking()
+        {
             ctx.request_repaint_after(std::time::Duration::from_millis(50));
+        } else if cfg!(target_os = "linux") && self.state.system_manager.has_tray() {
+            // GTK tray events are only pumped from update, so keep polling while idle
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        } else if !self.state.permissions_granted() {
+            // Keep re-checking so the listener starts once permission is granted
+            ctx.request_repaint_after(keyboard_manager::PERMISSION_POLL_INTERVAL);
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -102,6 +263,18 @@ impl eframe::App for WhispoApp {
 
             // Recording status
             status::render_status_section(ui, self.state.recording(), self.state.permissions_granted());
+            status::render_waveform(ui, &self.state.audio_recorder.recent_samples());
+            let mut test_microphone = false;
+            status::render_mic_test(ui, &self.state.mic_test, !self.state.recording(), || {
+                test_microphone = true;
+            });
+            if test_microphone {
+                self.state.mic_test.start();
+            }
+            if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+                ui.label("Drop a WAV or MP3 file to transcribe it");
+            }
+            self.render_transcription(ui);
 
             ui.separator();
 
@@ -112,14 +285,46 @@ impl eframe::App for WhispoApp {
 
             ui.separator();
 
+            // Transcript history
+            history::render_history(ui, &self.state.recent_transcripts);
+
+            ui.separator();
+
             // Logs section
             logs::render_logs(ui, self.state.logs());
         });
+
+        let overlay_started = self
+            .state
+            .session_manager
+            .recording_started
+            .filter(|_| self.state.config.recording_overlay);
+        if let Some(started) = overlay_started {
+            overlay::show_recording_overlay(
+                ctx,
+                started.elapsed(),
+                &self.state.audio_recorder.recent_samples(),
+                &shortcuts::format_shortcut(&self.state.config.recording_shortcut),
+            );
+        }
     }
 }
 
 // UI rendering methods
 impl WhispoApp {
+    fn render_transcription(&mut self, ui: &mut egui::Ui) {
+        let mut export = None;
+        status::render_transcription(
+            ui,
+            &self.state.transcription_manager.state,
+            self.state.subtitle_source.is_some(),
+            |format| export = Some(format),
+        );
+        if let Some(format) = export {
+            self.state.export_subtitles(format);
+        }
+    }
+
     fn render_configuration(&mut self, ui: &mut egui::Ui) {
         // STT Provider config
         let mut stt_message = None;
@@ -129,28 +334,166 @@ impl WhispoApp {
             if let Some(msg) = stt_message {
                 self.state.add_log(msg);
             }
-            self.state.config_manager.save_async(self.state.config.clone());
+            self.state.save_config();
         }
 
         ui.add_space(10.0);
 
         // STT Provider-specific settings
         let mut provider_message = None;
-        if self::config::render_stt_provider_settings(ui, &mut self.state.config, |msg| {
+        if self::config::render_stt_provider_settings(ui, &mut self.state.config, &mut self.state.key_checks, |msg| {
             provider_message = Some(msg.to_string());
         }) {
             if let Some(msg) = provider_message {
                 self.state.add_log(msg);
             }
-            self.state.config_manager.save_async(self.state.config.clone());
+            self.state.save_config();
         }
 
         ui.add_space(10.0);
 
+        // Local Whisper models
+        let mut download = None;
+        let mut delete = None;
+        models::render_models(
+            ui,
+            &self.state.model_manager,
+            |model| download = Some(model),
+            |model| delete = Some(model),
+        );
+        if let Some(model) = download {
+            self.state.download_model(model);
+        }
+        if let Some(model) = delete {
+            self.state.delete_model(&model);
+        }
+
+        ui.add_space(10.0);
+
+        // Speech detection
+        let mut vad_message = None;
+        if self::config::render_vad_sensitivity_config(ui, &mut self.state.config, |msg| {
+            vad_message = Some(msg.to_string());
+        }) {
+            if let Some(msg) = vad_message {
+                self.state.add_log(msg);
+            }
+            self.state.save_config();
+        }
+
+        ui.add_space(10.0);
+
+        // Text output method
+        let mut output_message = None;
+        if self::config::render_output_method_config(ui, &mut self.state.config, |msg| {
+            output_message = Some(msg.to_string());
+        }) {
+            if let Some(msg) = output_message {
+                self.state.add_log(msg);
+            }
+            self.state.save_config();
+        }
+
+        ui.add_space(10.0);
+
+        // Notifications
+        let mut notification_message = None;
+        if self::config::render_notification_config(ui, &mut self.state.config, |msg| {
+            notification_message = Some(msg.to_string());
+        }) {
+            if let Some(msg) = notification_message {
+                self.state.add_log(msg);
+            }
+            self.state.save_config();
+        }
+
+        ui.add_space(10.0);
+
+        // Sound cues
+        let mut sound_message = None;
+        if self::config::render_sound_config(ui, &mut self.state.config, |msg| {
+            sound_message = Some(msg.to_string());
+        }) {
+            if let Some(msg) = sound_message {
+                self.state.add_log(msg);
+            }
+            self.state.save_config();
+        }
+
+        ui.add_space(10.0);
+
+        // Streaming transcription
+        let mut streaming_message = None;
+        if self::config::render_streaming_config(ui, &mut self.state.config, |msg| {
+            streaming_message = Some(msg.to_string());
+        }) {
+            if let Some(msg) = streaming_message {
+                self.state.add_log(msg);
+            }
+            self.state.save_config();
+        }
+
+        ui.add_space(10.0);
+
+        self.render_app_configuration(ui);
+        self.render_shortcut_configuration(ui);
+    }
+
+    /// Appearance, window and startup settings
+    fn render_app_configuration(&mut self, ui: &mut egui::Ui) {
+        // Theme
+        let mut theme_message = None;
+        if self::config::render_theme_config(ui, &mut self.state.config, |msg| {
+            theme_message = Some(msg.to_string());
+        }) {
+            if let Some(msg) = theme_message {
+                self.state.add_log(msg);
+            }
+            self.state.save_config();
+        }
+
+        ui.add_space(10.0);
+
+        // Window behaviour
+        let mut window_message = None;
+        if self::config::render_window_config(ui, &mut self.state.config, |msg| {
+            window_message = Some(msg.to_string());
+        }) {
+            if let Some(msg) = window_message {
+                self.state.add_log(msg);
+            }
+            self.state.save_config();
+        }
+
+        ui.add_space(10.0);
+
+        // Startup behaviour
+        let mut startup_message = None;
+        if self::config::render_startup_config(ui, &mut self.state.config, |msg| {
+            startup_message = Some(msg.to_string());
+        }) {
+            if let Some(msg) = startup_message {
+                self.state.add_log(msg);
+            }
+            self.state.apply_launch_at_login();
+            self.state.save_config();
+        }
+        ui.add_space(10.0);
+    }
+
+    fn render_shortcut_configuration(&mut self, ui: &mut egui::Ui) {
         // Recording shortcut
         ui.group(|ui| {
             ui.label("Recording Shortcut:");
 
+            let (names, active) = self.state.profiles();
+            if !names.is_empty() {
+                shortcuts::render_profile_selector(ui, &names, active.as_deref(), |name| {
+                    self.state.set_active_profile(name);
+                });
+                ui.separator();
+            }
+
             // Presets
             shortcuts::render_shortcut_presets(ui, |shortcut| {
                 self.state.apply_shortcut(shortcut);
@@ -180,10 +523,38 @@ impl WhispoApp {
                 if let Some(msg) = mode_message {
                     self.state.add_log(msg);
                 }
-                self.state.config_manager.save_async(self.state.config.clone());
+                self.state.save_config();
                 self.state.update_shortcut_listener();
             }
 
+            // Physical key matching
+            let mut scan_code_message = None;
+            if shortcuts::render_scan_code_matching(
+                ui,
+                &mut self.state.config.match_scan_codes,
+                &mut self.state.config.recording_shortcut,
+                |msg| {
+                    scan_code_message = Some(msg.to_string());
+                },
+            ) {
+                if let Some(msg) = scan_code_message {
+                    self.state.add_log(msg);
+                }
+                self.state.save_config();
+                self.state.update_shortcut_listener();
+            }
+
+            // Trigger key consumption, picked up when the listener starts
+            let mut consume_message = None;
+            if shortcuts::render_consume_trigger(ui, &mut self.state.config.consume_trigger, |msg| {
+                consume_message = Some(msg.to_string());
+            }) {
+                if let Some(msg) = consume_message {
+                    self.state.add_log(msg);
+                }
+                self.state.save_config();
+            }
+
             ui.separator();
 
             // Visual editor
@@ -195,10 +566,18 @@ impl WhispoApp {
                 if let Some(msg) = editor_message {
                     self.state.add_log(msg);
                 }
-                self.state.config_manager.save_async(self.state.config.clone());
+                self.state.save_config();
                 self.state.update_shortcut_listener();
             }
             self.state.set_show_visual_editor(show_editor);
+
+            ui.separator();
+
+            // Shortcut tester
+            let mut testing = self.state.shortcut_manager.testing_keys;
+            if shortcuts::render_shortcut_tester(ui, &mut testing, &self.state.shortcut_manager.observed_keys) {
+                self.state.set_key_tester(testing);
+            }
         });
     }
 }
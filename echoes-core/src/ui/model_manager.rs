@@ -0,0 +1,109 @@
+use echoes_config::WhisperModel;
+use tokio::sync::{
+    oneshot::{self, error::TryRecvError},
+    watch,
+};
+
+/// Bytes of a model download received so far, and its size when known
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+impl DownloadProgress {
+    /// Completed fraction (0.0-1.0), or `None` when the size is unknown
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    pub fn fraction(self) -> Option<f32> {
+        self.total
+            .filter(|&total| total > 0)
+            .map(|total| (self.downloaded as f64 / total as f64).min(1.0) as f32)
+    }
+}
+
+/// Model download running on a background task
+struct ModelDownload {
+    model: WhisperModel,
+    progress_rx: watch::Receiver<DownloadProgress>,
+    result_rx: oneshot::Receiver<Result<(), String>>,
+}
+
+/// Downloads and deletes local Whisper models off the UI thread
+pub struct ModelManager {
+    downloads: Vec<ModelDownload>,
+}
+
+impl ModelManager {
+    pub const fn new() -> Self {
+        Self { downloads: Vec::new() }
+    }
+
+    pub const fn is_downloading(&self) -> bool {
+        !self.downloads.is_empty()
+    }
+
+    /// Progress of the download of `model`, if one is running
+    pub fn progress(&self, model: &WhisperModel) -> Option<DownloadProgress> {
+        self.downloads
+            .iter()
+            .find(|download| download.model == *model)
+            .map(|download| *download.progress_rx.borrow())
+    }
+
+    /// Downloads `model` on the tokio runtime unless it is already being
+    /// downloaded
+    pub fn start_download(&mut self, model: WhisperModel) -> Result<(), String> {
+        if self.progress(&model).is_some() {
+            return Ok(());
+        }
+        let runtime = tokio::runtime::Handle::try_current()
+            .map_err(|_| "No async runtime available for downloads".to_string())?;
+
+        let (progress_tx, progress_rx) = watch::channel(DownloadProgress::default());
+        let (result_tx, result_rx) = oneshot::channel();
+        let download_model = model.clone();
+        runtime.spawn(async move {
+            let result = echoes_stt::models::download_model(&download_model, |downloaded, total| {
+                progress_tx.send_replace(DownloadProgress { downloaded, total });
+            })
+            .await;
+            let _ = result_tx.send(result.map(|_| ()).map_err(|e| format!("{e:#}")));
+        });
+
+        self.downloads.push(ModelDownload {
+            model,
+            progress_rx,
+            result_rx,
+        });
+        Ok(())
+    }
+
+    /// Deletes the downloaded `model`; refused while it is being downloaded
+    pub fn delete(&self, model: &WhisperModel) -> Result<(), String> {
+        if self.progress(model).is_some() {
+            return Err(format!("{model:?} is still being downloaded"));
+        }
+        echoes_stt::models::delete_model(model).map_err(|e| format!("{e:#}"))
+    }
+
+    /// Returns the downloads that finished since the last call (non-blocking)
+    pub fn poll(&mut self) -> Vec<(WhisperModel, Result<(), String>)> {
+        let mut finished = Vec::new();
+        self.downloads.retain_mut(|download| {
+            let result = match download.result_rx.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Closed) => Err("Download task ended unexpectedly".into()),
+            };
+            finished.push((download.model.clone(), result));
+            false
+        });
+        finished
+    }
+}
+
+impl Default for ModelManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,54 @@
+use echoes_config::WhisperModel;
+use eframe::egui;
+
+use super::model_manager::ModelManager;
+
+/// Renders each local Whisper model with its download status and a button to
+/// download or delete it
+pub fn render_models(
+    ui: &mut egui::Ui, models: &ModelManager, mut on_download: impl FnMut(WhisperModel),
+    mut on_delete: impl FnMut(WhisperModel),
+) {
+    ui.collapsing("Models", |ui| {
+        ui.small("Models used by Local Whisper, stored in the models directory");
+
+        egui::Grid::new("whisper_models")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for model in WhisperModel::ALL {
+                    ui.label(format!("{model:?}"));
+
+                    if let Some(progress) = models.progress(&model) {
+                        let bar = progress.fraction().map_or_else(
+                            || egui::ProgressBar::new(0.0).animate(true),
+                            |fraction| egui::ProgressBar::new(fraction).show_percentage(),
+                        );
+                        ui.add(bar.desired_width(150.0));
+                        ui.small(format_size(progress.downloaded));
+                    } else if let Some(size) = echoes_stt::models::downloaded_size(&model) {
+                        ui.label(format!("Downloaded · {}", format_size(size)));
+                        if ui
+                            .button("Delete")
+                            .on_hover_text("Delete the model file to free disk space")
+                            .clicked()
+                        {
+                            on_delete(model);
+                        }
+                    } else {
+                        ui.label("Not downloaded");
+                        if ui.button("Download").clicked() {
+                            on_download(model);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+}
+
+/// Formats a byte count in MB, the scale Whisper model sizes are on
+#[allow(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+    format!("{:.0} MB", bytes as f64 / 1_000_000.0)
+}
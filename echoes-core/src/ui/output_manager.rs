@@ -0,0 +1,140 @@
+use std::{sync::mpsc, time::Duration};
+
+use echoes_config::{Config, OutputMethod, OutputPlacement};
+use echoes_keyboard::TypingOptions;
+
+/// Text to deliver to the focused application, with the output settings at
+/// the time it was queued
+struct OutputJob {
+    text: String,
+    method: OutputMethod,
+    placement: OutputPlacement,
+    restore_clipboard: bool,
+    type_delay: Duration,
+    /// How text is typed, when it is typed rather than pasted
+    typing: TypingOptions,
+    /// Logged once the text was delivered
+    done_message: Option<String>,
+}
+
+/// Types or pastes text on a worker thread, so waiting for focus to settle
+/// and synthesizing keystrokes never stall the UI. Jobs run one at a time in
+/// the order they were queued, and their outcome is reported as log lines.
+pub struct OutputManager {
+    job_tx: Option<mpsc::Sender<OutputJob>>,
+    /// Log lines of each finished job
+    log_tx: mpsc::Sender<Vec<String>>,
+    log_rx: mpsc::Receiver<Vec<String>>,
+    /// Jobs queued and not finished yet
+    pending: usize,
+}
+
+impl OutputManager {
+    pub fn new() -> Self {
+        let (log_tx, log_rx) = mpsc::channel();
+        Self {
+            job_tx: None,
+            log_tx,
+            log_rx,
+            pending: 0,
+        }
+    }
+
+    /// Whether queued text is still waiting to be delivered
+    pub const fn is_busy(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// Queues `text` for output with the configured method, logging
+    /// `done_message` once it was delivered
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker thread cannot be started.
+    pub fn output(&mut self, config: &Config, text: String, done_message: Option<String>) -> Result<(), String> {
+        let job = OutputJob {
+            text,
+            method: config.output_method,
+            placement: config.output_placement,
+            restore_clipboard: config.restore_clipboard,
+            type_delay: Duration::from_millis(config.type_delay_ms),
+            typing: TypingOptions {
+                initial_delay_ms: 0,
+                per_char_delay_ms: config.type_char_delay_ms,
+            },
+            done_message,
+        };
+        let job = match &self.job_tx {
+            Some(job_tx) => match job_tx.send(job) {
+                Ok(()) => {
+                    self.pending += 1;
+                    return Ok(());
+                }
+                // The worker is gone; start a new one below
+                Err(mpsc::SendError(job)) => job,
+            },
+            None => job,
+        };
+
+        let job_tx = self.spawn_worker()?;
+        if job_tx.send(job).is_ok() {
+            self.pending += 1;
+        }
+        self.job_tx = Some(job_tx);
+        Ok(())
+    }
+
+    fn spawn_worker(&self) -> Result<mpsc::Sender<OutputJob>, String> {
+        let (job_tx, job_rx) = mpsc::channel::<OutputJob>();
+        let log_tx = self.log_tx.clone();
+        std::thread::Builder::new()
+            .name("echoes-output".into())
+            .spawn(move || {
+                let _span = tracing::info_span!("output").entered();
+                for job in job_rx {
+                    let _ = log_tx.send(deliver(&job));
+                }
+            })
+            .map_err(|e| format!("Failed to start output thread: {e}"))?;
+        Ok(job_tx)
+    }
+
+    /// Drains the log lines of the jobs finished since the last call
+    pub fn poll(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for job_lines in self.log_rx.try_iter() {
+            self.pending = self.pending.saturating_sub(1);
+            lines.extend(job_lines);
+        }
+        lines
+    }
+}
+
+impl Default for OutputManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Delivers the job's text, typing it instead when pasting fails. Returns the
+/// lines to log.
+fn deliver(job: &OutputJob) -> Vec<String> {
+    // After a global shortcut the target app may not have focus back yet
+    echoes_platform::wait_for_focus_to_settle(job.type_delay);
+
+    let mut log = Vec::new();
+    let output = echoes_keyboard::output_text(&job.text, job.method, job.placement, job.restore_clipboard, job.typing);
+    let result = match output {
+        Ok(()) => Ok(()),
+        Err(e) if e.downcast_ref::<echoes_keyboard::PasteFailed>().is_some() => {
+            log.push(format!("{e}, typing the text instead"));
+            echoes_keyboard::type_text_with_options(&job.text, job.typing).map_err(|e| e.to_string())
+        }
+        Err(e) => Err(e.to_string()),
+    };
+    match result {
+        Ok(()) => log.extend(job.done_message.clone()),
+        Err(e) => log.push(format!("Failed to output text: {e}")),
+    }
+    log
+}
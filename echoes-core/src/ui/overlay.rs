@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use eframe::egui;
+
+/// Size of the overlay window in points
+const OVERLAY_SIZE: egui::Vec2 = egui::vec2(240.0, 64.0);
+
+/// Distance of the overlay from the top edge of the screen
+const EDGE_MARGIN: f32 = 24.0;
+
+/// Level shown as an empty meter, in dBFS
+const METER_FLOOR_DBFS: f32 = -60.0;
+
+/// Shows a small borderless window above all others with the elapsed time,
+/// the input level and the recording shortcut.
+///
+/// The window exists only while this is called every frame, so the caller
+/// creates and destroys it by calling it only while recording. It never takes
+/// focus and lets clicks through where the platform supports it, so the app
+/// being dictated into stays usable. Nothing is shown when the backend can't
+/// open extra windows.
+pub fn show_recording_overlay(ctx: &egui::Context, elapsed: Duration, samples: &[f32], shortcut: &str) {
+    let mut builder = egui::ViewportBuilder::default()
+        .with_title("Echoes recording")
+        .with_inner_size(OVERLAY_SIZE)
+        .with_decorations(false)
+        .with_resizable(false)
+        .with_always_on_top()
+        .with_active(false)
+        .with_taskbar(false)
+        .with_mouse_passthrough(true);
+    // Centered along the top edge of the monitor the main window is on
+    if let Some(monitor) = ctx.input(|i| i.viewport().monitor_size) {
+        builder = builder.with_position(egui::pos2((monitor.x - OVERLAY_SIZE.x) / 2.0, EDGE_MARGIN));
+    }
+
+    ctx.show_viewport_immediate(
+        egui::ViewportId::from_hash_of("recording_overlay"),
+        builder,
+        |ctx, class| {
+            if class == egui::ViewportClass::Embedded {
+                return;
+            }
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::RED, "●");
+                    let seconds = elapsed.as_secs();
+                    ui.strong(format!("{}:{:02}", seconds / 60, seconds % 60));
+                    ui.small(shortcut);
+                });
+                ui.add(egui::ProgressBar::new(meter_level(samples)).desired_height(8.0));
+            });
+        },
+    );
+}
+
+/// RMS level of `samples` on a 0.0-1.0 scale from [`METER_FLOOR_DBFS`] to
+/// full scale
+#[allow(clippy::cast_precision_loss)]
+fn meter_level(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    (1.0 - 20.0 * rms.log10() / METER_FLOOR_DBFS).clamp(0.0, 1.0)
+}
@@ -1,9 +1,15 @@
+use std::time::{Duration, Instant};
+
 use echoes_logging::debug;
 
 /// Manages session state like recording status and logs
 pub struct SessionManager {
     pub recording: bool,
+    pub recording_started: Option<Instant>,
     pub recording_shortcut: bool,
+    /// Copy the pending transcript to the clipboard instead of outputting it,
+    /// as requested by holding Shift when the recording stopped
+    pub copy_to_clipboard: bool,
     pub logs: Vec<String>,
     pub error_message: Option<String>,
 }
@@ -12,7 +18,9 @@ impl SessionManager {
     pub fn new() -> Self {
         Self {
             recording: false,
+            recording_started: None,
             recording_shortcut: false,
+            copy_to_clipboard: false,
             logs: vec!["App started".into()],
             error_message: None,
         }
@@ -33,12 +41,15 @@ impl SessionManager {
         self.error_message = error;
     }
 
-    pub const fn start_recording(&mut self) {
+    pub fn start_recording(&mut self) {
         self.recording = true;
+        self.recording_started = Some(Instant::now());
     }
 
-    pub const fn stop_recording(&mut self) {
+    /// Stops recording and returns how long it ran
+    pub fn stop_recording(&mut self) -> Option<Duration> {
         self.recording = false;
+        self.recording_started.take().map(|started| started.elapsed())
     }
 
     pub const fn start_shortcut_recording(&mut self) {
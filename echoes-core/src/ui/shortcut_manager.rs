@@ -1,9 +1,26 @@
-use echoes_config::RecordingShortcut;
+use std::collections::VecDeque;
+
+use echoes_config::{KeyCode, RecordingShortcut};
+
+/// Number of key events kept for the shortcut tester
+const OBSERVED_KEYS_LEN: usize = 20;
+
+/// A key event seen by the listener while the shortcut tester is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObservedKey {
+    pub code: KeyCode,
+    pub scan_code: u32,
+    pub pressed: bool,
+}
 
 /// Manages shortcut recording and editing state
 pub struct ShortcutManager {
     pub recorded_shortcut: Option<RecordingShortcut>,
     pub show_visual_editor: bool,
+    /// Whether the shortcut tester is showing the keys the listener sees
+    pub testing_keys: bool,
+    /// Most recent key events first
+    pub observed_keys: VecDeque<ObservedKey>,
 }
 
 impl ShortcutManager {
@@ -11,9 +28,16 @@ impl ShortcutManager {
         Self {
             recorded_shortcut: None,
             show_visual_editor: false,
+            testing_keys: false,
+            observed_keys: VecDeque::new(),
         }
     }
 
+    pub fn observe_key(&mut self, key: ObservedKey) {
+        self.observed_keys.push_front(key);
+        self.observed_keys.truncate(OBSERVED_KEYS_LEN);
+    }
+
     pub fn record_shortcut(&mut self, shortcut: RecordingShortcut) {
         self.recorded_shortcut = Some(shortcut);
     }
@@ -1,7 +1,12 @@
+use std::collections::VecDeque;
+
 use echoes_config::{KeyCode, RecordingShortcut, ShortcutMode};
 use eframe::egui;
 
-use super::shortcut_editor::{ConflictDisplay, ShortcutBuilder, ShortcutEditor, ShortcutEditorAction};
+use super::{
+    shortcut_editor::{ConflictDisplay, ShortcutBuilder, ShortcutEditor, ShortcutEditorAction},
+    shortcut_manager::ObservedKey,
+};
 
 /// Context for shortcut operations
 #[allow(dead_code)]
@@ -20,6 +25,7 @@ pub fn render_shortcut_presets(ui: &mut egui::Ui, mut on_apply: impl FnMut(Recor
                 mode: ShortcutMode::Hold,
                 key: KeyCode::ControlLeft,
                 modifiers: vec![],
+                scan_code: None,
             });
         }
         if ui.button("Ctrl+/").clicked() {
@@ -27,6 +33,7 @@ pub fn render_shortcut_presets(ui: &mut egui::Ui, mut on_apply: impl FnMut(Recor
                 mode: ShortcutMode::Toggle,
                 key: KeyCode::Slash,
                 modifiers: vec![KeyCode::ControlLeft],
+                scan_code: None,
             });
         }
         if ui.button("Cmd+Space").clicked() {
@@ -34,11 +41,32 @@ pub fn render_shortcut_presets(ui: &mut egui::Ui, mut on_apply: impl FnMut(Recor
                 mode: ShortcutMode::Toggle,
                 key: KeyCode::Space,
                 modifiers: vec![KeyCode::MetaLeft],
+                scan_code: None,
             });
         }
     });
 }
 
+/// Renders a dropdown of the profile `names`, calling `on_select` with the
+/// one picked when it isn't `active` already
+pub fn render_profile_selector(
+    ui: &mut egui::Ui, names: &[String], active: Option<&str>, mut on_select: impl FnMut(&str),
+) {
+    ui.horizontal(|ui| {
+        ui.label("Profile:");
+        egui::ComboBox::from_id_salt("active_profile")
+            .selected_text(active.unwrap_or("None"))
+            .show_ui(ui, |ui| {
+                for name in names {
+                    let selected = active == Some(name.as_str());
+                    if ui.selectable_label(selected, name).clicked() && !selected {
+                        on_select(name);
+                    }
+                }
+            });
+    });
+}
+
 /// Handles the shortcut editor UI and returns actions to take
 #[allow(dead_code)]
 pub fn handle_shortcut_editor(ui: &mut egui::Ui, ctx: &mut ShortcutContext<'_>) -> ShortcutEditorAction {
@@ -90,6 +118,51 @@ pub fn render_shortcut_mode(ui: &mut egui::Ui, mode: &mut ShortcutMode, mut on_c
     changed
 }
 
+/// Renders the toggle for matching the main key by physical position.
+/// Turning it off drops the scan code stored with the current shortcut.
+pub fn render_scan_code_matching(
+    ui: &mut egui::Ui, enabled: &mut bool, shortcut: &mut RecordingShortcut, mut on_change: impl FnMut(&str),
+) -> bool {
+    let changed = ui.checkbox(enabled, "Match physical key position").changed();
+    if changed {
+        if *enabled {
+            on_change("Enabled physical key matching; record the shortcut again to capture its position");
+        } else {
+            shortcut.scan_code = None;
+            on_change("Disabled physical key matching");
+        }
+    }
+    ui.small("For non-QWERTY layouts: the shortcut follows the key's position rather than its letter");
+
+    changed
+}
+
+/// Renders the option to keep the trigger key from reaching other apps,
+/// which needs event grabbing and so is disabled where that's unsupported
+pub fn render_consume_trigger(ui: &mut egui::Ui, enabled: &mut bool, mut on_change: impl FnMut(&str)) -> bool {
+    let supported = cfg!(any(target_os = "macos", target_os = "windows"));
+    let changed = ui
+        .add_enabled(
+            supported,
+            egui::Checkbox::new(enabled, "Keep the trigger key from other apps"),
+        )
+        .changed();
+    if changed {
+        on_change(if *enabled {
+            "Enabled consuming the trigger key; restart echoes to apply"
+        } else {
+            "Disabled consuming the trigger key; restart echoes to apply"
+        });
+    }
+    ui.small(if supported {
+        "Other apps won't see the key that starts recording. Applied after a restart"
+    } else {
+        "Only available on macOS and Windows"
+    });
+
+    changed
+}
+
 /// Renders the visual editor UI
 pub fn render_visual_editor(
     ui: &mut egui::Ui, shortcut: &mut RecordingShortcut, show_visual_editor: &mut bool, mut on_change: impl FnMut(&str),
@@ -131,6 +204,39 @@ pub fn render_visual_editor(
     changed
 }
 
+/// Renders the shortcut tester, listing the keys the listener reports.
+/// Returns `true` when the tester was switched on or off.
+pub fn render_shortcut_tester(ui: &mut egui::Ui, testing: &mut bool, observed: &VecDeque<ObservedKey>) -> bool {
+    let toggled = ui
+        .button(if *testing {
+            "Stop Shortcut Tester"
+        } else {
+            "Shortcut Tester"
+        })
+        .clicked();
+    if toggled {
+        *testing = !*testing;
+    }
+
+    if *testing {
+        ui.small("Press keys to see what the app detects. The recording shortcut is paused meanwhile.");
+        if observed.is_empty() {
+            ui.label("Waiting for key presses...");
+        }
+        for key in observed {
+            let action = if key.pressed { "pressed" } else { "released" };
+            ui.monospace(format!(
+                "{} ({:?}, scan code {}) {action}",
+                echoes_config::format_keycode(&key.code),
+                key.code,
+                key.scan_code
+            ));
+        }
+    }
+
+    toggled
+}
+
 /// Formats a shortcut for display
 pub fn format_shortcut(shortcut: &RecordingShortcut) -> String {
     shortcut.format_display()
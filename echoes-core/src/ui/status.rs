@@ -1,5 +1,9 @@
 use eframe::egui;
 
+use crate::recordings::SubtitleFormat;
+
+use super::{mic_test::MicTest, transcription_manager::TranscriptionState};
+
 /// Renders the status section showing recording state and permissions
 pub fn render_status_section(ui: &mut egui::Ui, recording: bool, permissions_granted: bool) {
     ui.horizontal(|ui| {
@@ -14,6 +18,133 @@ pub fn render_status_section(ui: &mut egui::Ui, recording: bool, permissions_gra
     });
 }
 
+/// Renders the "Test Microphone" button and the outcome of the last test.
+/// The button is disabled while a test or a recording is running.
+pub fn render_mic_test(ui: &mut egui::Ui, mic_test: &MicTest, enabled: bool, on_test: impl FnOnce()) {
+    ui.horizontal(|ui| {
+        let button = egui::Button::new("Test Microphone");
+        if ui
+            .add_enabled(enabled && !mic_test.is_running(), button)
+            .on_hover_text("Record briefly and report the input level")
+            .clicked()
+        {
+            on_test();
+        }
+        if mic_test.is_running() {
+            ui.spinner();
+            ui.label("Listening...");
+        } else if let Some(outcome) = &mic_test.outcome {
+            ui.label(outcome);
+        }
+    });
+}
+
+/// Renders transcription progress: a spinner while running, then the result
+/// with a copy button, and subtitle export buttons when `can_export`
+pub fn render_transcription(
+    ui: &mut egui::Ui, state: &TranscriptionState, can_export: bool, mut on_export: impl FnMut(SubtitleFormat),
+) {
+    match state {
+        TranscriptionState::Idle => {}
+        TranscriptionState::Transcribing => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Transcribing...");
+            });
+        }
+        TranscriptionState::Streaming(text) => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Transcribing while recording...");
+            });
+            if !text.is_empty() {
+                ui.label(text);
+            }
+        }
+        TranscriptionState::Done(text) => {
+            ui.horizontal(|ui| {
+                ui.label("Transcript:");
+                if ui.button("Copy").clicked() {
+                    ui.ctx().copy_text(text.clone());
+                }
+                if can_export {
+                    for (format, label) in [(SubtitleFormat::Srt, "Export SRT"), (SubtitleFormat::Vtt, "Export VTT")] {
+                        if ui
+                            .button(label)
+                            .on_hover_text("Write subtitles next to the recording")
+                            .clicked()
+                        {
+                            on_export(format);
+                        }
+                    }
+                }
+            });
+            ui.label(text);
+        }
+        TranscriptionState::NoSpeech => {
+            ui.colored_label(egui::Color32::YELLOW, "No speech detected");
+        }
+        TranscriptionState::Failed(error) => {
+            ui.colored_label(egui::Color32::RED, format!("⚠️ Transcription failed: {error}"));
+        }
+    }
+}
+
+/// Height of the waveform strip in points
+const WAVEFORM_HEIGHT: f32 = 48.0;
+
+/// Samples at or above this magnitude are drawn as clipping
+const CLIP_THRESHOLD: f32 = 0.99;
+
+/// Renders the most recent audio samples as a line, downsampled to one peak
+/// per horizontal point. Clipping is tinted red; no samples draws a flat line.
+pub fn render_waveform(ui: &mut egui::Ui, samples: &[f32]) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), WAVEFORM_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+    let normal = egui::Stroke::new(1.0, visuals.text_color());
+    let mid_y = rect.center().y;
+    let half_height = rect.height() / 2.0;
+
+    if samples.is_empty() {
+        painter.line_segment(
+            [egui::pos2(rect.left(), mid_y), egui::pos2(rect.right(), mid_y)],
+            normal,
+        );
+        return;
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let columns = (rect.width() as usize).clamp(1, samples.len());
+    let bucket_len = samples.len().div_ceil(columns);
+    let clipping = egui::Stroke::new(1.0, egui::Color32::RED);
+
+    // Keep the sample with the largest magnitude in each bucket so peaks stay visible
+    let peaks = samples.chunks(bucket_len).map(|bucket| {
+        bucket
+            .iter()
+            .copied()
+            .fold(0.0_f32, |peak, s| if s.abs() > peak.abs() { s } else { peak })
+    });
+
+    #[allow(clippy::cast_precision_loss)]
+    let step = rect.width() / columns as f32;
+    let mut previous: Option<(egui::Pos2, bool)> = None;
+    for (i, peak) in peaks.enumerate() {
+        #[allow(clippy::cast_precision_loss)]
+        let x = (i as f32).mul_add(step, rect.left());
+        let point = egui::pos2(x, peak.clamp(-1.0, 1.0).mul_add(-half_height, mid_y));
+        let clipped = peak.abs() >= CLIP_THRESHOLD;
+        if let Some((from, from_clipped)) = previous {
+            let stroke = if clipped || from_clipped { clipping } else { normal };
+            painter.line_segment([from, point], stroke);
+        }
+        previous = Some((point, clipped));
+    }
+}
+
 /// Renders error messages and permission-related UI
 pub fn render_error_section(
     ui: &mut egui::Ui, error_message: Option<&String>, permissions_granted: bool, mut on_open_settings: impl FnMut(),
@@ -1,40 +1,166 @@
+use std::sync::mpsc;
+
+use eframe::egui;
+use tray_icon::{
+    Icon, TrayIcon, TrayIconBuilder,
+    menu::{Menu, MenuEvent, MenuItem},
+};
+
+/// Size of the generated tray icon in pixels
+const TRAY_ICON_SIZE: u16 = 32;
+
+/// Actions requested from the tray menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayCommand {
+    Open,
+    ToggleRecording,
+    Quit,
+}
+
+/// Tray icon and the menu items whose ids and labels we track
+struct Tray {
+    icon: TrayIcon,
+    open: MenuItem,
+    toggle_recording: MenuItem,
+    quit: MenuItem,
+    recording: bool,
+}
+
 /// Manages system-level operations like permissions and platform-specific
 /// features
-pub struct SystemManager;
+pub struct SystemManager {
+    tray: Option<Tray>,
+    menu_rx: Option<mpsc::Receiver<MenuEvent>>,
+}
 
 impl SystemManager {
     pub const fn new() -> Self {
-        Self
+        Self {
+            tray: None,
+            menu_rx: None,
+        }
+    }
+
+    /// Creates the tray icon. Must be called on the thread running the UI
+    /// event loop; on Linux this also initializes GTK, whose events are pumped
+    /// from [`SystemManager::poll_tray`]. Menu clicks wake the UI via `ctx`.
+    pub fn init_tray(&mut self, ctx: &egui::Context) -> Result<(), String> {
+        #[cfg(target_os = "linux")]
+        gtk::init().map_err(|e| format!("Failed to initialize GTK: {e}"))?;
+
+        let open = MenuItem::new("Open", true, None);
+        let toggle_recording = MenuItem::new("Start recording", true, None);
+        let quit = MenuItem::new("Quit", true, None);
+
+        let menu = Menu::new();
+        menu.append_items(&[&open, &toggle_recording, &quit])
+            .map_err(|e| format!("Failed to build tray menu: {e}"))?;
+
+        let icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip("Echoes - Idle")
+            .with_icon(status_icon(false)?)
+            .build()
+            .map_err(|e| format!("Failed to create tray icon: {e}"))?;
+
+        let (menu_tx, menu_rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        MenuEvent::set_event_handler(Some(move |event| {
+            let _ = menu_tx.send(event);
+            ctx.request_repaint();
+        }));
+
+        self.menu_rx = Some(menu_rx);
+        self.tray = Some(Tray {
+            icon,
+            open,
+            toggle_recording,
+            quit,
+            recording: false,
+        });
+        Ok(())
     }
 
-    pub fn open_accessibility_settings() -> Result<(), String> {
-        #[cfg(target_os = "macos")]
-        {
-            use std::process::Command;
+    pub const fn has_tray(&self) -> bool {
+        self.tray.is_some()
+    }
 
-            // Open System Settings to Privacy & Security > Accessibility
-            Command::new("open")
-                .arg("x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility")
-                .spawn()
-                .map_err(|e| format!("Failed to open System Settings: {e}"))?;
+    /// Updates the tray icon, tooltip and menu label when the recording state
+    /// changes
+    pub fn update_tray(&mut self, recording: bool) {
+        let Some(tray) = &mut self.tray else {
+            return;
+        };
+        if tray.recording == recording {
+            return;
         }
+        tray.recording = recording;
 
-        #[cfg(target_os = "windows")]
-        {
-            // Windows doesn't need special permissions for keyboard monitoring
-            return Err("No special permissions needed on Windows".into());
+        let (tooltip, label) = if recording {
+            ("Echoes - Recording", "Stop recording")
+        } else {
+            ("Echoes - Idle", "Start recording")
+        };
+        tray.toggle_recording.set_text(label);
+        if let Err(e) = tray.icon.set_tooltip(Some(tooltip)) {
+            tracing::warn!("Failed to update tray tooltip: {e}");
         }
+        match status_icon(recording) {
+            Ok(icon) => {
+                if let Err(e) = tray.icon.set_icon(Some(icon)) {
+                    tracing::warn!("Failed to update tray icon: {e}");
+                }
+            }
+            Err(e) => tracing::warn!("{e}"),
+        }
+    }
 
+    /// Drains pending tray menu clicks
+    pub fn poll_tray(&self) -> Vec<TrayCommand> {
         #[cfg(target_os = "linux")]
-        {
-            // Linux might need the user to be in the input group
-            return Err("On Linux, ensure you're in the 'input' group: sudo usermod -a -G input $USER".into());
+        while gtk::events_pending() {
+            gtk::main_iteration_do(false);
         }
 
-        Ok(())
+        let (Some(tray), Some(menu_rx)) = (&self.tray, &self.menu_rx) else {
+            return Vec::new();
+        };
+
+        let mut commands = Vec::new();
+        while let Ok(event) = menu_rx.try_recv() {
+            if event.id == *tray.open.id() {
+                commands.push(TrayCommand::Open);
+            } else if event.id == *tray.toggle_recording.id() {
+                commands.push(TrayCommand::ToggleRecording);
+            } else if event.id == *tray.quit.id() {
+                commands.push(TrayCommand::Quit);
+            }
+        }
+        commands
     }
 }
 
+/// Draws a filled circle icon: red while recording, grey when idle
+fn status_icon(recording: bool) -> Result<Icon, String> {
+    let color: [u8; 3] = if recording { [220, 40, 40] } else { [140, 140, 140] };
+    let size = f32::from(TRAY_ICON_SIZE);
+    let center = (size - 1.0) / 2.0;
+    let radius = size / 2.0 - 2.0;
+
+    let mut rgba = Vec::with_capacity(usize::from(TRAY_ICON_SIZE).pow(2) * 4);
+    for y in 0..TRAY_ICON_SIZE {
+        for x in 0..TRAY_ICON_SIZE {
+            let dx = f32::from(x) - center;
+            let dy = f32::from(y) - center;
+            let alpha = if dx.hypot(dy) <= radius { 255 } else { 0 };
+            rgba.extend_from_slice(&[color[0], color[1], color[2], alpha]);
+        }
+    }
+
+    let size = u32::from(TRAY_ICON_SIZE);
+    Icon::from_rgba(rgba, size, size).map_err(|e| format!("Failed to create tray icon image: {e}"))
+}
+
 impl Default for SystemManager {
     fn default() -> Self {
         Self::new()
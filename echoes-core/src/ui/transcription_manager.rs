@@ -0,0 +1,419 @@
+use std::{collections::VecDeque, path::PathBuf};
+
+use echoes_audio::Segment;
+use echoes_config::Config;
+use echoes_stt::{ConfiguredStt, FallbackProvider, LazyStt, SttProvider as _, Transcript, TranscriptSegment};
+use tokio::sync::{
+    mpsc,
+    oneshot::{self, error::TryRecvError},
+};
+
+/// Progress of the most recent transcription
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TranscriptionState {
+    #[default]
+    Idle,
+    Transcribing,
+    /// Streaming mode: text of the segments transcribed so far
+    Streaming(String),
+    Done(String),
+    /// The recording contained no speech, so nothing was output
+    NoSpeech,
+    Failed(String),
+}
+
+/// A successful transcription together with the recording it came from
+pub struct CompletedTranscription {
+    pub text: String,
+    /// Language the provider detected, when it reports one
+    pub detected_language: Option<String>,
+    /// Timed pieces of the text: the provider's for a whole recording or
+    /// file, one per speech segment timed by VAD in streaming mode
+    pub segments: Vec<TranscriptSegment>,
+    pub provider: String,
+    pub duration_ms: u64,
+    pub audio_path: Option<PathBuf>,
+    /// Transcribed from a dropped file rather than a recording, so the text
+    /// is only shown instead of output
+    pub from_file: bool,
+}
+
+/// Transcription running on a background task
+struct PendingTranscription {
+    result_rx: oneshot::Receiver<Result<Transcript, String>>,
+    provider: String,
+    duration_ms: u64,
+    audio_path: Option<PathBuf>,
+    from_file: bool,
+}
+
+/// Transcription of one speech segment in streaming mode, with where the
+/// segment sits in the recording
+struct QueuedSegment {
+    result_rx: oneshot::Receiver<Result<Transcript, String>>,
+    start_ms: u64,
+    end_ms: u64,
+}
+
+/// A speech segment's audio (WAV) handed to the segment worker, with where
+/// to send its transcription
+struct SegmentJob {
+    audio: Vec<u8>,
+    result_tx: oneshot::Sender<Result<Transcript, String>>,
+}
+
+/// Segment-by-segment transcription of a recording, either in streaming mode
+/// or of a long recording's segments after it stopped
+struct StreamingTranscription {
+    /// Queue of the worker transcribing this recording's segments; `None`
+    /// when there is no runtime to run it on
+    job_tx: Option<mpsc::UnboundedSender<SegmentJob>>,
+    /// Segment transcriptions in recording order; only the front is polled so
+    /// text is appended in order
+    segments: VecDeque<QueuedSegment>,
+    text: String,
+    /// The transcribed segments' text, timed by their VAD offsets
+    timed: Vec<TranscriptSegment>,
+    /// Language most recently detected in a segment
+    detected_language: Option<String>,
+    last_error: Option<String>,
+    /// Set once the recording stopped and no more segments will arrive
+    finished: Option<(u64, Option<PathBuf>)>,
+    provider: String,
+    /// End each segment's text as a sentence before appending the next
+    sentence_breaks: bool,
+}
+
+/// Runs transcriptions off the UI thread and tracks their state
+pub struct TranscriptionManager {
+    pub state: TranscriptionState,
+    pending: Option<PendingTranscription>,
+    streaming: Option<StreamingTranscription>,
+}
+
+impl TranscriptionManager {
+    pub const fn new() -> Self {
+        Self {
+            state: TranscriptionState::Idle,
+            pending: None,
+            streaming: None,
+        }
+    }
+
+    pub const fn is_transcribing(&self) -> bool {
+        self.pending.is_some() || self.streaming.is_some()
+    }
+
+    /// Whether a streaming transcription is collecting segments
+    pub const fn is_streaming(&self) -> bool {
+        self.streaming.is_some()
+    }
+
+    /// Transcribes `audio` (WAV) with the configured provider on the tokio
+    /// runtime. A transcription still in flight is abandoned.
+    pub fn start(&mut self, config: &Config, audio: Vec<u8>, duration_ms: u64, audio_path: Option<PathBuf>) {
+        let result_rx = spawn_transcription(config, audio);
+        self.begin(config, result_rx, duration_ms, audio_path, false);
+    }
+
+    /// Reads and transcribes the audio file at `path` like
+    /// [`TranscriptionManager::start`]. The duration is taken from the
+    /// provider when it reports one.
+    pub fn start_file(&mut self, config: &Config, path: PathBuf) {
+        let result_rx = spawn_file_transcription(config, path.clone());
+        self.begin(config, result_rx, 0, Some(path), true);
+    }
+
+    fn begin(
+        &mut self, config: &Config, result_rx: Option<oneshot::Receiver<Result<Transcript, String>>>, duration_ms: u64,
+        audio_path: Option<PathBuf>, from_file: bool,
+    ) {
+        self.streaming = None;
+        let Some(result_rx) = result_rx else {
+            self.pending = None;
+            self.state = TranscriptionState::Failed("No async runtime available for transcription".into());
+            return;
+        };
+        let provider = format!("{:?}", config.stt_provider);
+
+        self.state = TranscriptionState::Transcribing;
+        self.pending = Some(PendingTranscription {
+            result_rx,
+            provider,
+            duration_ms,
+            audio_path,
+            from_file,
+        });
+    }
+
+    /// Begins a streaming transcription: segments passed to
+    /// [`TranscriptionManager::push_segment`] are transcribed one at a time as
+    /// they arrive, by one provider chain for the whole recording, and their
+    /// text is appended in order
+    pub fn start_streaming(&mut self, config: &Config) {
+        self.pending = None;
+        self.streaming = Some(StreamingTranscription {
+            job_tx: spawn_segment_worker(config),
+            segments: VecDeque::new(),
+            text: String::new(),
+            timed: Vec::new(),
+            detected_language: None,
+            last_error: None,
+            finished: None,
+            provider: format!("{:?}", config.stt_provider),
+            sentence_breaks: false,
+        });
+        self.state = TranscriptionState::Streaming(String::new());
+    }
+
+    /// Transcribes each speech segment of a stopped recording separately and
+    /// joins their text in order as sentences, which keeps long recordings
+    /// accurate and bounds the work per transcription
+    pub fn start_segmented(
+        &mut self, config: &Config, segments: Vec<Segment>, duration_ms: u64, audio_path: Option<PathBuf>,
+    ) {
+        self.start_streaming(config);
+        if let Some(streaming) = &mut self.streaming {
+            streaming.sentence_breaks = true;
+        }
+        for segment in segments {
+            self.push_segment(segment);
+        }
+        self.finish_streaming(duration_ms, audio_path);
+    }
+
+    /// Transcribes one speech segment of the streaming recording
+    pub fn push_segment(&mut self, segment: Segment) {
+        let Some(streaming) = &mut self.streaming else {
+            return;
+        };
+        let Some(job_tx) = &streaming.job_tx else {
+            streaming.last_error = Some("No async runtime available for transcription".into());
+            return;
+        };
+        let (result_tx, result_rx) = oneshot::channel();
+        // A worker that is gone closes `result_tx`, which poll reports
+        let _ = job_tx.send(SegmentJob {
+            audio: segment.wav,
+            result_tx,
+        });
+        streaming.segments.push_back(QueuedSegment {
+            result_rx,
+            start_ms: segment.start_ms,
+            end_ms: segment.end_ms,
+        });
+    }
+
+    /// Marks the streaming recording as stopped; the transcription completes
+    /// once the queued segments are done
+    pub fn finish_streaming(&mut self, duration_ms: u64, audio_path: Option<PathBuf>) {
+        if let Some(streaming) = &mut self.streaming {
+            streaming.finished = Some((duration_ms, audio_path));
+        }
+    }
+
+    /// Abandons a streaming transcription, e.g. when the recording was
+    /// cancelled
+    pub fn cancel_streaming(&mut self) {
+        if self.streaming.take().is_some() {
+            self.state = TranscriptionState::Idle;
+        }
+    }
+
+    /// Checks whether the running transcription finished (non-blocking).
+    /// Returns the outcome once, when it completes.
+    pub fn poll(&mut self) -> Option<Result<CompletedTranscription, String>> {
+        if self.streaming.is_some() {
+            return self.poll_streaming();
+        }
+
+        let pending = self.pending.as_mut()?;
+        let result = match pending.result_rx.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Closed) => Err("Transcription task ended unexpectedly".into()),
+        };
+        let pending = self.pending.take()?;
+
+        match result {
+            Ok(transcript) => {
+                self.state = TranscriptionState::Done(transcript.text.clone());
+                let duration_ms = if pending.from_file {
+                    transcript.duration_ms.unwrap_or_default()
+                } else {
+                    pending.duration_ms
+                };
+                Some(Ok(CompletedTranscription {
+                    text: transcript.text,
+                    detected_language: transcript.detected_language,
+                    segments: transcript.segments,
+                    provider: pending.provider,
+                    duration_ms,
+                    audio_path: pending.audio_path,
+                    from_file: pending.from_file,
+                }))
+            }
+            Err(e) => {
+                self.state = TranscriptionState::Failed(e.clone());
+                Some(Err(e))
+            }
+        }
+    }
+
+    fn poll_streaming(&mut self) -> Option<Result<CompletedTranscription, String>> {
+        let streaming = self.streaming.as_mut()?;
+
+        while let Some(queued) = streaming.segments.front_mut() {
+            let result = match queued.result_rx.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Closed) => Err("Transcription task ended unexpectedly".into()),
+            };
+            let Some(queued) = streaming.segments.pop_front() else {
+                break;
+            };
+
+            match result {
+                Ok(transcript) => {
+                    if transcript.detected_language.is_some() {
+                        streaming.detected_language = transcript.detected_language;
+                    }
+                    let text = transcript.text.trim();
+                    if !text.is_empty() {
+                        if !streaming.text.is_empty() {
+                            if streaming.sentence_breaks {
+                                end_sentence(&mut streaming.text);
+                            }
+                            streaming.text.push(' ');
+                        }
+                        streaming.text.push_str(text);
+                        streaming.timed.push(TranscriptSegment {
+                            start_ms: queued.start_ms,
+                            end_ms: queued.end_ms,
+                            text: text.to_string(),
+                        });
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Segment transcription failed: {e}");
+                    streaming.last_error = Some(e);
+                }
+            }
+        }
+        self.state = TranscriptionState::Streaming(streaming.text.clone());
+
+        if streaming.finished.is_none() || !streaming.segments.is_empty() {
+            return None;
+        }
+        let streaming = self.streaming.take()?;
+        let (duration_ms, audio_path) = streaming.finished?;
+
+        // Only fail when nothing could be transcribed at all
+        match streaming.last_error {
+            Some(e) if streaming.text.is_empty() => {
+                self.state = TranscriptionState::Failed(e.clone());
+                Some(Err(e))
+            }
+            _ => {
+                self.state = TranscriptionState::Done(streaming.text.clone());
+                Some(Ok(CompletedTranscription {
+                    text: streaming.text,
+                    detected_language: streaming.detected_language,
+                    segments: streaming.timed,
+                    provider: streaming.provider,
+                    duration_ms,
+                    audio_path,
+                    from_file: false,
+                }))
+            }
+        }
+    }
+}
+
+impl Default for TranscriptionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Ends `text` with a full stop unless it already ends a sentence
+fn end_sentence(text: &mut String) {
+    if !text.ends_with(['.', '!', '?', '…', '。', '！', '？']) {
+        text.push('.');
+    }
+}
+
+/// Transcribes `audio` on the tokio runtime; `None` when there is no runtime
+fn spawn_transcription(config: &Config, audio: Vec<u8>) -> Option<oneshot::Receiver<Result<Transcript, String>>> {
+    spawn_task(transcribe(config.clone(), audio))
+}
+
+/// Like [`spawn_transcription`], reading the audio from `path` on the task so
+/// large files don't stall the UI
+fn spawn_file_transcription(config: &Config, path: PathBuf) -> Option<oneshot::Receiver<Result<Transcript, String>>> {
+    let config = config.clone();
+    spawn_task(async move {
+        let audio = tokio::fs::read(&path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        transcribe(config, audio).await
+    })
+}
+
+/// Starts the task transcribing a streaming recording's segments one at a
+/// time, in the order they are queued, with one provider chain for the whole
+/// recording. It ends once the queue's sender is dropped.
+fn spawn_segment_worker(config: &Config) -> Option<mpsc::UnboundedSender<SegmentJob>> {
+    let runtime = tokio::runtime::Handle::try_current().ok()?;
+    let (job_tx, mut job_rx) = mpsc::unbounded_channel::<SegmentJob>();
+    let config = config.clone();
+    runtime.spawn(async move {
+        let stt = ConfiguredStt::fallback_chain(&config);
+        while let Some(job) = job_rx.recv().await {
+            let _ = job.result_tx.send(transcribe_with(&config, &stt, job.audio).await);
+        }
+    });
+    Some(job_tx)
+}
+
+fn spawn_task(
+    task: impl Future<Output = Result<Transcript, String>> + Send + 'static,
+) -> Option<oneshot::Receiver<Result<Transcript, String>>> {
+    let runtime = tokio::runtime::Handle::try_current().ok()?;
+    let (result_tx, result_rx) = oneshot::channel();
+    runtime.spawn(async move {
+        let _ = result_tx.send(task.await);
+    });
+    Some(result_rx)
+}
+
+/// Sends the audio to the configured STT providers, then through the basic
+/// clean-up and post-processing when enabled
+async fn transcribe(config: Config, audio: Vec<u8>) -> Result<Transcript, String> {
+    let stt = ConfiguredStt::fallback_chain(&config);
+    transcribe_with(&config, &stt, audio).await
+}
+
+/// Like [`transcribe`], with a provider chain built by the caller
+async fn transcribe_with(
+    config: &Config, stt: &FallbackProvider<LazyStt>, audio: Vec<u8>,
+) -> Result<Transcript, String> {
+    let result = stt.transcribe_detailed(audio).await;
+
+    let mut transcript = result.map_err(|e| format!("{e:#}"))?;
+    if let Some(language) = &transcript.detected_language {
+        tracing::info!("Detected language: {language}");
+    }
+    if config.basic_cleanup {
+        transcript.text = echoes_stt::basic_cleanup(&transcript.text);
+    }
+    if config.post_processing.enabled {
+        // A failed clean-up shouldn't lose the transcript
+        match crate::post_processing::post_process(config, &transcript.text).await {
+            Ok(processed) => transcript.text = processed,
+            Err(e) => tracing::warn!("Post-processing failed, using the raw transcript: {e}"),
+        }
+    }
+
+    Ok(transcript)
+}
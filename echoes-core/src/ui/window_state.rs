@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+use echoes_config::WindowState;
+use eframe::egui;
+
+/// How long the geometry must stay unchanged before it is saved, so dragging
+/// or resizing doesn't write the config on every frame
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// Watches the main window geometry and reports it once it settles
+pub struct WindowTracker {
+    pending: Option<(WindowState, Instant)>,
+    placement_checked: bool,
+}
+
+impl WindowTracker {
+    pub const fn new() -> Self {
+        Self {
+            pending: None,
+            placement_checked: false,
+        }
+    }
+
+    /// Returns the new geometry once it has settled and differs from `saved`
+    pub fn update(&mut self, ctx: &egui::Context, saved: Option<WindowState>) -> Option<WindowState> {
+        let (current, monitor_size) = ctx.input(|i| {
+            let viewport = i.viewport();
+            let current = viewport
+                .inner_rect
+                .zip(viewport.outer_rect)
+                .map(|(inner, outer)| WindowState {
+                    width: inner.width(),
+                    height: inner.height(),
+                    x: outer.min.x,
+                    y: outer.min.y,
+                });
+            (current, viewport.monitor_size)
+        });
+        let current = current?;
+
+        if !self.placement_checked {
+            self.placement_checked = true;
+            Self::ensure_visible(ctx, current, monitor_size);
+        }
+
+        if saved == Some(current) {
+            self.pending = None;
+            return None;
+        }
+
+        match self.pending {
+            Some((pending, since)) if pending == current => {
+                if since.elapsed() >= SAVE_DELAY {
+                    self.pending = None;
+                    return Some(current);
+                }
+            }
+            _ => self.pending = Some((current, Instant::now())),
+        }
+        ctx.request_repaint_after(SAVE_DELAY);
+        None
+    }
+
+    /// Brings a restored window back on screen when it no longer fits the
+    /// monitor layout, e.g. after a monitor was disconnected
+    fn ensure_visible(ctx: &egui::Context, current: WindowState, monitor_size: Option<egui::Vec2>) {
+        match monitor_size {
+            // The window doesn't overlap any monitor; move it to the primary one
+            None => {
+                ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::Pos2::ZERO));
+            }
+            Some(size) if current.width > size.x || current.height > size.y => {
+                let fitted = egui::vec2(current.width.min(size.x), current.height.min(size.y));
+                ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(fitted));
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+impl Default for WindowTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
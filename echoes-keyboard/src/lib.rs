@@ -1,10 +1,16 @@
 use std::{
-    sync::{mpsc, Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::Result;
-use echoes_config::{is_modifier_key, KeyCode, RecordingShortcut, ShortcutMode};
+use echoes_config::{
+    is_modifier_key, normalize_modifier, KeyCode, OutputMethod, OutputPlacement, RecordingShortcut, ShortcutMode,
+};
 use rdev::{listen, Event, EventType};
 
 pub mod keys;
@@ -27,40 +33,163 @@ impl ErrorHandler for ChannelErrorHandler {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum KeyboardEvent {
     RecordingKeyPressed,
-    RecordingKeyReleased,
+    /// The recording shortcut was released (hold mode) or pressed again
+    /// (toggle mode). `to_clipboard` is set when Shift was held at that
+    /// moment, asking for the transcript to be copied instead of typed, e.g.
+    /// for password fields. Such transcripts are kept out of the history,
+    /// notifications and subtitle export.
+    ///
+    /// Shift only has this meaning when the shortcut doesn't use it. Pressing
+    /// it during a hold then doesn't count as another key, so unlike other
+    /// keys it doesn't cancel the recording with `OtherKeyPressed`; a release
+    /// shorter than the minimum hold is still `RecordingCancelled`.
+    RecordingKeyReleased {
+        to_clipboard: bool,
+    },
     OtherKeyPressed,
     ListenerError(String),
     ShortcutRecorded(RecordingShortcut),
     RecordingCancelled,
+    /// The retype shortcut was pressed and all keys are up again, so the last
+    /// transcript can be output without modifiers interfering
+    RetypeLast,
+    /// A key went down or up while key observation is enabled
+    KeyObserved {
+        code: KeyCode,
+        scan_code: u32,
+        pressed: bool,
+    },
+}
+
+/// Whether a key went down or up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputEventKind {
+    Press,
+    Release,
+}
+
+/// Platform-independent key event driving the shortcut state machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct InputEvent {
+    kind: InputEventKind,
+    code: KeyCode,
+    /// Physical scan code of the key
+    scan_code: u32,
+}
+
+impl InputEvent {
+    /// Adapts an `rdev` event; returns `None` for non-key events and keys we
+    /// have no `KeyCode` for
+    fn from_rdev(event: &Event) -> Option<Self> {
+        let (kind, key) = match event.event_type {
+            EventType::KeyPress(key) => (InputEventKind::Press, key),
+            EventType::KeyRelease(key) => (InputEventKind::Release, key),
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            code: rdev_key_to_keycode(key)?,
+            scan_code: event.position_code,
+        })
+    }
 }
 
+#[allow(clippy::struct_excessive_bools)]
 struct ListenerState {
     pressed_keys: Vec<KeyCode>,
+    /// Physical scan codes of the currently pressed keys
+    pressed_scan_codes: Vec<u32>,
     recording_active: bool,
+    /// When the current hold-mode recording started
+    pressed_at: Option<Instant>,
     recording_shortcut: bool,
     recorded_keys: Vec<KeyCode>,
+    /// Scan code of each key pressed while recording a shortcut
+    recorded_scan_codes: Vec<(KeyCode, u32)>,
+    /// Report every key as `KeyObserved` instead of matching the shortcut
+    observing_keys: bool,
+    /// Aborts any recording when pressed, see
+    /// [`KeyboardListener::set_cancel_shortcut`]
+    cancel_shortcut: Option<RecordingShortcut>,
+    /// See [`KeyboardListener::set_retype_shortcut`]
+    retype_shortcut: Option<RecordingShortcut>,
+    /// The retype shortcut matched; `RetypeLast` is sent once all keys are up
+    retype_pending: bool,
+    /// Keys whose press triggered the shortcut; their repeats and release are
+    /// consumed too, so other apps never see a release without a press
+    consumed_keys: Vec<KeyCode>,
+}
+
+impl ListenerState {
+    const fn new() -> Self {
+        Self {
+            pressed_keys: Vec::new(),
+            pressed_scan_codes: Vec::new(),
+            recording_active: false,
+            pressed_at: None,
+            recording_shortcut: false,
+            recorded_keys: Vec::new(),
+            recorded_scan_codes: Vec::new(),
+            observing_keys: false,
+            cancel_shortcut: None,
+            retype_shortcut: None,
+            retype_pending: false,
+            consumed_keys: Vec::new(),
+        }
+    }
+}
+
+/// Controls whether the listener thread re-invokes `rdev::listen` after it
+/// fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Number of restarts attempted before the final `ListenerError` is sent
+    pub max_restarts: u32,
+    /// Base wait before a restart; attempt `n` waits `n * backoff`
+    pub backoff: Duration,
 }
 
+/// Listens for global keyboard events and reports shortcut activity.
+///
+/// `rdev::listen` blocks its thread forever and offers no portable way to
+/// unregister the hook, so [`KeyboardListener::stop`] cannot join the listener
+/// thread. Instead it flips a shared flag that the event callback checks first:
+/// a stopped listener drops every event and never sends on its channel again,
+/// leaving only an inert OS hook behind until the process exits.
 pub struct KeyboardListener {
     sender: mpsc::Sender<KeyboardEvent>,
     shortcut: Arc<Mutex<RecordingShortcut>>,
     state: Arc<Mutex<ListenerState>>,
+    min_hold_ms: Arc<AtomicU64>,
+    consume_trigger: AtomicBool,
+    started: AtomicBool,
+    stopped: Arc<AtomicBool>,
+    restart_policy: RestartPolicy,
 }
 
 impl KeyboardListener {
     #[must_use]
     pub fn new(sender: mpsc::Sender<KeyboardEvent>, shortcut: RecordingShortcut) -> Self {
+        Self::with_restart_policy(sender, shortcut, RestartPolicy::default())
+    }
+
+    /// Create a listener that restarts `rdev::listen` after transient errors.
+    #[must_use]
+    pub fn with_restart_policy(
+        sender: mpsc::Sender<KeyboardEvent>, shortcut: RecordingShortcut, restart_policy: RestartPolicy,
+    ) -> Self {
         Self {
             sender,
             shortcut: Arc::new(Mutex::new(shortcut)),
-            state: Arc::new(Mutex::new(ListenerState {
-                pressed_keys: Vec::new(),
-                recording_active: false,
-                recording_shortcut: false,
-                recorded_keys: Vec::new(),
-            })),
+            state: Arc::new(Mutex::new(ListenerState::new())),
+            min_hold_ms: Arc::new(AtomicU64::new(0)),
+            consume_trigger: AtomicBool::new(false),
+            started: AtomicBool::new(false),
+            stopped: Arc::new(AtomicBool::new(false)),
+            restart_policy,
         }
     }
 
@@ -68,6 +197,7 @@ impl KeyboardListener {
         if let Ok(mut state) = self.state.lock() {
             state.recording_shortcut = true;
             state.recorded_keys.clear();
+            state.recorded_scan_codes.clear();
             tracing::debug!("Started recording shortcut");
         }
     }
@@ -77,10 +207,24 @@ impl KeyboardListener {
         if let Ok(mut state) = self.state.lock() {
             state.recording_shortcut = false;
             state.recorded_keys.clear();
+            state.recorded_scan_codes.clear();
             tracing::debug!("Stopped recording shortcut");
         }
     }
 
+    /// Report every key press and release as [`KeyboardEvent::KeyObserved`]
+    /// instead of acting on the shortcut, to diagnose keys that don't
+    /// trigger it
+    pub fn set_key_observer(&self, enabled: bool) {
+        if let Ok(mut state) = self.state.lock() {
+            state.observing_keys = enabled;
+            // Keys held across the switch would otherwise stay "pressed"
+            state.pressed_keys.clear();
+            state.pressed_scan_codes.clear();
+            tracing::debug!("Key observation {}", if enabled { "enabled" } else { "disabled" });
+        }
+    }
+
     pub fn update_shortcut(&self, new_shortcut: RecordingShortcut) {
         if let Ok(mut shortcut) = self.shortcut.lock() {
             *shortcut = new_shortcut;
@@ -88,6 +232,55 @@ impl KeyboardListener {
         }
     }
 
+    /// Set a shortcut that cancels any recording as soon as it is pressed,
+    /// sending `RecordingCancelled` and resetting the tracked keys; a way out
+    /// when a recording or key gets stuck. Its mode is ignored.
+    pub fn set_cancel_shortcut(&self, cancel_shortcut: Option<RecordingShortcut>) {
+        if let Ok(mut state) = self.state.lock() {
+            tracing::debug!("Updated cancel shortcut: {:?}", cancel_shortcut);
+            state.cancel_shortcut = cancel_shortcut;
+        }
+    }
+
+    /// Set a shortcut that asks for the last transcript to be output again,
+    /// sending `RetypeLast` once its keys are released. It is ignored while
+    /// recording; its mode is ignored.
+    pub fn set_retype_shortcut(&self, retype_shortcut: Option<RecordingShortcut>) {
+        if let Ok(mut state) = self.state.lock() {
+            tracing::debug!("Updated retype shortcut: {:?}", retype_shortcut);
+            state.retype_shortcut = retype_shortcut;
+        }
+    }
+
+    /// Set the minimum time the shortcut must be held in hold mode. Releasing
+    /// earlier sends `RecordingCancelled` instead of `RecordingKeyReleased`;
+    /// 0 disables the check.
+    pub fn set_min_hold_ms(&self, min_hold_ms: u64) {
+        self.min_hold_ms.store(min_hold_ms, Ordering::SeqCst);
+    }
+
+    /// Keep the key press that triggers the shortcut, and its release, from
+    /// reaching other apps. Only macOS and Windows can grab events; elsewhere
+    /// the keys are still delivered. Takes effect when the listener starts.
+    pub fn set_consume_trigger(&self, consume_trigger: bool) {
+        self.consume_trigger.store(consume_trigger, Ordering::SeqCst);
+    }
+
+    /// Signal the listener thread to stop handling events.
+    ///
+    /// See the type-level docs for why the underlying thread keeps running.
+    pub fn stop(&self) {
+        if !self.stopped.swap(true, Ordering::SeqCst) {
+            tracing::debug!("Keyboard listener stopped");
+        }
+    }
+
+    /// Whether [`KeyboardListener::stop`] has been called.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
     /// Start listening for keyboard events in a background thread.
     ///
     /// # Errors
@@ -96,74 +289,196 @@ impl KeyboardListener {
     /// platform permissions are insufficient.
     pub fn start_listening(&self) -> Result<()> {
         tracing::debug!("Starting keyboard listener thread");
+        if self.started.swap(true, Ordering::SeqCst) {
+            anyhow::bail!("Keyboard listener is already running");
+        }
 
         let sender = self.sender.clone();
         let shortcut = self.shortcut.clone();
         let state = self.state.clone();
+        let min_hold_ms = self.min_hold_ms.clone();
+        let stopped = self.stopped.clone();
+        let policy = self.restart_policy;
+        let consume_trigger = self.consume_trigger.load(Ordering::SeqCst);
+        if consume_trigger && !CAN_GRAB {
+            tracing::warn!(
+                "Consuming the trigger key is only supported on macOS and Windows; it will reach other apps"
+            );
+        }
 
-        thread::spawn(move || {
+        let spawned = thread::Builder::new().name("echoes-keyboard".into()).spawn(move || {
+            let _span = tracing::info_span!("keyboard_listener").entered();
             tracing::debug!("Keyboard listener thread started");
 
             let error_handler = ChannelErrorHandler { sender: sender.clone() };
+            let mut attempt = 0;
 
-            match listen(move |event| {
-                handle_event(&event, &sender, &shortcut, &state);
-            }) {
-                Ok(()) => {
-                    tracing::debug!("Keyboard listener exited normally");
-                }
-                Err(error) => {
-                    error_handler.handle_error(&format!(
-                        "Keyboard listener failed: {error:?}. This might be due to missing accessibility permissions."
-                    ));
+            loop {
+                // Each run shares the same shortcut/state, so changes made
+                // between restarts are picked up
+                let sender = sender.clone();
+                let shortcut = shortcut.clone();
+                let state = state.clone();
+                let min_hold_ms = min_hold_ms.clone();
+                let callback_stopped = stopped.clone();
+
+                match run_hook(consume_trigger, move |event| {
+                    if callback_stopped.load(Ordering::SeqCst) {
+                        return false;
+                    }
+                    InputEvent::from_rdev(event).is_some_and(|input| {
+                        let min_hold = Duration::from_millis(min_hold_ms.load(Ordering::SeqCst));
+                        handle_event(input, &sender, &shortcut, &state, min_hold)
+                    })
+                }) {
+                    Ok(()) => {
+                        tracing::debug!("Keyboard listener exited normally");
+                    }
+                    Err(_) if stopped.load(Ordering::SeqCst) => {
+                        tracing::debug!("Keyboard listener exited after stop");
+                    }
+                    Err(error) if attempt < policy.max_restarts => {
+                        attempt += 1;
+                        let wait = policy.backoff * attempt;
+                        tracing::warn!(
+                            "Keyboard listener failed: {error}; restarting in {wait:?} (attempt {attempt}/{})",
+                            policy.max_restarts
+                        );
+                        thread::sleep(wait);
+                        if !stopped.load(Ordering::SeqCst) {
+                            continue;
+                        }
+                    }
+                    Err(error) => {
+                        error_handler.handle_error(&format!(
+                            "Keyboard listener failed: {error}. This might be due to missing accessibility permissions."
+                        ));
+                    }
                 }
+                break;
             }
         });
 
+        if let Err(e) = spawned {
+            self.started.store(false, Ordering::SeqCst);
+            anyhow::bail!("Failed to start keyboard listener thread: {e}");
+        }
         Ok(())
     }
 }
 
+/// Whether events can be kept from other apps with `rdev::grab`
+const CAN_GRAB: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+/// Runs the OS keyboard hook until it fails. `on_event` returns whether the
+/// event should be kept from other apps, which only happens when `grab` is
+/// set.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn run_hook(grab: bool, on_event: impl Fn(&Event) -> bool + 'static) -> std::result::Result<(), String> {
+    if grab {
+        rdev::grab(move |event| if on_event(&event) { None } else { Some(event) }).map_err(|e| format!("{e:?}"))
+    } else {
+        listen(move |event| {
+            on_event(&event);
+        })
+        .map_err(|e| format!("{e:?}"))
+    }
+}
+
+/// Runs the OS keyboard hook until it fails; events can't be grabbed here, so
+/// they always reach other apps
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn run_hook(grab: bool, on_event: impl Fn(&Event) -> bool + 'static) -> std::result::Result<(), String> {
+    let _ = grab;
+    listen(move |event| {
+        on_event(&event);
+    })
+    .map_err(|e| format!("{e:?}"))
+}
+
+/// Updates the shortcut state with `event`. Returns whether the event
+/// triggered the shortcut, or repeats or releases a key that did, so it may be
+/// kept from other apps.
 fn handle_event(
-    event: &Event, sender: &mpsc::Sender<KeyboardEvent>, shortcut: &Arc<Mutex<RecordingShortcut>>,
-    state: &Arc<Mutex<ListenerState>>,
-) {
+    event: InputEvent, sender: &mpsc::Sender<KeyboardEvent>, shortcut: &Arc<Mutex<RecordingShortcut>>,
+    state: &Arc<Mutex<ListenerState>>, min_hold: Duration,
+) -> bool {
     if let Ok(state_guard) = state.lock() {
         if state_guard.recording_shortcut {
             drop(state_guard);
             handle_recording_event(event, sender, state);
-            return;
+            return false;
+        }
+        if state_guard.observing_keys {
+            let _ = sender.send(KeyboardEvent::KeyObserved {
+                code: event.code,
+                scan_code: event.scan_code,
+                pressed: event.kind == InputEventKind::Press,
+            });
+            return false;
         }
     }
 
-    match event.event_type {
-        EventType::KeyPress(key) => {
-            if let Some(keycode) = rdev_key_to_keycode(key) {
-                handle_key_press(keycode, sender, shortcut, state);
-            }
-        }
-        EventType::KeyRelease(key) => {
-            if let Some(keycode) = rdev_key_to_keycode(key) {
-                handle_key_release(keycode, sender, shortcut, state);
-            }
-        }
-        _ => {}
+    match event.kind {
+        InputEventKind::Press => handle_key_press(event.code, event.scan_code, sender, shortcut, state),
+        InputEventKind::Release => handle_key_release(event.code, event.scan_code, sender, shortcut, state, min_hold),
     }
 }
 
 fn handle_key_press(
-    keycode: KeyCode, sender: &mpsc::Sender<KeyboardEvent>, shortcut: &Arc<Mutex<RecordingShortcut>>,
+    keycode: KeyCode, scan_code: u32, sender: &mpsc::Sender<KeyboardEvent>, shortcut: &Arc<Mutex<RecordingShortcut>>,
     state: &Arc<Mutex<ListenerState>>,
-) {
+) -> bool {
     if let Ok(mut state) = state.lock() {
-        if !state.pressed_keys.contains(&keycode) {
-            state.pressed_keys.push(keycode);
-            tracing::debug!("Key pressed: {:?}", keycode);
+        // Auto-repeat sends further presses for a key that is already down;
+        // only the first one may trigger the shortcut
+        if state.pressed_keys.contains(&keycode) {
+            return state.consumed_keys.contains(&keycode);
+        }
+        state.pressed_keys.push(keycode);
+        tracing::debug!("Key pressed: {:?} (scan code {})", keycode, scan_code);
+        if !state.pressed_scan_codes.contains(&scan_code) {
+            state.pressed_scan_codes.push(scan_code);
+        }
+
+        if state
+            .cancel_shortcut
+            .as_ref()
+            .is_some_and(|cancel| is_shortcut_active(&state.pressed_keys, &state.pressed_scan_codes, cancel))
+        {
+            tracing::debug!("Cancel shortcut pressed, resetting listener state");
+            state.recording_active = false;
+            state.pressed_at = None;
+            state.pressed_keys.clear();
+            state.pressed_scan_codes.clear();
+            let _ = sender.send(KeyboardEvent::RecordingCancelled);
+            return false;
+        }
+
+        if !state.recording_active
+            && state
+                .retype_shortcut
+                .as_ref()
+                .is_some_and(|retype| is_shortcut_active(&state.pressed_keys, &state.pressed_scan_codes, retype))
+        {
+            tracing::debug!("Retype shortcut pressed, waiting for its keys to be released");
+            state.retype_pending = true;
+            return false;
         }
 
         if let Ok(shortcut) = shortcut.lock() {
-            if is_shortcut_active(&state.pressed_keys, &shortcut) {
+            // While recording, Shift is the clipboard modifier rather than
+            // another key, so it neither cancels a hold nor stops a toggle
+            // from matching
+            let keys = if state.recording_active {
+                without_clipboard_modifier(&state.pressed_keys, &shortcut)
+            } else {
+                state.pressed_keys.clone()
+            };
+            if is_shortcut_active(&keys, &state.pressed_scan_codes, &shortcut) {
                 handle_shortcut_activation(&mut state, &shortcut, sender);
+                state.consumed_keys.push(keycode);
+                return true;
             } else if state.recording_active && shortcut.mode == ShortcutMode::Hold {
                 // Any other key during hold mode cancels recording
                 state.recording_active = false;
@@ -171,26 +486,51 @@ fn handle_key_press(
             }
         }
     }
+    false
 }
 
 fn handle_key_release(
-    keycode: KeyCode, sender: &mpsc::Sender<KeyboardEvent>, shortcut: &Arc<Mutex<RecordingShortcut>>,
-    state: &Arc<Mutex<ListenerState>>,
-) {
+    keycode: KeyCode, scan_code: u32, sender: &mpsc::Sender<KeyboardEvent>, shortcut: &Arc<Mutex<RecordingShortcut>>,
+    state: &Arc<Mutex<ListenerState>>, min_hold: Duration,
+) -> bool {
     if let Ok(mut state) = state.lock() {
         state.pressed_keys.retain(|&k| k != keycode);
+        state.pressed_scan_codes.retain(|&c| c != scan_code);
         tracing::debug!("Key released: {:?}", keycode);
+        let consumed = state.consumed_keys.contains(&keycode);
+        state.consumed_keys.retain(|&k| k != keycode);
+
+        if state.retype_pending && state.pressed_keys.is_empty() {
+            state.retype_pending = false;
+            let _ = sender.send(KeyboardEvent::RetypeLast);
+        }
 
         if let Ok(shortcut) = shortcut.lock() {
             if shortcut.mode == ShortcutMode::Hold
                 && state.recording_active
-                && !is_shortcut_active(&state.pressed_keys, &shortcut)
+                && !is_shortcut_active(
+                    &without_clipboard_modifier(&state.pressed_keys, &shortcut),
+                    &state.pressed_scan_codes,
+                    &shortcut,
+                )
             {
                 state.recording_active = false;
-                let _ = sender.send(KeyboardEvent::RecordingKeyReleased);
+                let held = state
+                    .pressed_at
+                    .take()
+                    .map_or(Duration::MAX, |pressed_at| pressed_at.elapsed());
+                if held < min_hold {
+                    tracing::debug!("Shortcut released after {:?}, below minimum hold {:?}", held, min_hold);
+                    let _ = sender.send(KeyboardEvent::RecordingCancelled);
+                } else {
+                    let to_clipboard = clipboard_modifier_held(&state.pressed_keys, &shortcut);
+                    let _ = sender.send(KeyboardEvent::RecordingKeyReleased { to_clipboard });
+                }
             }
         }
+        return consumed;
     }
+    false
 }
 
 fn handle_shortcut_activation(
@@ -200,13 +540,15 @@ fn handle_shortcut_activation(
         ShortcutMode::Hold => {
             if !state.recording_active {
                 state.recording_active = true;
+                state.pressed_at = Some(Instant::now());
                 let _ = sender.send(KeyboardEvent::RecordingKeyPressed);
             }
         }
         ShortcutMode::Toggle => {
             if state.recording_active {
                 state.recording_active = false;
-                let _ = sender.send(KeyboardEvent::RecordingKeyReleased);
+                let to_clipboard = clipboard_modifier_held(&state.pressed_keys, shortcut);
+                let _ = sender.send(KeyboardEvent::RecordingKeyReleased { to_clipboard });
             } else {
                 state.recording_active = true;
                 let _ = sender.send(KeyboardEvent::RecordingKeyPressed);
@@ -215,24 +557,15 @@ fn handle_shortcut_activation(
     }
 }
 
-fn handle_recording_event(event: &Event, sender: &mpsc::Sender<KeyboardEvent>, state: &Arc<Mutex<ListenerState>>) {
-    match event.event_type {
-        EventType::KeyPress(key) => {
-            if let Some(keycode) = rdev_key_to_keycode(key) {
-                handle_recording_key_press(keycode, sender, state);
-            }
-        }
-        EventType::KeyRelease(key) => {
-            if let Some(keycode) = rdev_key_to_keycode(key) {
-                handle_recording_key_release(keycode, sender, state);
-            }
-        }
-        _ => {}
+fn handle_recording_event(event: InputEvent, sender: &mpsc::Sender<KeyboardEvent>, state: &Arc<Mutex<ListenerState>>) {
+    match event.kind {
+        InputEventKind::Press => handle_recording_key_press(event.code, event.scan_code, sender, state),
+        InputEventKind::Release => handle_recording_key_release(event.code, sender, state),
     }
 }
 
 fn handle_recording_key_press(
-    keycode: KeyCode, sender: &mpsc::Sender<KeyboardEvent>, state: &Arc<Mutex<ListenerState>>,
+    keycode: KeyCode, scan_code: u32, sender: &mpsc::Sender<KeyboardEvent>, state: &Arc<Mutex<ListenerState>>,
 ) {
     if let Ok(mut state) = state.lock() {
         tracing::debug!("Recording mode - key pressed: {:?}", keycode);
@@ -248,7 +581,8 @@ fn handle_recording_key_press(
 
         if !state.recorded_keys.contains(&keycode) {
             state.recorded_keys.push(keycode);
-            tracing::debug!("Recorded key: {:?}", keycode);
+            state.recorded_scan_codes.push((keycode, scan_code));
+            tracing::debug!("Recorded key: {:?} (scan code {})", keycode, scan_code);
         }
     }
 }
@@ -271,7 +605,9 @@ fn cancel_recording(state: &mut ListenerState, sender: &mpsc::Sender<KeyboardEve
     tracing::debug!("Escape pressed, cancelling recording");
     state.recording_shortcut = false;
     state.recorded_keys.clear();
+    state.recorded_scan_codes.clear();
     state.pressed_keys.clear();
+    state.pressed_scan_codes.clear();
     let _ = sender.send(KeyboardEvent::RecordingCancelled);
 }
 
@@ -283,10 +619,16 @@ fn finalize_recording(state: &mut ListenerState, sender: &mpsc::Sender<KeyboardE
 
     let (main_key, modifiers) = extract_shortcut_from_keys(&state.recorded_keys);
     if let Some(main_key) = main_key {
+        let scan_code = state
+            .recorded_scan_codes
+            .iter()
+            .find(|(key, _)| *key == main_key)
+            .map(|&(_, code)| code);
         let new_shortcut = RecordingShortcut {
             mode: ShortcutMode::Hold,
             key: main_key,
             modifiers,
+            scan_code,
         };
         tracing::debug!(
             "Created new shortcut: key={:?}, modifiers={:?}",
@@ -295,6 +637,7 @@ fn finalize_recording(state: &mut ListenerState, sender: &mpsc::Sender<KeyboardE
         );
         state.recording_shortcut = false;
         state.recorded_keys.clear();
+        state.recorded_scan_codes.clear();
         let _ = sender.send(KeyboardEvent::ShortcutRecorded(new_shortcut));
     } else {
         tracing::debug!("No main key found in recorded keys");
@@ -332,7 +675,7 @@ fn normalize_modifier_keys(modifier_keys: &[KeyCode]) -> Vec<KeyCode> {
     let mut normalized = Vec::new();
 
     for key in modifier_keys {
-        let normalized_key = normalize_modifier_key(*key);
+        let normalized_key = normalize_modifier(key);
         if !normalized.contains(&normalized_key) {
             normalized.push(normalized_key);
         }
@@ -341,68 +684,657 @@ fn normalize_modifier_keys(modifier_keys: &[KeyCode]) -> Vec<KeyCode> {
     normalized
 }
 
-const fn normalize_modifier_key(key: KeyCode) -> KeyCode {
-    match key {
-        KeyCode::ControlLeft | KeyCode::ControlRight => KeyCode::ControlLeft,
-        KeyCode::ShiftLeft | KeyCode::ShiftRight => KeyCode::ShiftLeft,
-        KeyCode::MetaLeft | KeyCode::MetaRight => KeyCode::MetaLeft,
-        _ => key,
+/// Whether Shift is free to act as the clipboard modifier, i.e. the shortcut
+/// doesn't use it itself
+fn has_clipboard_modifier(shortcut: &RecordingShortcut) -> bool {
+    normalize_modifier(&shortcut.key) != KeyCode::ShiftLeft
+        && !shortcut
+            .modifiers
+            .iter()
+            .any(|key| normalize_modifier(key) == KeyCode::ShiftLeft)
+}
+
+/// Whether Shift is held on top of the shortcut, asking for the transcript to
+/// go to the clipboard
+fn clipboard_modifier_held(pressed_keys: &[KeyCode], shortcut: &RecordingShortcut) -> bool {
+    has_clipboard_modifier(shortcut)
+        && pressed_keys
+            .iter()
+            .any(|key| matches!(key, KeyCode::ShiftLeft | KeyCode::ShiftRight))
+}
+
+/// The pressed keys without the clipboard modifier
+fn without_clipboard_modifier(pressed_keys: &[KeyCode], shortcut: &RecordingShortcut) -> Vec<KeyCode> {
+    if !has_clipboard_modifier(shortcut) {
+        return pressed_keys.to_vec();
     }
+    pressed_keys
+        .iter()
+        .copied()
+        .filter(|key| !matches!(key, KeyCode::ShiftLeft | KeyCode::ShiftRight))
+        .collect()
 }
 
-fn is_shortcut_active(pressed_keys: &[KeyCode], shortcut: &RecordingShortcut) -> bool {
-    // Check if main key is pressed
-    if !pressed_keys.contains(&shortcut.key) {
+fn is_shortcut_active(pressed_keys: &[KeyCode], pressed_scan_codes: &[u32], shortcut: &RecordingShortcut) -> bool {
+    // Left and right variants of a modifier are interchangeable
+    let pressed_keys: Vec<KeyCode> = pressed_keys.iter().map(normalize_modifier).collect();
+    let key = normalize_modifier(&shortcut.key);
+    let modifiers: Vec<KeyCode> = shortcut.modifiers.iter().map(normalize_modifier).collect();
+
+    // Check if main key is pressed, by physical position when a scan code is set
+    let main_key_pressed = shortcut.scan_code.map_or_else(
+        || pressed_keys.contains(&key),
+        |scan_code| pressed_scan_codes.contains(&scan_code),
+    );
+    if !main_key_pressed {
         return false;
     }
 
     // Check if all modifiers are pressed
-    for modifier in &shortcut.modifiers {
-        if !pressed_keys.contains(modifier) {
-            return false;
-        }
+    if !modifiers.iter().all(|modifier| pressed_keys.contains(modifier)) {
+        return false;
     }
 
     // For shortcuts with modifiers, ensure no extra modifier keys are pressed
     // This prevents Ctrl+Shift+A from triggering when the shortcut is just Ctrl+A
-    if !shortcut.modifiers.is_empty() {
-        let modifier_keys = [
-            KeyCode::ControlLeft,
-            KeyCode::ControlRight,
-            KeyCode::ShiftLeft,
-            KeyCode::ShiftRight,
-            KeyCode::Alt,
-            KeyCode::AltGr,
-            KeyCode::MetaLeft,
-            KeyCode::MetaRight,
-        ];
-
-        for key in pressed_keys {
-            if modifier_keys.contains(key) && !shortcut.modifiers.contains(key) && *key != shortcut.key {
-                return false;
-            }
+    if !modifiers.is_empty() {
+        if pressed_keys
+            .iter()
+            .any(|pressed| is_modifier_key(pressed) && !modifiers.contains(pressed) && *pressed != key)
+        {
+            return false;
         }
+    } else if is_modifier_key(&key) && pressed_keys.iter().any(|pressed| *pressed != key) {
+        // A bare modifier shortcut (e.g. hold Ctrl) is only meant when the
+        // modifier is held on its own; Ctrl+C is a copy, not dictation
+        return false;
     }
 
     true
 }
 
+/// Options controlling how [`type_text_with_options`] types text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypingOptions {
+    /// Delay before anything is typed, giving the target field time to regain
+    /// focus
+    pub initial_delay_ms: u64,
+    /// Delay between graphemes; `0` types the whole text in one call
+    pub per_char_delay_ms: u64,
+}
+
 /// Type the given text using the system's text input mechanism.
 ///
 /// # Errors
 ///
 /// Returns an error if the text input system cannot be initialized or if text
 /// cannot be typed.
-#[allow(dead_code)]
 pub fn type_text(text: &str) -> Result<()> {
+    type_text_with_options(text, TypingOptions::default())
+}
+
+/// Type the given text, honoring an initial delay and an optional per-grapheme
+/// delay.
+///
+/// Typing grapheme by grapheme keeps multi-codepoint characters (emoji,
+/// combining accents) intact while slowing input down for applications that
+/// drop characters when text arrives too fast.
+///
+/// # Errors
+///
+/// Returns an error if the text input system cannot be initialized or if text
+/// cannot be typed.
+pub fn type_text_with_options(text: &str, opts: TypingOptions) -> Result<()> {
     use enigo::{Enigo, Keyboard, Settings};
 
+    if opts.initial_delay_ms > 0 {
+        thread::sleep(Duration::from_millis(opts.initial_delay_ms));
+    }
+
     let mut enigo =
-        Enigo::new(&Settings::default()).map_err(|e| anyhow::anyhow!("Failed to create Enigo instance: {}", e))?;
+        Enigo::new(&Settings::default()).map_err(|e| anyhow::anyhow!("Failed to create Enigo instance: {e}"))?;
 
-    enigo
-        .text(text)
-        .map_err(|e| anyhow::anyhow!("Failed to type text: {}", e))?;
+    let delay = Duration::from_millis(opts.per_char_delay_ms);
+    for (i, chunk) in typing_chunks(text, opts).into_iter().enumerate() {
+        if i > 0 {
+            thread::sleep(delay);
+        }
+        enigo
+            .text(chunk)
+            .map_err(|e| anyhow::anyhow!("Failed to type text: {e}"))?;
+    }
 
     Ok(())
 }
+
+/// Pieces of `text` typed one call at a time: graphemes with a per-char
+/// delay, otherwise the whole text
+fn typing_chunks(text: &str, opts: TypingOptions) -> Vec<&str> {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    if opts.per_char_delay_ms == 0 {
+        vec![text]
+    } else {
+        text.graphemes(true).collect()
+    }
+}
+
+/// Returned by [`paste_text`] when the text could not be pasted. The user's
+/// clipboard has been put back by then, so callers can fall back to
+/// [`type_text`].
+#[derive(Debug)]
+pub struct PasteFailed(pub String);
+
+impl std::fmt::Display for PasteFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for PasteFailed {}
+
+/// Paste the given text into the focused application via the clipboard.
+///
+/// With `restore_clipboard` the previous clipboard text is put back after the
+/// paste; otherwise the pasted text stays on the clipboard. When the paste
+/// fails, the previous text is always restored. Whether the target
+/// application actually handled the shortcut can't be detected.
+///
+/// # Errors
+///
+/// Returns [`PasteFailed`] if the clipboard cannot be accessed or if the
+/// paste shortcut cannot be sent.
+pub fn paste_text(text: &str, restore_clipboard: bool) -> Result<()> {
+    use arboard::Clipboard;
+
+    let mut clipboard = Clipboard::new().map_err(|e| PasteFailed(format!("Failed to access clipboard: {e}")))?;
+    let previous = clipboard.get_text().ok();
+
+    clipboard
+        .set_text(text)
+        .map_err(|e| PasteFailed(format!("Failed to set clipboard text: {e}")))?;
+
+    let paste_result = send_paste_shortcut();
+
+    // Never leave the transcript in place of the user's clipboard when
+    // nothing was pasted
+    if restore_clipboard || paste_result.is_err() {
+        // The target application reads the clipboard asynchronously after the
+        // shortcut, so wait before putting the previous contents back
+        thread::sleep(Duration::from_millis(100));
+
+        if let Some(previous) = previous {
+            if let Err(e) = clipboard.set_text(previous) {
+                tracing::warn!("Failed to restore previous clipboard contents: {e}");
+            }
+        } else {
+            tracing::debug!("Clipboard held no text before pasting, nothing to restore");
+        }
+    }
+
+    paste_result.map_err(|e| PasteFailed(e).into())
+}
+
+/// Sends Cmd+V on macOS and Ctrl+V elsewhere
+fn send_paste_shortcut() -> std::result::Result<(), String> {
+    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+
+    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create Enigo instance: {e}"))?;
+
+    let modifier = if cfg!(target_os = "macos") {
+        Key::Meta
+    } else {
+        Key::Control
+    };
+
+    // Give the clipboard owner a moment to publish the new contents
+    thread::sleep(Duration::from_millis(50));
+
+    enigo
+        .key(modifier, Direction::Press)
+        .and_then(|()| enigo.key(Key::Unicode('v'), Direction::Click))
+        .and_then(|()| enigo.key(modifier, Direction::Release))
+        .map_err(|e| format!("Failed to send paste shortcut: {e}"))
+}
+
+/// Clipboard kept open after [`copy_text`]: on Linux the copied text is only
+/// available while the clipboard that set it is alive
+static COPY_CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+/// Put the given text on the clipboard without pasting it.
+///
+/// # Errors
+///
+/// Returns an error if the clipboard cannot be accessed.
+pub fn copy_text(text: &str) -> Result<()> {
+    let mut guard = COPY_CLIPBOARD
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Clipboard lock poisoned"))?;
+    let clipboard = match &mut *guard {
+        Some(clipboard) => clipboard,
+        None => {
+            guard.insert(arboard::Clipboard::new().map_err(|e| anyhow::anyhow!("Failed to access clipboard: {e}"))?)
+        }
+    };
+    clipboard
+        .set_text(text)
+        .map_err(|e| anyhow::anyhow!("Failed to set clipboard text: {e}"))
+}
+
+/// Deliver text to the focused application using the configured method.
+///
+/// With [`OutputPlacement::ReplaceSelection`] the text is always pasted,
+/// since pasting replaces selected text everywhere; see
+/// [`delivery_method`].
+///
+/// `restore_clipboard` is passed on to [`paste_text`], `typing` to
+/// [`type_text_with_options`].
+///
+/// # Errors
+///
+/// Returns an error if the selected output method fails; [`PasteFailed`]
+/// when pasting did.
+pub fn output_text(
+    text: &str, method: OutputMethod, placement: OutputPlacement, restore_clipboard: bool, typing: TypingOptions,
+) -> Result<()> {
+    match delivery_method(method, placement) {
+        OutputMethod::Type => type_text_with_options(text, typing),
+        OutputMethod::Paste => paste_text(text, restore_clipboard),
+    }
+}
+
+/// The method text is actually delivered with.
+///
+/// Typing is swapped for pasting when the selection must be replaced, because
+/// some applications insert synthesized keystrokes next to the selection
+/// instead of over it.
+#[must_use]
+pub const fn delivery_method(method: OutputMethod, placement: OutputPlacement) -> OutputMethod {
+    match placement {
+        OutputPlacement::Insert => method,
+        OutputPlacement::ReplaceSelection => OutputMethod::Paste,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drives the shortcut state machine with synthetic key events
+    struct Harness {
+        sender: mpsc::Sender<KeyboardEvent>,
+        receiver: mpsc::Receiver<KeyboardEvent>,
+        shortcut: Arc<Mutex<RecordingShortcut>>,
+        state: Arc<Mutex<ListenerState>>,
+        min_hold: Duration,
+    }
+
+    impl Harness {
+        fn new(shortcut: RecordingShortcut) -> Self {
+            Self::with_min_hold(shortcut, Duration::ZERO)
+        }
+
+        fn with_min_hold(shortcut: RecordingShortcut, min_hold: Duration) -> Self {
+            let (sender, receiver) = mpsc::channel();
+            Self {
+                sender,
+                receiver,
+                shortcut: Arc::new(Mutex::new(shortcut)),
+                state: Arc::new(Mutex::new(ListenerState::new())),
+                min_hold,
+            }
+        }
+
+        /// Returns whether the event would be consumed
+        fn send(&self, kind: InputEventKind, code: KeyCode) -> bool {
+            let event = InputEvent {
+                kind,
+                code,
+                scan_code: 0,
+            };
+            handle_event(event, &self.sender, &self.shortcut, &self.state, self.min_hold)
+        }
+
+        fn press(&self, code: KeyCode) -> bool {
+            self.send(InputEventKind::Press, code)
+        }
+
+        fn release(&self, code: KeyCode) -> bool {
+            self.send(InputEventKind::Release, code)
+        }
+
+        fn events(&self) -> Vec<KeyboardEvent> {
+            self.receiver.try_iter().collect()
+        }
+    }
+
+    #[test]
+    fn test_observed_keys_bypass_shortcut() {
+        let harness = Harness::new(RecordingShortcut::new(ShortcutMode::Hold, KeyCode::F9, vec![]));
+        harness.state.lock().unwrap().observing_keys = true;
+
+        harness.press(KeyCode::F9);
+        harness.release(KeyCode::F9);
+        assert_eq!(
+            harness.events(),
+            vec![
+                KeyboardEvent::KeyObserved {
+                    code: KeyCode::F9,
+                    scan_code: 0,
+                    pressed: true,
+                },
+                KeyboardEvent::KeyObserved {
+                    code: KeyCode::F9,
+                    scan_code: 0,
+                    pressed: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_toggle_fires_once_per_press() {
+        let harness = Harness::new(RecordingShortcut::new(
+            ShortcutMode::Toggle,
+            KeyCode::Slash,
+            vec![KeyCode::ControlLeft],
+        ));
+
+        harness.press(KeyCode::ControlLeft);
+        harness.press(KeyCode::Slash);
+        // Auto-repeat while the key is held must not toggle again
+        harness.press(KeyCode::Slash);
+        harness.release(KeyCode::Slash);
+        harness.release(KeyCode::ControlLeft);
+        assert_eq!(harness.events(), vec![KeyboardEvent::RecordingKeyPressed]);
+
+        harness.press(KeyCode::ControlLeft);
+        harness.press(KeyCode::Slash);
+        harness.release(KeyCode::Slash);
+        harness.release(KeyCode::ControlLeft);
+        assert_eq!(
+            harness.events(),
+            vec![KeyboardEvent::RecordingKeyReleased { to_clipboard: false }]
+        );
+    }
+
+    #[test]
+    fn test_only_trigger_key_is_consumed() {
+        let harness = Harness::new(RecordingShortcut::new(
+            ShortcutMode::Hold,
+            KeyCode::F9,
+            vec![KeyCode::ControlLeft],
+        ));
+
+        // The modifier already reached other apps before the shortcut matched
+        assert!(!harness.press(KeyCode::ControlLeft));
+        assert!(harness.press(KeyCode::F9));
+        assert!(harness.press(KeyCode::F9));
+        assert!(harness.release(KeyCode::F9));
+        assert!(!harness.release(KeyCode::ControlLeft));
+        assert!(!harness.press(KeyCode::F9));
+        assert!(!harness.release(KeyCode::F9));
+    }
+
+    #[test]
+    fn test_hold_cancelled_by_other_key() {
+        let harness = Harness::new(RecordingShortcut::new(
+            ShortcutMode::Hold,
+            KeyCode::F9,
+            vec![KeyCode::ControlLeft],
+        ));
+
+        harness.press(KeyCode::ControlLeft);
+        harness.press(KeyCode::F9);
+        harness.press(KeyCode::Alt);
+        harness.release(KeyCode::Alt);
+        harness.release(KeyCode::F9);
+        harness.release(KeyCode::ControlLeft);
+
+        assert_eq!(
+            harness.events(),
+            vec![KeyboardEvent::RecordingKeyPressed, KeyboardEvent::OtherKeyPressed]
+        );
+    }
+
+    #[test]
+    fn test_shift_at_release_requests_clipboard() {
+        let harness = Harness::new(RecordingShortcut::new(
+            ShortcutMode::Hold,
+            KeyCode::F9,
+            vec![KeyCode::ControlLeft],
+        ));
+
+        // Shift during a hold doesn't cancel it; held at release it asks for
+        // the clipboard
+        harness.press(KeyCode::ControlLeft);
+        harness.press(KeyCode::F9);
+        harness.press(KeyCode::ShiftRight);
+        harness.release(KeyCode::F9);
+        harness.release(KeyCode::ShiftRight);
+        harness.release(KeyCode::ControlLeft);
+        assert_eq!(
+            harness.events(),
+            vec![
+                KeyboardEvent::RecordingKeyPressed,
+                KeyboardEvent::RecordingKeyReleased { to_clipboard: true }
+            ]
+        );
+
+        // A shortcut using Shift never asks for the clipboard
+        *harness.shortcut.lock().unwrap() =
+            RecordingShortcut::new(ShortcutMode::Hold, KeyCode::F9, vec![KeyCode::ShiftLeft]);
+        harness.press(KeyCode::ShiftLeft);
+        harness.press(KeyCode::F9);
+        harness.release(KeyCode::F9);
+        harness.release(KeyCode::ShiftLeft);
+        assert_eq!(
+            harness.events(),
+            vec![
+                KeyboardEvent::RecordingKeyPressed,
+                KeyboardEvent::RecordingKeyReleased { to_clipboard: false }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retype_sent_after_keys_released() {
+        let harness = Harness::new(RecordingShortcut::new(ShortcutMode::Hold, KeyCode::F9, vec![]));
+        harness.state.lock().unwrap().retype_shortcut = Some(RecordingShortcut::new(
+            ShortcutMode::Toggle,
+            KeyCode::R,
+            vec![KeyCode::ControlLeft],
+        ));
+
+        harness.press(KeyCode::ControlLeft);
+        harness.press(KeyCode::R);
+        harness.release(KeyCode::R);
+        assert!(harness.events().is_empty());
+        harness.release(KeyCode::ControlLeft);
+        assert_eq!(harness.events(), vec![KeyboardEvent::RetypeLast]);
+    }
+
+    #[test]
+    fn test_cancel_shortcut_resets_recording() {
+        let harness = Harness::new(RecordingShortcut::new(ShortcutMode::Toggle, KeyCode::F9, vec![]));
+        harness.state.lock().unwrap().cancel_shortcut = Some(RecordingShortcut::new(
+            ShortcutMode::Hold,
+            KeyCode::Escape,
+            vec![KeyCode::ControlLeft],
+        ));
+
+        harness.press(KeyCode::F9);
+        harness.release(KeyCode::F9);
+        harness.press(KeyCode::ControlLeft);
+        harness.press(KeyCode::Escape);
+        assert_eq!(
+            harness.events(),
+            vec![KeyboardEvent::RecordingKeyPressed, KeyboardEvent::RecordingCancelled]
+        );
+        let (recording_active, pressed_keys) = {
+            let state = harness.state.lock().unwrap();
+            (state.recording_active, state.pressed_keys.len())
+        };
+        assert!(!recording_active);
+        assert_eq!(pressed_keys, 0);
+    }
+
+    #[test]
+    fn test_hold_released() {
+        let harness = Harness::new(RecordingShortcut::new(ShortcutMode::Hold, KeyCode::F9, vec![]));
+
+        harness.press(KeyCode::F9);
+        harness.release(KeyCode::F9);
+
+        assert_eq!(
+            harness.events(),
+            vec![
+                KeyboardEvent::RecordingKeyPressed,
+                KeyboardEvent::RecordingKeyReleased { to_clipboard: false }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hold_below_min_hold_cancelled() {
+        let min_hold = Duration::from_millis(300);
+        let harness = Harness::with_min_hold(RecordingShortcut::new(ShortcutMode::Hold, KeyCode::F9, vec![]), min_hold);
+
+        // An accidental tap
+        harness.press(KeyCode::F9);
+        harness.release(KeyCode::F9);
+        assert_eq!(
+            harness.events(),
+            vec![KeyboardEvent::RecordingKeyPressed, KeyboardEvent::RecordingCancelled]
+        );
+
+        // Held for the minimum
+        harness.press(KeyCode::F9);
+        harness.state.lock().unwrap().pressed_at = Instant::now().checked_sub(min_hold);
+        harness.release(KeyCode::F9);
+        assert_eq!(
+            harness.events(),
+            vec![
+                KeyboardEvent::RecordingKeyPressed,
+                KeyboardEvent::RecordingKeyReleased { to_clipboard: false }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modifier_superset_rejected() {
+        let shortcut = RecordingShortcut::new(ShortcutMode::Toggle, KeyCode::Slash, vec![KeyCode::ControlLeft]);
+
+        assert!(is_shortcut_active(
+            &[KeyCode::ControlLeft, KeyCode::Slash],
+            &[],
+            &shortcut
+        ));
+        assert!(!is_shortcut_active(
+            &[KeyCode::ControlLeft, KeyCode::ShiftLeft, KeyCode::Slash],
+            &[],
+            &shortcut
+        ));
+    }
+
+    #[test]
+    fn test_opposite_hand_modifiers_match() {
+        let shortcut = RecordingShortcut::new(ShortcutMode::Toggle, KeyCode::Slash, vec![KeyCode::ControlLeft]);
+
+        assert!(is_shortcut_active(
+            &[KeyCode::ControlRight, KeyCode::Slash],
+            &[],
+            &shortcut
+        ));
+        // Shift is still an extra modifier from either side
+        assert!(!is_shortcut_active(
+            &[KeyCode::ControlRight, KeyCode::ShiftRight, KeyCode::Slash],
+            &[],
+            &shortcut
+        ));
+
+        let bare = RecordingShortcut::new(ShortcutMode::Hold, KeyCode::MetaLeft, vec![]);
+        assert!(is_shortcut_active(&[KeyCode::MetaRight], &[], &bare));
+
+        let harness = Harness::new(RecordingShortcut::new(
+            ShortcutMode::Hold,
+            KeyCode::F9,
+            vec![KeyCode::ShiftRight],
+        ));
+        harness.press(KeyCode::ShiftLeft);
+        harness.press(KeyCode::F9);
+        harness.release(KeyCode::F9);
+        harness.release(KeyCode::ShiftLeft);
+        assert_eq!(
+            harness.events(),
+            vec![
+                KeyboardEvent::RecordingKeyPressed,
+                KeyboardEvent::RecordingKeyReleased { to_clipboard: false }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bare_modifier_shortcut_alone() {
+        let shortcut = RecordingShortcut::new(ShortcutMode::Hold, KeyCode::ControlLeft, vec![]);
+
+        assert!(is_shortcut_active(&[KeyCode::ControlLeft], &[], &shortcut));
+        assert!(!is_shortcut_active(&[KeyCode::ControlLeft, KeyCode::C], &[], &shortcut));
+    }
+
+    #[test]
+    fn test_bare_modifier_hold_cancelled_by_combo() {
+        let harness = Harness::new(RecordingShortcut::new(ShortcutMode::Hold, KeyCode::ControlLeft, vec![]));
+
+        harness.press(KeyCode::ControlLeft);
+        harness.release(KeyCode::ControlLeft);
+        assert_eq!(
+            harness.events(),
+            vec![
+                KeyboardEvent::RecordingKeyPressed,
+                KeyboardEvent::RecordingKeyReleased { to_clipboard: false }
+            ]
+        );
+
+        // Ctrl+C is a copy: the recording started by Ctrl is cancelled
+        harness.press(KeyCode::ControlLeft);
+        harness.press(KeyCode::C);
+        harness.release(KeyCode::C);
+        harness.release(KeyCode::ControlLeft);
+        assert_eq!(
+            harness.events(),
+            vec![KeyboardEvent::RecordingKeyPressed, KeyboardEvent::OtherKeyPressed]
+        );
+    }
+
+    #[test]
+    fn test_replace_selection_always_pastes() {
+        // Pasting replaces a selection everywhere, so it is kept as is
+        for placement in [OutputPlacement::Insert, OutputPlacement::ReplaceSelection] {
+            assert_eq!(delivery_method(OutputMethod::Paste, placement), OutputMethod::Paste);
+        }
+
+        // Typing only replaces a selection in some applications
+        assert_eq!(
+            delivery_method(OutputMethod::Type, OutputPlacement::Insert),
+            OutputMethod::Type
+        );
+        assert_eq!(
+            delivery_method(OutputMethod::Type, OutputPlacement::ReplaceSelection),
+            OutputMethod::Paste
+        );
+    }
+
+    #[test]
+    fn test_per_char_delay_types_graphemes() {
+        let text = "hi 👋🏽 e\u{301}";
+        assert_eq!(typing_chunks(text, TypingOptions::default()), vec![text]);
+
+        // Emoji with skin tones and combining accents are typed whole
+        let opts = TypingOptions {
+            per_char_delay_ms: 5,
+            ..TypingOptions::default()
+        };
+        assert_eq!(typing_chunks(text, opts), vec!["h", "i", " ", "👋🏽", " ", "e\u{301}"]);
+    }
+}
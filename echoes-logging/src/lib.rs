@@ -8,12 +8,17 @@
 //! - Panic handling
 
 pub mod error;
+pub mod rotation;
 pub mod tracing_setup;
 
 pub use error::LoggingError;
+pub use rotation::RotationPolicy;
 /// Re-export tracing macros for convenience
 pub use tracing::{debug, error, info, trace, warn};
-pub use tracing_setup::{cleanup_tracing, init_tracing, setup_panic_handler, ErrorReport, TracingConfig};
+pub use tracing_setup::{
+    cleanup_old_logs, cleanup_tracing, enforce_total_size_budget, error_count, init_tracing, set_log_level,
+    setup_panic_handler, ErrorReport, ErrorSink, TracingConfig, LOG_DIR_ENV,
+};
 
 /// Result type for logging operations
 pub type Result<T> = std::result::Result<T, LoggingError>;
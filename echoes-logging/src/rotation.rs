@@ -0,0 +1,90 @@
+//! Log file rotation policies
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// How log files are rotated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RotationPolicy {
+    /// Start a new file every day
+    #[default]
+    Daily,
+    /// Start a new file every hour
+    Hourly,
+    /// Start a new file once the current one exceeds the given number of bytes
+    SizeLimit(u64),
+}
+
+/// Writer that rotates `<app_name>.log` once it grows past a size limit.
+///
+/// Rotated files are renamed to `<app_name>.log.<timestamp>` so they sort
+/// chronologically and are picked up by the cleanup helpers.
+pub struct SizeRotatingWriter {
+    dir: PathBuf,
+    app_name: String,
+    max_bytes: u64,
+    file: File,
+    size: u64,
+}
+
+impl SizeRotatingWriter {
+    /// Open (or continue) the current log file in `dir`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log file cannot be opened.
+    pub fn new(dir: &Path, app_name: &str, max_bytes: u64) -> io::Result<Self> {
+        let path = dir.join(format!("{app_name}.log"));
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            app_name: app_name.to_string(),
+            max_bytes,
+            file,
+            size,
+        })
+    }
+
+    fn current_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.log", self.app_name))
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        let timestamp = chrono::Local::now().format("%Y-%m-%d-%H-%M-%S");
+        let mut rotated = self.dir.join(format!("{}.log.{timestamp}", self.app_name));
+        let mut suffix = 1;
+        while rotated.exists() {
+            rotated = self.dir.join(format!("{}.log.{timestamp}.{suffix}", self.app_name));
+            suffix += 1;
+        }
+
+        let current = self.current_path();
+        std::fs::rename(&current, rotated)?;
+        self.file = OpenOptions::new().create(true).append(true).open(current)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRotatingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
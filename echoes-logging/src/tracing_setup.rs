@@ -1,18 +1,38 @@
 use std::{
-    path::PathBuf,
-    sync::{atomic::AtomicPtr, LazyLock},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicPtr, AtomicU64, Ordering},
+        Arc, LazyLock, OnceLock,
+    },
 };
 
 use tracing::{Level, Subscriber};
 use tracing_appender::{non_blocking, rolling};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer, Registry};
 
-use crate::{error::LoggingError, Result};
+use crate::{
+    error::LoggingError,
+    rotation::{RotationPolicy, SizeRotatingWriter},
+    Result,
+};
 
 /// Global atomic storage for the tracing guard to prevent memory leaks
 static TRACING_GUARD: LazyLock<AtomicPtr<tracing_appender::non_blocking::WorkerGuard>> =
     LazyLock::new(|| AtomicPtr::new(std::ptr::null_mut()));
 
+/// Number of ERROR events recorded since tracing was initialized
+static ERROR_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Callback invoked with a report for every ERROR event
+pub type ErrorSink = Arc<dyn Fn(ErrorReport) + Send + Sync>;
+
+/// Handle for swapping the active log filter after initialization
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Environment variable naming a directory to write log files to instead of
+/// the data directory
+pub const LOG_DIR_ENV: &str = "ECHOES_LOG_DIR";
+
 /// Configuration for the tracing system
 pub struct TracingConfig {
     /// Directory for log files
@@ -25,20 +45,64 @@ pub struct TracingConfig {
     pub file_output: bool,
     /// Log level filter
     pub log_level: String,
+    /// `(target, level)` pairs layered over `log_level`, e.g.
+    /// `("echoes_audio", "trace")` to debug only the audio pipeline
+    pub directives: Vec<(String, String)>,
     /// Enable ANSI colors in console output
     pub ansi_colors: bool,
+    /// When to start a new log file
+    pub rotation: RotationPolicy,
+    /// Called for each ERROR event, e.g. to forward it to an error tracking
+    /// service. Must not log at ERROR level itself.
+    pub on_error: Option<ErrorSink>,
 }
 
 impl Default for TracingConfig {
     fn default() -> Self {
         Self {
-            log_dir: directories::ProjectDirs::from("com", "echoes", "Echoes")
-                .map_or_else(|| PathBuf::from("."), |dirs| dirs.data_dir().to_path_buf()),
+            log_dir: std::env::var_os(LOG_DIR_ENV)
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| {
+                    directories::ProjectDirs::from("com", "echoes", "Echoes").map(|dirs| dirs.data_dir().to_path_buf())
+                })
+                .unwrap_or_else(|| PathBuf::from(".")),
             app_name: "echoes".to_string(),
             console_output: true,
             file_output: true,
             log_level: "echoes=debug,warn".to_string(),
+            directives: Vec::new(),
             ansi_colors: true,
+            rotation: RotationPolicy::default(),
+            on_error: None,
+        }
+    }
+}
+
+impl TracingConfig {
+    /// Log events of `target`, e.g. a crate such as `echoes_audio`, at
+    /// `level` and above regardless of `log_level`
+    #[must_use]
+    pub fn with_directive(mut self, target: impl Into<String>, level: impl Into<String>) -> Self {
+        self.directives.push((target.into(), level.into()));
+        self
+    }
+
+    /// The filter to log with: `RUST_LOG` when it is set, `log_level` with
+    /// the directives appended otherwise
+    #[must_use]
+    pub fn filter(&self) -> String {
+        match std::env::var("RUST_LOG") {
+            Ok(rust_log) if !rust_log.trim().is_empty() => rust_log,
+            _ => std::iter::once(self.log_level.clone())
+                .chain(
+                    self.directives
+                        .iter()
+                        .map(|(target, level)| format!("{target}={level}")),
+                )
+                .filter(|directive| !directive.is_empty())
+                .collect::<Vec<_>>()
+                .join(","),
         }
     }
 }
@@ -59,8 +123,9 @@ pub fn init_tracing(config: &TracingConfig) -> Result<()> {
     }
 
     // Set up environment filter
-    let env_filter = EnvFilter::try_new(&config.log_level)
+    let env_filter = EnvFilter::try_new(config.filter())
         .map_err(|e| LoggingError::InvalidConfiguration(format!("Invalid log filter: {e}")))?;
+    let (env_filter, filter_handle) = reload::Layer::new(env_filter);
 
     // Create the subscriber layers
     let mut layers = Vec::new();
@@ -80,8 +145,15 @@ pub fn init_tracing(config: &TracingConfig) -> Result<()> {
 
     // File layer with rotation
     if config.file_output {
-        let file_appender = rolling::daily(&config.log_dir, &config.app_name);
-        let (non_blocking, guard) = non_blocking(file_appender);
+        let (non_blocking, guard) = match config.rotation {
+            RotationPolicy::Daily => non_blocking(rolling::daily(&config.log_dir, &config.app_name)),
+            RotationPolicy::Hourly => non_blocking(rolling::hourly(&config.log_dir, &config.app_name)),
+            RotationPolicy::SizeLimit(max_bytes) => {
+                let writer = SizeRotatingWriter::new(&config.log_dir, &config.app_name, max_bytes)
+                    .map_err(|e| LoggingError::FileCreationFailed(format!("Failed to open log file: {e}")))?;
+                non_blocking(writer)
+            }
+        };
 
         let file_layer = fmt::layer()
             .with_writer(non_blocking)
@@ -113,7 +185,7 @@ pub fn init_tracing(config: &TracingConfig) -> Result<()> {
     }
 
     // Error tracking layer
-    let error_layer = ErrorTrackingLayer::new();
+    let error_layer = ErrorTrackingLayer::new(config.on_error.clone());
 
     // Build and initialize the subscriber
     tracing_subscriber::registry()
@@ -122,6 +194,7 @@ pub fn init_tracing(config: &TracingConfig) -> Result<()> {
         .with(error_layer)
         .try_init()
         .map_err(|e| LoggingError::TracingInitFailed(format!("Failed to initialize tracing: {e}")))?;
+    let _ = FILTER_HANDLE.set(filter_handle);
 
     tracing::info!(
         app_name = config.app_name,
@@ -132,6 +205,28 @@ pub fn init_tracing(config: &TracingConfig) -> Result<()> {
     Ok(())
 }
 
+/// Replace the active log filter without reinitializing tracing
+///
+/// Accepts the same directive syntax as `RUST_LOG`, e.g. `"echoes=trace,warn"`.
+///
+/// # Errors
+///
+/// Returns an error if the filter string is invalid or tracing has not been
+/// initialized with [`init_tracing`].
+pub fn set_log_level(new: &str) -> Result<()> {
+    let filter =
+        EnvFilter::try_new(new).map_err(|e| LoggingError::InvalidConfiguration(format!("Invalid log filter: {e}")))?;
+    let handle = FILTER_HANDLE
+        .get()
+        .ok_or_else(|| LoggingError::TracingInitFailed("Tracing is not initialized".to_string()))?;
+    handle
+        .reload(filter)
+        .map_err(|e| LoggingError::TracingInitFailed(format!("Failed to reload log filter: {e}")))?;
+
+    tracing::info!(log_level = new, "Log level changed");
+    Ok(())
+}
+
 /// Cleanup tracing resources on shutdown
 #[allow(dead_code)]
 pub fn cleanup_tracing() {
@@ -148,17 +243,20 @@ pub fn cleanup_tracing() {
     }
 }
 
-/// Custom layer for tracking errors and sending them to an error reporting
-/// service
+/// Number of ERROR events logged since tracing was initialized
+#[must_use]
+pub fn error_count() -> u64 {
+    ERROR_COUNT.load(Ordering::Relaxed)
+}
+
+/// Custom layer for tracking errors and forwarding them to an optional sink
 struct ErrorTrackingLayer {
-    error_count: std::sync::atomic::AtomicU64,
+    on_error: Option<ErrorSink>,
 }
 
 impl ErrorTrackingLayer {
-    const fn new() -> Self {
-        Self {
-            error_count: std::sync::atomic::AtomicU64::new(0),
-        }
+    const fn new(on_error: Option<ErrorSink>) -> Self {
+        Self { on_error }
     }
 }
 
@@ -169,18 +267,44 @@ where
     fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
         // Track error events
         if event.metadata().level() == &Level::ERROR {
-            self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let error_count = ERROR_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
+
+            if let Some(on_error) = &self.on_error {
+                on_error(ErrorReport::from_event(event));
+            }
 
-            // In a production app, you might send this to an error tracking service
-            // For now, we'll just track locally
-            let error_count = self.error_count.load(std::sync::atomic::Ordering::Relaxed);
-            if error_count > 0 && error_count % 10 == 0 {
+            if error_count % 10 == 0 {
                 tracing::warn!("Application has logged {} errors", error_count);
             }
         }
     }
 }
 
+/// Collects the fields of an event that make up an [`ErrorReport`]
+#[derive(Default)]
+struct ErrorFieldVisitor {
+    message: Option<String>,
+    error_type: Option<String>,
+}
+
+impl tracing::field::Visit for ErrorFieldVisitor {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        match field.name() {
+            "message" | "error_message" => self.message = Some(value.to_string()),
+            "error_type" => self.error_type = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" | "error_message" => self.message = Some(format!("{value:?}")),
+            "error_type" => self.error_type = Some(format!("{value:?}")),
+            _ => {}
+        }
+    }
+}
+
 /// Structured error reporting
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -211,6 +335,23 @@ impl ErrorReport {
         }
     }
 
+    /// Build a report from an ERROR tracing event
+    fn from_event(event: &tracing::Event<'_>) -> Self {
+        let mut visitor = ErrorFieldVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+
+        Self {
+            timestamp: chrono::Utc::now(),
+            error_type: visitor.error_type.unwrap_or_else(|| metadata.target().to_string()),
+            message: visitor.message.unwrap_or_default(),
+            file: metadata.file().map(std::string::ToString::to_string),
+            line: metadata.line(),
+            thread: std::thread::current().name().map(std::string::ToString::to_string),
+            backtrace: None,
+        }
+    }
+
     /// Convert to JSON for logging or reporting
     #[allow(dead_code)]
     #[must_use]
@@ -308,3 +449,102 @@ pub fn cleanup_old_logs(log_dir: &PathBuf, days_to_keep: u32) -> Result<()> {
 
     Ok(())
 }
+
+/// Delete the oldest of `app_name`'s log files until they are under
+/// `max_bytes` in total
+///
+/// Only `<app_name>.log*` files and daily/hourly `<app_name>.<date>` files are
+/// considered, and the newest of them is never removed since it is the one
+/// being written to.
+///
+/// # Errors
+///
+/// Returns an error if the log directory or file metadata cannot be read.
+pub fn enforce_total_size_budget(log_dir: &Path, app_name: &str, max_bytes: u64) -> Result<()> {
+    let prefix = format!("{app_name}.");
+    let active = format!("{app_name}.log");
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(log_dir)
+        .map_err(|e| LoggingError::FileCreationFailed(format!("Failed to read log directory: {e}")))?
+    {
+        let entry =
+            entry.map_err(|e| LoggingError::FileCreationFailed(format!("Failed to read directory entry: {e}")))?;
+
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if !name.starts_with(&prefix) {
+            continue;
+        }
+
+        let metadata = entry
+            .metadata()
+            .map_err(|e| LoggingError::FileCreationFailed(format!("Failed to read file metadata: {e}")))?;
+
+        if metadata.is_file() {
+            let modified = metadata.modified().unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+            files.push((modified, metadata.len(), entry.path()));
+        }
+    }
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(modified, _, _)| *modified);
+    files.pop();
+
+    for (_, size, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if path.file_name().is_some_and(|name| name == active.as_str()) {
+            continue;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= size;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    fn write_log(dir: &Path, name: &str, age_secs: u64) {
+        let path = dir.join(name);
+        std::fs::write(&path, [0u8; 100]).unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age_secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_size_budget_only_removes_old_app_logs() {
+        let dir = std::env::temp_dir().join(format!("echoes-log-budget-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        write_log(&dir, "echoes.log", 0);
+        write_log(&dir, "echoes.log.20240101", 300);
+        write_log(&dir, "echoes.log.20240102", 200);
+        write_log(&dir, "notes.txt", 1000);
+        write_log(&dir, "other.log", 1000);
+
+        enforce_total_size_budget(&dir, "echoes", 150).unwrap();
+
+        assert!(dir.join("echoes.log").exists());
+        assert!(!dir.join("echoes.log.20240101").exists());
+        assert!(!dir.join("echoes.log.20240102").exists());
+        assert!(dir.join("notes.txt").exists());
+        assert!(dir.join("other.log").exists());
+
+        // The active file is kept even when it alone exceeds the budget
+        enforce_total_size_budget(&dir, "echoes", 0).unwrap();
+        assert!(dir.join("echoes.log").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+//! Launching the app when the user logs in
+//!
+//! macOS uses a launch agent, Windows the `Run` registry key of the current
+//! user and Linux an XDG autostart entry. Each points at the running
+//! executable, so registering again after the app moved updates the entry.
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use std::path::PathBuf;
+
+use crate::{PlatformError, Result};
+
+/// Name the login item is registered under
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+const LOGIN_ITEM_NAME: &str = "com.echoes.echoes";
+
+/// Registers or unregisters the app to start at login.
+///
+/// # Errors
+///
+/// Returns `PlatformError::SystemError` if the login item cannot be written or
+/// removed, or `PlatformError::PlatformNotSupported` on platforms without an
+/// implementation.
+pub fn set_launch_at_login(enabled: bool) -> Result<()> {
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        let path = login_item_path()?;
+        if enabled {
+            let contents = login_item_contents(&current_exe()?);
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| system_error("create the login item directory", &e))?;
+            }
+            std::fs::write(&path, contents).map_err(|e| system_error("write the login item", &e))
+        } else {
+            match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(system_error("remove the login item", &e)),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let mut command = std::process::Command::new("reg");
+        if enabled {
+            let exe = current_exe()?;
+            command.args(["add", RUN_KEY, "/v", LOGIN_ITEM_NAME, "/t", "REG_SZ", "/f", "/d"]);
+            command.arg(format!("\"{}\"", exe.display()));
+        } else if launch_at_login_enabled() {
+            command.args(["delete", RUN_KEY, "/v", LOGIN_ITEM_NAME, "/f"]);
+        } else {
+            return Ok(());
+        }
+
+        let status = command.status().map_err(|e| system_error("run reg", &e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(PlatformError::SystemError(format!("reg exited with {status}")))
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        let _ = enabled;
+        Err(PlatformError::PlatformNotSupported(
+            "Launching at login is not implemented on this platform".to_string(),
+        ))
+    }
+}
+
+/// Whether the app is registered to start at login
+#[must_use]
+pub fn launch_at_login_enabled() -> bool {
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        login_item_path().is_ok_and(|path| path.is_file())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        std::process::Command::new("reg")
+            .args(["query", RUN_KEY, "/v", LOGIN_ITEM_NAME])
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        false
+    }
+}
+
+/// Registry key of the programs started when the current user logs in
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn current_exe() -> Result<std::path::PathBuf> {
+    std::env::current_exe().map_err(|e| system_error("locate the executable", &e))
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn system_error(action: &str, e: &std::io::Error) -> PlatformError {
+    PlatformError::SystemError(format!("Failed to {action}: {e}"))
+}
+
+/// `~/Library/LaunchAgents/com.echoes.echoes.plist`
+#[cfg(target_os = "macos")]
+fn login_item_path() -> Result<PathBuf> {
+    Ok(home_dir()?
+        .join("Library/LaunchAgents")
+        .join(format!("{LOGIN_ITEM_NAME}.plist")))
+}
+
+/// `echoes.desktop` in the XDG autostart directory
+#[cfg(target_os = "linux")]
+fn login_item_path() -> Result<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => home_dir()?.join(".config"),
+    };
+    Ok(config_dir.join("autostart/echoes.desktop"))
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn home_dir() -> Result<PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| PlatformError::SystemError("HOME is not set".to_string()))
+}
+
+/// Launch agent property list running `exe` once at login
+#[cfg(target_os = "macos")]
+fn login_item_contents(exe: &std::path::Path) -> String {
+    let exe = exe
+        .display()
+        .to_string()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LOGIN_ITEM_NAME}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{exe}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#
+    )
+}
+
+/// Autostart entry running `exe`
+#[cfg(target_os = "linux")]
+fn login_item_contents(exe: &std::path::Path) -> String {
+    let exe = exe
+        .display()
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("[Desktop Entry]\nType=Application\nName=Echoes\nExec=\"{exe}\"\nX-GNOME-Autostart-enabled=true\n")
+}
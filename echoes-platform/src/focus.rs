@@ -0,0 +1,87 @@
+//! Querying which application has keyboard focus
+//!
+//! Text is output into whatever has focus, so before typing we wait for the
+//! app the user dictates into to get focus back from echoes. macOS and
+//! Windows can report the process owning the focused window; elsewhere a
+//! fixed delay is used instead.
+
+use std::time::{Duration, Instant};
+
+/// Interval between focus queries while waiting for focus to settle
+const FOCUS_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Process id of the frontmost application
+#[cfg(target_os = "macos")]
+#[must_use]
+pub fn frontmost_process_id() -> Option<u32> {
+    use objc2::{class, msg_send, runtime::AnyObject};
+
+    // SAFETY: NSWorkspace and NSRunningApplication are always available and
+    // these getters have no preconditions
+    unsafe {
+        let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+        let app: *mut AnyObject = msg_send![workspace, frontmostApplication];
+        if app.is_null() {
+            return None;
+        }
+        let pid: i32 = msg_send![app, processIdentifier];
+        u32::try_from(pid).ok()
+    }
+}
+
+/// Process id owning the foreground window
+#[cfg(target_os = "windows")]
+#[must_use]
+pub fn frontmost_process_id() -> Option<u32> {
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    let mut pid = 0;
+    // SAFETY: both calls only read window state; `pid` outlives the call
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.is_invalid() {
+            return None;
+        }
+        GetWindowThreadProcessId(window, Some(&raw mut pid));
+    }
+    (pid != 0).then_some(pid)
+}
+
+/// Focus can't be queried on this platform
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+#[must_use]
+pub const fn frontmost_process_id() -> Option<u32> {
+    None
+}
+
+/// Blocks until another application than this one has focus and kept it for
+/// two consecutive queries, or at most `max_wait`.
+///
+/// Where focus can't be queried the full `max_wait` is slept as a fixed
+/// delay. Returns immediately for a zero `max_wait`.
+pub fn wait_for_focus_to_settle(max_wait: Duration) {
+    if max_wait.is_zero() {
+        return;
+    }
+
+    let own_pid = std::process::id();
+    let deadline = Instant::now() + max_wait;
+    let mut previous = None;
+    loop {
+        let Some(pid) = frontmost_process_id() else {
+            std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            return;
+        };
+        if pid != own_pid && previous == Some(pid) {
+            return;
+        }
+        previous = Some(pid);
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            tracing::debug!("Focus didn't settle within {:?}", max_wait);
+            return;
+        }
+        std::thread::sleep(remaining.min(FOCUS_POLL_INTERVAL));
+    }
+}
@@ -1,13 +1,18 @@
 //! Platform-specific functionality for echoes dictation application
 //!
 //! This crate provides platform-specific implementations for permissions,
-//! notifications, and other system integration features.
+//! notifications, launching at login, focus queries and other system
+//! integration features.
 
 // Re-export platform modules
+pub mod autostart;
+pub mod focus;
 pub mod notifications;
 pub mod permissions;
 
 // Re-export common types
+pub use autostart::*;
+pub use focus::*;
 pub use notifications::*;
 pub use permissions::*;
 
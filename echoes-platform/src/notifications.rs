@@ -1,44 +1,131 @@
 //! Platform-specific notification handling
 
+use std::time::Duration;
+
 #[cfg(target_os = "macos")]
 use mac_notification_sys::Notification;
-#[cfg(target_os = "linux")]
-use notify_rust::Notification as LinuxNotification;
 
-#[cfg(target_os = "linux")]
-use crate::PlatformError;
-use crate::Result;
+use crate::{PlatformError, Result};
 
-/// Sends a platform-specific notification with the given title and message.
+/// How prominently a notification should be shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NotificationUrgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// Extra presentation options for [`notify_with_options`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NotificationOptions {
+    /// How long the notification stays visible; `None` uses the system default
+    pub timeout: Option<Duration>,
+    pub urgency: NotificationUrgency,
+}
+
+/// Posts a desktop notification with the given title and body.
 ///
 /// # Errors
 ///
-/// Returns an error if the notification system fails to send the notification.
-/// On Linux, this can occur if the notification daemon is not running.
-pub fn send_notification(title: &str, message: &str) -> Result<()> {
+/// Returns `PlatformError::SystemError` if the notification cannot be posted,
+/// or `PlatformError::PlatformNotSupported` on platforms without an
+/// implementation.
+pub fn notify(title: &str, body: &str) -> Result<()> {
+    notify_with_options(title, body, NotificationOptions::default())
+}
+
+/// Posts a desktop notification with a timeout and urgency.
+///
+/// On macOS this goes through the user notification center, falling back to
+/// `osascript` if the native call fails; timeout and urgency are controlled by
+/// the user's system settings there. Linux uses the freedesktop notification
+/// service and Windows uses toast notifications.
+///
+/// # Errors
+///
+/// Returns `PlatformError::SystemError` if the notification cannot be posted,
+/// or `PlatformError::PlatformNotSupported` on platforms without an
+/// implementation.
+pub fn notify_with_options(title: &str, body: &str, options: NotificationOptions) -> Result<()> {
     #[cfg(target_os = "macos")]
     {
-        let _ = Notification::new().title(title).subtitle(message).send();
+        let _ = options;
+        if let Err(e) = Notification::new().title(title).message(body).send() {
+            tracing::debug!("Native notification failed ({e}), falling back to osascript");
+            notify_osascript(title, body)?;
+        }
         Ok(())
     }
 
-    #[cfg(target_os = "windows")]
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
     {
-        // TODO: Implement Windows notifications
-        tracing::info!("Notification: {}: {}", title, message);
+        let mut notification = notify_rust::Notification::new();
+        notification.summary(title).body(body);
+        if let Some(timeout) = options.timeout {
+            notification.timeout(notify_rust::Timeout::from(timeout));
+        }
+        #[cfg(target_os = "linux")]
+        notification.urgency(match options.urgency {
+            NotificationUrgency::Low => notify_rust::Urgency::Low,
+            NotificationUrgency::Normal => notify_rust::Urgency::Normal,
+            NotificationUrgency::Critical => notify_rust::Urgency::Critical,
+        });
+
+        notification
+            .show()
+            .map_err(|e| PlatformError::SystemError(format!("Failed to send notification: {e}")))?;
         Ok(())
     }
 
-    #[cfg(target_os = "linux")]
+    #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
     {
-        LinuxNotification::new()
-            .summary(title)
-            .body(message)
-            .show()
-            .map_err(|e| PlatformError::SystemError(format!("Failed to send notification: {}", e)))?;
+        let _ = (title, body, options);
+        Err(PlatformError::PlatformNotSupported(
+            "Notifications are not implemented on this platform".to_string(),
+        ))
+    }
+}
+
+/// Posts a notification by running AppleScript's `display notification`.
+#[cfg(target_os = "macos")]
+fn notify_osascript(title: &str, body: &str) -> Result<()> {
+    fn quote(s: &str) -> String {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    let script = format!("display notification {} with title {}", quote(body), quote(title));
+    let status = std::process::Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .status()
+        .map_err(|e| PlatformError::SystemError(format!("Failed to run osascript: {e}")))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(PlatformError::SystemError(format!("osascript exited with {status}")))
+    }
+}
+
+/// Sends a platform-specific notification with the given title and message.
+///
+/// # Errors
+///
+/// Returns an error if the notification system fails to send the notification.
+/// On Linux, this can occur if the notification daemon is not running.
+pub fn send_notification(title: &str, message: &str) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = Notification::new().title(title).subtitle(message).send();
         Ok(())
     }
 
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    {
+        notify(title, message)
+    }
+
     #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     {
         tracing::info!("Notification: {}: {}", title, message);
@@ -42,6 +42,56 @@ pub fn check_accessibility_permissions(_prompt: bool) -> bool {
     true
 }
 
+/// Authorization state of a privacy-protected resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionStatus {
+    Granted,
+    Denied,
+    /// The user has not been asked yet (or a prompt is pending)
+    NotDetermined,
+}
+
+/// Checks whether the app may record from the microphone.
+///
+/// On macOS this queries `AVCaptureDevice`; when `prompt` is set and the user
+/// has not decided yet, the system prompt is requested and `NotDetermined` is
+/// returned until they answer. Other platforms don't gate microphone access
+/// per application and always report `Granted`.
+#[cfg(target_os = "macos")]
+#[must_use]
+pub fn check_microphone_permission(prompt: bool) -> PermissionStatus {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_av_foundation::{AVAuthorizationStatus, AVCaptureDevice, AVMediaTypeAudio};
+
+    let Some(media_type) = (unsafe { AVMediaTypeAudio }) else {
+        return PermissionStatus::NotDetermined;
+    };
+
+    let status = unsafe { AVCaptureDevice::authorizationStatusForMediaType(media_type) };
+    tracing::debug!("Microphone permission check: status={:?}, prompt={}", status, prompt);
+
+    if status == AVAuthorizationStatus::Authorized {
+        PermissionStatus::Granted
+    } else if status == AVAuthorizationStatus::Denied || status == AVAuthorizationStatus::Restricted {
+        PermissionStatus::Denied
+    } else {
+        if prompt {
+            let handler = RcBlock::new(|granted: Bool| {
+                tracing::debug!("Microphone permission request finished: granted={}", granted.as_bool());
+            });
+            unsafe { AVCaptureDevice::requestAccessForMediaType_completionHandler(media_type, &handler) };
+        }
+        PermissionStatus::NotDetermined
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+#[must_use]
+pub const fn check_microphone_permission(_prompt: bool) -> PermissionStatus {
+    PermissionStatus::Granted
+}
+
 /// Ensures that the application has the required permissions to function.
 ///
 /// On macOS, this checks and prompts for accessibility permissions if not
@@ -70,7 +120,7 @@ pub fn ensure_permissions() -> Result<bool> {
             tracing::error!("User denied accessibility permissions");
             Err(PlatformError::PermissionDenied(
                 "Accessibility permissions required. Please grant access in System Settings > Privacy & Security > \
-                 Accessibility, then restart the app."
+                 Accessibility; Echoes starts listening once access is granted."
                     .to_string(),
             ))
         }
@@ -83,6 +133,103 @@ pub fn ensure_permissions() -> Result<bool> {
     }
 }
 
+/// Opens the system settings page where the user grants the permissions this
+/// app needs.
+///
+/// On macOS this is Privacy & Security > Accessibility and on Windows the
+/// microphone privacy page. Linux has no settings page for keyboard capture;
+/// access is granted through the `input` group instead.
+///
+/// # Errors
+///
+/// Returns `PlatformError::SystemError` if the settings app cannot be launched,
+/// or `PlatformError::PlatformNotSupported` when there is nothing to open.
+pub fn open_settings() -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg("x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility")
+            .spawn()
+            .map_err(|e| PlatformError::SystemError(format!("Failed to open System Settings: {e}")))?;
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        std::process::Command::new("cmd")
+            .args(["/C", "start", "", "ms-settings:privacy-microphone"])
+            .spawn()
+            .map_err(|e| PlatformError::SystemError(format!("Failed to open Settings: {e}")))?;
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Err(PlatformError::PlatformNotSupported(
+            "No settings page on Linux; add yourself to the 'input' group: sudo usermod -a -G input $USER, then log \
+             out and back in"
+                .to_string(),
+        ))
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        Err(PlatformError::PlatformNotSupported(
+            "No permission settings to open on this platform".to_string(),
+        ))
+    }
+}
+
+/// Command that adds the current user to the `input` group on Linux
+pub const LINUX_INPUT_GROUP_COMMAND: &str = "sudo usermod -a -G input $USER";
+
+/// Checks whether the current process is a member of the Linux `input` group,
+/// which global keyboard capture needs when reading input devices directly.
+///
+/// Membership is taken from the process credentials, so a freshly added group
+/// only counts after logging out and back in.
+///
+/// # Errors
+///
+/// Returns `PlatformError::SystemError` if `/etc/group` or the process status
+/// cannot be read, or `PlatformError::PlatformNotSupported` on other platforms.
+pub fn linux_input_group_status() -> Result<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        let groups = std::fs::read_to_string("/etc/group")
+            .map_err(|e| PlatformError::SystemError(format!("Failed to read /etc/group: {e}")))?;
+        let Some(input_gid) = groups.lines().find_map(|line| {
+            let mut fields = line.split(':');
+            (fields.next() == Some("input"))
+                .then(|| fields.nth(1))
+                .flatten()
+                .and_then(|gid| gid.parse::<u32>().ok())
+        }) else {
+            tracing::debug!("No 'input' group on this system");
+            return Ok(false);
+        };
+
+        let status = std::fs::read_to_string("/proc/self/status")
+            .map_err(|e| PlatformError::SystemError(format!("Failed to read process status: {e}")))?;
+        let is_member = status
+            .lines()
+            .filter_map(|line| line.strip_prefix("Groups:").or_else(|| line.strip_prefix("Gid:")))
+            .flat_map(str::split_whitespace)
+            .filter_map(|gid| gid.parse::<u32>().ok())
+            .any(|gid| gid == input_gid);
+
+        tracing::debug!("Input group check: gid={}, member={}", input_gid, is_member);
+        Ok(is_member)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(PlatformError::PlatformNotSupported(
+            "The input group only exists on Linux".to_string(),
+        ))
+    }
+}
+
 #[must_use]
 pub fn get_required_permissions_description() -> String {
     #[cfg(target_os = "macos")]
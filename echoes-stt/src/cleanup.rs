@@ -0,0 +1,62 @@
+//! Light clean-up of transcripts that needs no LLM
+
+/// Tidies a transcript: trims it, collapses repeated spaces, capitalizes the
+/// first letter and ends it with a period when it has no closing punctuation.
+///
+/// Text that already looks deliberate is left alone: a first word with
+/// capitals inside (`iPhone`) isn't capitalized, and text ending in any
+/// punctuation or symbol gets no period. Running it again changes nothing.
+#[must_use]
+pub fn basic_cleanup(text: &str) -> String {
+    let mut cleaned = String::with_capacity(text.len() + 1);
+    for c in text.trim().chars() {
+        if c == ' ' && cleaned.ends_with(' ') {
+            continue;
+        }
+        cleaned.push(c);
+    }
+
+    let first_word = cleaned.split_whitespace().next().unwrap_or_default();
+    let mut chars = first_word.chars();
+    if let Some(first) = chars.next() {
+        if first.is_lowercase() && !chars.any(char::is_uppercase) {
+            let upper: String = first.to_uppercase().collect();
+            cleaned.replace_range(..first.len_utf8(), &upper);
+        }
+    }
+
+    if cleaned.chars().next_back().is_some_and(char::is_alphanumeric) {
+        cleaned.push('.');
+    }
+    cleaned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_cleanup() {
+        assert_eq!(basic_cleanup("  hello   world "), "Hello world.");
+        assert_eq!(basic_cleanup("is it done?"), "Is it done?");
+        assert_eq!(basic_cleanup("iPhone sales rose"), "iPhone sales rose.");
+        assert_eq!(basic_cleanup("élan vital"), "Élan vital.");
+        assert_eq!(basic_cleanup("see the list:"), "See the list:");
+        assert_eq!(basic_cleanup("   "), "");
+    }
+
+    #[test]
+    fn test_basic_cleanup_is_idempotent() {
+        for text in [
+            "  hello   world ",
+            "Already fine.",
+            "iPhone sales rose",
+            "he said \"wait\"",
+            "3 items",
+            "",
+        ] {
+            let once = basic_cleanup(text);
+            assert_eq!(basic_cleanup(&once), once, "cleaning {text:?} twice");
+        }
+    }
+}
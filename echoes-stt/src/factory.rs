@@ -0,0 +1,216 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::{Context, Result};
+use echoes_config::{ApiKeyProvider, Config, SttProvider as SttProviderKind};
+use tokio::sync::OnceCell;
+
+use super::{FallbackProvider, LocalWhisperStt, OpenAiStt, RetryPolicy, SttProvider, Transcript};
+
+/// Groq's OpenAI-compatible endpoint, used when no base URL is configured
+const GROQ_BASE_URL: &str = "https://api.groq.com/openai/v1";
+const GROQ_DEFAULT_MODEL: &str = "whisper-large-v3";
+
+/// The STT provider selected in the config
+pub enum ConfiguredStt {
+    /// `OpenAI` or an `OpenAI`-compatible API such as Groq
+    OpenAi(OpenAiStt),
+    LocalWhisper(LocalWhisperStt),
+}
+
+impl ConfiguredStt {
+    /// Builds the provider selected by `config.stt_provider`.
+    ///
+    /// Loading a local Whisper model is slow and blocking, so call this off
+    /// the async worker threads when that provider is selected.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the provider's API key is not set or the local
+    /// Whisper model cannot be loaded.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Self::for_provider(config, &config.stt_provider)
+    }
+
+    /// `config.stt_provider` followed by its fallbacks. Each provider is
+    /// only built once it is tried, so a fallback's Whisper model isn't
+    /// loaded while the providers before it succeed, and one that can't be
+    /// built, e.g. for a missing API key, fails like a failed transcription.
+    #[must_use]
+    pub fn fallback_chain(config: &Config) -> FallbackProvider<LazyStt> {
+        let config = Arc::new(config.clone());
+        config
+            .stt_providers()
+            .into_iter()
+            .fold(FallbackProvider::new(), |chain, kind| {
+                chain.with_provider(format!("{kind:?}"), LazyStt::new(Arc::clone(&config), kind))
+            })
+    }
+
+    /// Builds the given provider from its settings in `config`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the provider's API key is not set or the local
+    /// Whisper model cannot be loaded.
+    pub fn for_provider(config: &Config, provider: &SttProviderKind) -> Result<Self> {
+        match provider {
+            SttProviderKind::OpenAI => {
+                let api_key = config
+                    .get_api_key(ApiKeyProvider::OpenAI)
+                    .context("OpenAI API key is not set")?;
+                let mut stt = OpenAiStt::new(api_key)
+                    .with_retry_policy(RetryPolicy::from(&config.stt_retry))
+                    .with_task(config.task);
+                if let Some(base_url) = &config.openai_base_url {
+                    stt = stt.with_base_url(base_url);
+                }
+                stt = stt
+                    .with_extra_headers(&config.openai_extra_headers)
+                    .context("Invalid openai_extra_headers")?;
+                if let Some(model) = &config.openai_stt_model {
+                    stt = stt.with_model(model);
+                }
+                if let Some(prompt) = &config.openai_stt_prompt {
+                    stt = stt.with_prompt(prompt);
+                }
+                Ok(Self::OpenAi(stt))
+            }
+            SttProviderKind::Groq => {
+                let api_key = config
+                    .get_api_key(ApiKeyProvider::Groq)
+                    .context("Groq API key is not set")?;
+                let mut stt = OpenAiStt::new(api_key)
+                    .with_retry_policy(RetryPolicy::from(&config.stt_retry))
+                    .with_task(config.task)
+                    .with_base_url(config.groq_base_url.as_deref().unwrap_or(GROQ_BASE_URL))
+                    .with_model(config.groq_stt_model.as_deref().unwrap_or(GROQ_DEFAULT_MODEL));
+                if let Some(prompt) = &config.groq_stt_prompt {
+                    stt = stt.with_prompt(prompt);
+                }
+                Ok(Self::OpenAi(stt))
+            }
+            SttProviderKind::LocalWhisper => Ok(Self::LocalWhisper(
+                LocalWhisperStt::new(&config.local_whisper)?.with_task(config.task),
+            )),
+        }
+    }
+}
+
+impl SttProvider for ConfiguredStt {
+    async fn transcribe(&self, audio_data: Vec<u8>) -> Result<String> {
+        match self {
+            Self::OpenAi(stt) => stt.transcribe(audio_data).await,
+            Self::LocalWhisper(stt) => stt.transcribe(audio_data).await,
+        }
+    }
+
+    async fn transcribe_detailed(&self, audio_data: Vec<u8>) -> Result<Transcript> {
+        match self {
+            Self::OpenAi(stt) => stt.transcribe_detailed(audio_data).await,
+            Self::LocalWhisper(stt) => stt.transcribe_detailed(audio_data).await,
+        }
+    }
+
+    async fn transcribe_file(&self, path: &Path) -> Result<Transcript> {
+        match self {
+            Self::OpenAi(stt) => stt.transcribe_file(path).await,
+            Self::LocalWhisper(stt) => stt.transcribe_file(path).await,
+        }
+    }
+}
+
+/// A provider from the config that is built the first time it transcribes
+/// and reused after that.
+///
+/// Local Whisper models are loaded and run on the blocking thread pool, so
+/// unlike [`ConfiguredStt`] this can be awaited on the async worker threads.
+pub struct LazyStt {
+    config: Arc<Config>,
+    kind: SttProviderKind,
+    stt: OnceCell<Result<Arc<ConfiguredStt>, String>>,
+}
+
+impl LazyStt {
+    #[must_use]
+    pub const fn new(config: Arc<Config>, kind: SttProviderKind) -> Self {
+        Self {
+            config,
+            kind,
+            stt: OnceCell::const_new(),
+        }
+    }
+
+    const fn is_local(&self) -> bool {
+        matches!(self.kind, SttProviderKind::LocalWhisper)
+    }
+
+    /// The built provider; a failed build is reported on every use
+    async fn get(&self) -> Result<Arc<ConfiguredStt>> {
+        let built = self
+            .stt
+            .get_or_init(|| async {
+                let config = Arc::clone(&self.config);
+                let kind = self.kind.clone();
+                let build = move || ConfiguredStt::for_provider(&config, &kind).map(Arc::new);
+                let result = if self.is_local() {
+                    tokio::task::spawn_blocking(build)
+                        .await
+                        .unwrap_or_else(|e| Err(anyhow::anyhow!("Loading the Whisper model failed: {e}")))
+                } else {
+                    build()
+                };
+                result.map_err(|e| format!("{e:#}"))
+            })
+            .await;
+        built.clone().map_err(anyhow::Error::msg)
+    }
+}
+
+impl SttProvider for LazyStt {
+    async fn transcribe(&self, audio_data: Vec<u8>) -> Result<String> {
+        Ok(self.transcribe_detailed(audio_data).await?.text)
+    }
+
+    async fn transcribe_detailed(&self, audio_data: Vec<u8>) -> Result<Transcript> {
+        let stt = self.get().await?;
+        if !self.is_local() {
+            return stt.transcribe_detailed(audio_data).await;
+        }
+        tokio::task::spawn_blocking(move || {
+            tokio::runtime::Handle::current().block_on(stt.transcribe_detailed(audio_data))
+        })
+        .await
+        .context("Transcription task failed")?
+    }
+
+    async fn transcribe_file(&self, path: &Path) -> Result<Transcript> {
+        let stt = self.get().await?;
+        if !self.is_local() {
+            return stt.transcribe_file(path).await;
+        }
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || tokio::runtime::Handle::current().block_on(stt.transcribe_file(&path)))
+            .await
+            .context("Transcription task failed")?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fallback_chain_builds_providers_when_tried() {
+        let mut config = Config::default();
+        config.stt_provider = SttProviderKind::OpenAI;
+        config.fallback_stt_providers = vec![SttProviderKind::Groq];
+        config.openai_api_key = None;
+        config.groq_api_key = None;
+
+        // Missing keys only surface once the providers are tried
+        let chain = ConfiguredStt::fallback_chain(&config);
+        let error = chain.transcribe_detailed(Vec::new()).await.unwrap_err().to_string();
+        assert!(error.contains("OpenAI API key is not set"), "{error}");
+        assert!(error.contains("Groq API key is not set"), "{error}");
+    }
+}
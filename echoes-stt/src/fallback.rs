@@ -0,0 +1,120 @@
+use std::{fmt::Write as _, path::Path};
+
+use anyhow::Result;
+use tracing::{info, warn};
+
+use super::{SttProvider, Transcript};
+
+/// Tries a list of providers in order until one transcribes the audio
+pub struct FallbackProvider<P> {
+    providers: Vec<(String, P)>,
+}
+
+impl<P: SttProvider> FallbackProvider<P> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { providers: Vec::new() }
+    }
+
+    /// Append a provider, tried after the ones already added. `name` is used
+    /// in logs and errors.
+    #[must_use]
+    pub fn with_provider(mut self, name: impl Into<String>, provider: P) -> Self {
+        self.providers.push((name.into(), provider));
+        self
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+}
+
+impl<P: SttProvider> Default for FallbackProvider<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: SttProvider> SttProvider for FallbackProvider<P> {
+    // Send whenever the wrapped providers' futures are
+    #[allow(clippy::future_not_send)]
+    async fn transcribe(&self, audio_data: Vec<u8>) -> Result<String> {
+        Ok(self.transcribe_detailed(audio_data).await?.text)
+    }
+
+    #[allow(clippy::future_not_send)]
+    async fn transcribe_detailed(&self, audio_data: Vec<u8>) -> Result<Transcript> {
+        let mut errors = Vec::new();
+        for (name, provider) in &self.providers {
+            match provider.transcribe_detailed(audio_data.clone()).await {
+                Ok(transcript) => {
+                    info!("Transcribed with {name}");
+                    return Ok(transcript);
+                }
+                Err(e) => {
+                    warn!("{name} transcription failed: {e:#}");
+                    errors.push((name, e));
+                }
+            }
+        }
+        Err(all_failed(errors))
+    }
+
+    // Each provider reads the file itself, so none needs a copy in memory
+    #[allow(clippy::future_not_send)]
+    async fn transcribe_file(&self, path: &Path) -> Result<Transcript> {
+        let mut errors = Vec::new();
+        for (name, provider) in &self.providers {
+            match provider.transcribe_file(path).await {
+                Ok(transcript) => {
+                    info!("Transcribed with {name}");
+                    return Ok(transcript);
+                }
+                Err(e) => {
+                    warn!("{name} transcription failed: {e:#}");
+                    errors.push((name, e));
+                }
+            }
+        }
+        Err(all_failed(errors))
+    }
+}
+
+/// The error reported when every provider failed
+fn all_failed(errors: Vec<(&String, anyhow::Error)>) -> anyhow::Error {
+    let mut message = String::from("All STT providers failed");
+    for (name, e) in errors {
+        let _ = write!(message, "\n  {name}: {e:#}");
+    }
+    anyhow::anyhow!(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(Option<&'static str>);
+
+    impl SttProvider for Fixed {
+        async fn transcribe(&self, _audio_data: Vec<u8>) -> Result<String> {
+            self.0
+                .map(str::to_string)
+                .ok_or_else(|| anyhow::anyhow!("unavailable"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_until_a_provider_succeeds() {
+        let chain = FallbackProvider::new()
+            .with_provider("local", Fixed(None))
+            .with_provider("cloud", Fixed(Some("hello")));
+        assert_eq!(chain.transcribe(vec![1, 2, 3]).await.unwrap(), "hello");
+
+        let chain = FallbackProvider::new()
+            .with_provider("local", Fixed(None))
+            .with_provider("cloud", Fixed(None));
+        let error = chain.transcribe(Vec::new()).await.unwrap_err().to_string();
+        assert!(error.contains("local: unavailable") && error.contains("cloud: unavailable"));
+    }
+}
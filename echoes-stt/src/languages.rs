@@ -0,0 +1,171 @@
+//! Languages Whisper can transcribe, for choosing and validating the spoken
+//! language
+
+use anyhow::Result;
+
+/// Language setting that lets Whisper detect the spoken language
+pub const AUTO_DETECT: &str = "auto";
+
+/// `(code, English name)` of every language Whisper knows, in the order of
+/// `whisper.cpp`'s language table
+const LANGUAGES: [(&str, &str); 100] = [
+    ("en", "English"),
+    ("zh", "Chinese"),
+    ("de", "German"),
+    ("es", "Spanish"),
+    ("ru", "Russian"),
+    ("ko", "Korean"),
+    ("fr", "French"),
+    ("ja", "Japanese"),
+    ("pt", "Portuguese"),
+    ("tr", "Turkish"),
+    ("pl", "Polish"),
+    ("ca", "Catalan"),
+    ("nl", "Dutch"),
+    ("ar", "Arabic"),
+    ("sv", "Swedish"),
+    ("it", "Italian"),
+    ("id", "Indonesian"),
+    ("hi", "Hindi"),
+    ("fi", "Finnish"),
+    ("vi", "Vietnamese"),
+    ("he", "Hebrew"),
+    ("uk", "Ukrainian"),
+    ("el", "Greek"),
+    ("ms", "Malay"),
+    ("cs", "Czech"),
+    ("ro", "Romanian"),
+    ("da", "Danish"),
+    ("hu", "Hungarian"),
+    ("ta", "Tamil"),
+    ("no", "Norwegian"),
+    ("th", "Thai"),
+    ("ur", "Urdu"),
+    ("hr", "Croatian"),
+    ("bg", "Bulgarian"),
+    ("lt", "Lithuanian"),
+    ("la", "Latin"),
+    ("mi", "Maori"),
+    ("ml", "Malayalam"),
+    ("cy", "Welsh"),
+    ("sk", "Slovak"),
+    ("te", "Telugu"),
+    ("fa", "Persian"),
+    ("lv", "Latvian"),
+    ("bn", "Bengali"),
+    ("sr", "Serbian"),
+    ("az", "Azerbaijani"),
+    ("sl", "Slovenian"),
+    ("kn", "Kannada"),
+    ("et", "Estonian"),
+    ("mk", "Macedonian"),
+    ("br", "Breton"),
+    ("eu", "Basque"),
+    ("is", "Icelandic"),
+    ("hy", "Armenian"),
+    ("ne", "Nepali"),
+    ("mn", "Mongolian"),
+    ("bs", "Bosnian"),
+    ("kk", "Kazakh"),
+    ("sq", "Albanian"),
+    ("sw", "Swahili"),
+    ("gl", "Galician"),
+    ("mr", "Marathi"),
+    ("pa", "Punjabi"),
+    ("si", "Sinhala"),
+    ("km", "Khmer"),
+    ("sn", "Shona"),
+    ("yo", "Yoruba"),
+    ("so", "Somali"),
+    ("af", "Afrikaans"),
+    ("oc", "Occitan"),
+    ("ka", "Georgian"),
+    ("be", "Belarusian"),
+    ("tg", "Tajik"),
+    ("sd", "Sindhi"),
+    ("gu", "Gujarati"),
+    ("am", "Amharic"),
+    ("yi", "Yiddish"),
+    ("lo", "Lao"),
+    ("uz", "Uzbek"),
+    ("fo", "Faroese"),
+    ("ht", "Haitian Creole"),
+    ("ps", "Pashto"),
+    ("tk", "Turkmen"),
+    ("nn", "Nynorsk"),
+    ("mt", "Maltese"),
+    ("sa", "Sanskrit"),
+    ("lb", "Luxembourgish"),
+    ("my", "Myanmar"),
+    ("bo", "Tibetan"),
+    ("tl", "Tagalog"),
+    ("mg", "Malagasy"),
+    ("as", "Assamese"),
+    ("tt", "Tatar"),
+    ("haw", "Hawaiian"),
+    ("ln", "Lingala"),
+    ("ha", "Hausa"),
+    ("ba", "Bashkir"),
+    ("jw", "Javanese"),
+    ("su", "Sundanese"),
+    ("yue", "Cantonese"),
+];
+
+/// `(code, English name)` of every language Whisper can transcribe
+#[must_use]
+pub const fn supported_languages() -> &'static [(&'static str, &'static str)] {
+    &LANGUAGES
+}
+
+/// The code of the supported language `code`, with a static lifetime, or
+/// `None` if Whisper doesn't know it
+#[must_use]
+pub fn find_language(code: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(code.trim()))
+        .map(|(known, _)| *known)
+}
+
+/// English name of the language `code`, or of automatic detection
+#[must_use]
+pub fn language_name(code: &str) -> Option<&'static str> {
+    if code == AUTO_DETECT {
+        return Some("Auto-detect");
+    }
+    LANGUAGES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(code.trim()))
+        .map(|(_, name)| *name)
+}
+
+/// Checks that `code` is a supported language or [`AUTO_DETECT`]
+///
+/// # Errors
+///
+/// Returns an error naming the language setting if Whisper doesn't know it.
+pub fn validate_language(code: &str) -> Result<()> {
+    if code == AUTO_DETECT || find_language(code).is_some() {
+        return Ok(());
+    }
+    anyhow::bail!("Unsupported language {code:?}: use a Whisper language code such as \"en\", or \"{AUTO_DETECT}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_languages_are_unique_and_validated() {
+        let mut codes: Vec<_> = supported_languages().iter().map(|(code, _)| *code).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), supported_languages().len());
+
+        assert!(validate_language("en").is_ok());
+        assert!(validate_language(AUTO_DETECT).is_ok());
+        assert!(validate_language("xx").is_err());
+        assert_eq!(find_language("DE"), Some("de"));
+        assert_eq!(language_name("yue"), Some("Cantonese"));
+    }
+}
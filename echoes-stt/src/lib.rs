@@ -1,12 +1,73 @@
+pub mod cleanup;
+pub mod factory;
+pub mod fallback;
+pub mod languages;
+pub mod models;
 pub mod openai;
+pub mod retry;
+pub mod subtitles;
 pub mod whisper;
 
-use anyhow::Result;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+pub use cleanup::basic_cleanup;
+pub use factory::{ConfiguredStt, LazyStt};
+pub use fallback::FallbackProvider;
+pub use languages::{language_name, supported_languages, validate_language};
+pub use models::MODELS_DIR_ENV;
 pub use openai::OpenAiStt;
+pub use retry::RetryPolicy;
+use serde::Serialize;
 #[allow(unused_imports)]
 pub use whisper::LocalWhisperStt;
 
+/// A transcription together with what the provider reported about it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Transcript {
+    pub text: String,
+    /// Language the provider detected in the speech, as its lowercase English
+    /// name (e.g. `english`). `None` when the provider doesn't report it or
+    /// the language wasn't detected but set.
+    #[serde(rename = "language")]
+    pub detected_language: Option<String>,
+    /// Length of the transcribed audio, when the provider reports it
+    pub duration_ms: Option<u64>,
+    /// Timed pieces of the text, for providers that report them
+    pub segments: Vec<TranscriptSegment>,
+}
+
+/// A piece of a transcript and when it was spoken
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TranscriptSegment {
+    /// Offset from the start of the audio
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
 pub trait SttProvider {
     #[allow(async_fn_in_trait)]
     async fn transcribe(&self, audio_data: Vec<u8>) -> Result<String>;
+
+    /// Like [`SttProvider::transcribe`], also reporting the detected language
+    /// for providers that know it. By default no language is reported.
+    #[allow(async_fn_in_trait)]
+    async fn transcribe_detailed(&self, audio_data: Vec<u8>) -> Result<Transcript> {
+        Ok(Transcript {
+            text: self.transcribe(audio_data).await?,
+            ..Transcript::default()
+        })
+    }
+
+    /// Transcribes the WAV file at `path`. By default the file is read into
+    /// memory and passed to [`SttProvider::transcribe_detailed`]; providers
+    /// that can read it incrementally override this.
+    #[allow(async_fn_in_trait)]
+    async fn transcribe_file(&self, path: &Path) -> Result<Transcript> {
+        let audio = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        self.transcribe_detailed(audio).await
+    }
 }
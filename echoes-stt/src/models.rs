@@ -0,0 +1,129 @@
+//! Locating, downloading and deleting local Whisper models
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use echoes_config::WhisperModel;
+use tokio::io::AsyncWriteExt;
+
+/// Environment variable naming a directory to keep downloaded models in
+/// instead of the data directory
+pub const MODELS_DIR_ENV: &str = "ECHOES_MODELS_DIR";
+
+/// Where the `whisper.cpp` project publishes its GGML models
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Directory downloaded models are kept in: the one named by
+/// [`MODELS_DIR_ENV`] when it is set, `models` in the data directory
+/// otherwise
+///
+/// # Errors
+///
+/// Returns an error if the platform data directory cannot be determined.
+pub fn models_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(MODELS_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    // Use a standard location for models
+    Ok(directories::ProjectDirs::from("com", "echoes", "echoes")
+        .context("Failed to get project directories")?
+        .data_dir()
+        .join("models"))
+}
+
+/// File name `whisper.cpp` publishes `model` under
+#[must_use]
+pub const fn model_file_name(model: &WhisperModel) -> &'static str {
+    match model {
+        WhisperModel::Tiny => "ggml-tiny.bin",
+        WhisperModel::TinyEn => "ggml-tiny.en.bin",
+        WhisperModel::Base => "ggml-base.bin",
+        WhisperModel::BaseEn => "ggml-base.en.bin",
+        WhisperModel::Small => "ggml-small.bin",
+        WhisperModel::SmallEn => "ggml-small.en.bin",
+        WhisperModel::Medium => "ggml-medium.bin",
+        WhisperModel::MediumEn => "ggml-medium.en.bin",
+        WhisperModel::LargeV1 => "ggml-large-v1.bin",
+        WhisperModel::LargeV2 => "ggml-large-v2.bin",
+        WhisperModel::LargeV3 => "ggml-large-v3.bin",
+    }
+}
+
+/// Path `model` is stored at in the models directory, whether or not it was
+/// downloaded
+///
+/// # Errors
+///
+/// Returns an error if the models directory cannot be determined.
+pub fn model_path(model: &WhisperModel) -> Result<PathBuf> {
+    Ok(models_dir()?.join(model_file_name(model)))
+}
+
+/// Size in bytes of the downloaded `model`, or `None` if it isn't downloaded
+#[must_use]
+pub fn downloaded_size(model: &WhisperModel) -> Option<u64> {
+    let metadata = std::fs::metadata(model_path(model).ok()?).ok()?;
+    metadata.is_file().then_some(metadata.len())
+}
+
+/// Downloads `model` into the models directory and returns its path.
+///
+/// `on_progress` is called with the bytes received so far and the total size
+/// when the server reports it. The model is written to a `.part` file that is
+/// only renamed once complete, so an interrupted download never leaves a
+/// truncated model behind.
+///
+/// # Errors
+///
+/// Returns an error if the request fails or the file cannot be written.
+pub async fn download_model(
+    model: &WhisperModel, mut on_progress: impl FnMut(u64, Option<u64>) + Send,
+) -> Result<PathBuf> {
+    let path = model_path(model)?;
+    let dir = path.parent().context("Model path has no parent directory")?;
+    tokio::fs::create_dir_all(dir).await?;
+
+    let file_name = model_file_name(model);
+    let mut response = reqwest::get(format!("{MODEL_BASE_URL}/{file_name}"))
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to download {file_name}"))?;
+    let total = response.content_length();
+
+    let part_path = path.with_extension("bin.part");
+    let result = async {
+        let mut file = tokio::fs::File::create(&part_path).await?;
+        let mut downloaded = 0;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            downloaded += chunk.len() as u64;
+            on_progress(downloaded, total);
+        }
+        file.flush().await?;
+        anyhow::Ok(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&part_path).await;
+        return Err(e.context(format!("Failed to download {file_name}")));
+    }
+    tokio::fs::rename(&part_path, &path).await?;
+    Ok(path)
+}
+
+/// Deletes the downloaded `model` to reclaim disk space
+///
+/// # Errors
+///
+/// Returns an error if the file exists but cannot be removed.
+pub fn delete_model(model: &WhisperModel) -> Result<()> {
+    let path = model_path(model)?;
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to delete {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
@@ -1,14 +1,29 @@
-use anyhow::Result;
-use reqwest::multipart::{Form, Part};
-use tracing::{debug, error};
+use std::time::Duration;
 
-use super::SttProvider;
+use anyhow::{Context, Result};
+use echoes_config::TranscriptionTask;
+use reqwest::{
+    header::{HeaderName, HeaderValue, RETRY_AFTER},
+    multipart::{Form, Part},
+    StatusCode,
+};
+use tracing::{debug, error, warn};
+
+use super::{
+    retry::{RetryPolicy, RetryableError},
+    SttProvider, Transcript, TranscriptSegment,
+};
 
 pub struct OpenAiStt {
     api_key: String,
     base_url: String,
     model: String,
     prompt: Option<String>,
+    task: TranscriptionTask,
+    retry_policy: RetryPolicy,
+    /// Sent with every request; the values are marked sensitive so they
+    /// never show up in logs
+    extra_headers: Vec<(HeaderName, HeaderValue)>,
     client: reqwest::Client,
 }
 
@@ -19,6 +34,9 @@ impl OpenAiStt {
             base_url: "https://api.openai.com/v1".to_string(),
             model: "whisper-1".to_string(),
             prompt: None,
+            task: TranscriptionTask::default(),
+            retry_policy: RetryPolicy::default(),
+            extra_headers: Vec::new(),
             client: reqwest::Client::new(),
         }
     }
@@ -40,38 +58,90 @@ impl OpenAiStt {
         self.prompt = Some(prompt.into());
         self
     }
-}
 
-impl SttProvider for OpenAiStt {
-    async fn transcribe(&self, audio_data: Vec<u8>) -> Result<String> {
-        debug!("Starting OpenAI transcription with model: {}", self.model);
-        let audio_part = Part::bytes(audio_data).file_name("audio.wav").mime_str("audio/wav")?;
+    /// Translate to English via `/audio/translations` instead of
+    /// transcribing
+    #[must_use]
+    pub const fn with_task(mut self, task: TranscriptionTask) -> Self {
+        self.task = task;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Headers to send with every request, e.g. for a gateway in front of a
+    /// self-hosted OpenAI-compatible server. Only their names are logged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a header name or value is not valid in HTTP.
+    pub fn with_extra_headers(mut self, headers: &[(String, String)]) -> Result<Self> {
+        for (name, value) in headers {
+            let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+                .with_context(|| format!("Invalid header name {name:?}"))?;
+            let mut header_value =
+                HeaderValue::from_str(value).with_context(|| format!("Invalid value for header {header_name}"))?;
+            header_value.set_sensitive(true);
+            debug!("Sending extra header {header_name}: <redacted>");
+            self.extra_headers.push((header_name, header_value));
+        }
+        Ok(self)
+    }
+
+    /// Sends one transcription request. Rate limiting and server errors are
+    /// returned as [`RetryableError`].
+    async fn request_transcription(&self, audio_data: Vec<u8>) -> Result<Transcript> {
+        debug!("Starting OpenAI {:?} with model: {}", self.task, self.model);
+        let (file_name, mime) = audio_file_type(&audio_data);
+        let audio_part = Part::bytes(audio_data).file_name(file_name).mime_str(mime)?;
 
         let mut form = Form::new()
             .part("file", audio_part)
             .text("model", self.model.clone())
-            .text("response_format", "json");
+            .text("response_format", self.response_format());
 
         if let Some(ref prompt) = self.prompt {
             form = form.text("prompt", prompt.clone());
         }
 
-        let url = format!("{}/audio/transcriptions", self.base_url);
+        let endpoint = match self.task {
+            TranscriptionTask::Transcribe => "transcriptions",
+            TranscriptionTask::Translate => "translations",
+        };
+        let url = format!("{}/audio/{endpoint}", self.base_url);
         debug!("Making request to: {}", url);
 
-        let response = self
+        let mut request = self
             .client
             .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()
-            .await?;
+            .header("Authorization", format!("Bearer {}", self.api_key));
+        for (name, value) in &self.extra_headers {
+            request = request.header(name, value);
+        }
+        let response = request.multipart(form).send().await?;
 
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs);
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
             let error_message = format!("OpenAI API error: {status} - {error_text}");
             error!("{}", error_message);
+            if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+                return Err(RetryableError {
+                    message: error_message,
+                    retry_after,
+                }
+                .into());
+            }
             #[allow(clippy::wildcard_imports)]
             return Err(anyhow::anyhow!(error_message));
         }
@@ -87,7 +157,100 @@ impl SttProvider for OpenAiStt {
             .ok_or_else(|| anyhow::anyhow!("Missing 'text' field in response"))?
             .to_string();
 
+        let detected_language = response_json["language"].as_str().map(str::to_lowercase);
+
         debug!("Transcription result: {}", text);
-        Ok(text)
+        Ok(Transcript {
+            text,
+            detected_language,
+            duration_ms: response_json["duration"].as_f64().map(seconds_to_ms),
+            segments: response_json["segments"]
+                .as_array()
+                .map(|segments| segments.iter().filter_map(parse_segment).collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// `verbose_json` reports the detected language, but only Whisper models
+    /// support it; the GPT-4o transcription models only return `json`
+    fn response_format(&self) -> &'static str {
+        if self.model.contains("whisper") {
+            "verbose_json"
+        } else {
+            "json"
+        }
+    }
+}
+
+/// File name and MIME type the API needs to decode `audio`: MP3 when it
+/// starts with an ID3 tag or an MPEG frame header, WAV otherwise
+fn audio_file_type(audio: &[u8]) -> (&'static str, &'static str) {
+    match audio {
+        [b'I', b'D', b'3', ..] => ("audio.mp3", "audio/mpeg"),
+        [0xFF, second, ..] if second & 0xE0 == 0xE0 => ("audio.mp3", "audio/mpeg"),
+        _ => ("audio.wav", "audio/wav"),
+    }
+}
+
+/// A segment of a `verbose_json` response, with times in seconds
+fn parse_segment(segment: &serde_json::Value) -> Option<TranscriptSegment> {
+    Some(TranscriptSegment {
+        start_ms: seconds_to_ms(segment["start"].as_f64()?),
+        end_ms: seconds_to_ms(segment["end"].as_f64()?),
+        text: segment["text"].as_str()?.trim().to_string(),
+    })
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn seconds_to_ms(seconds: f64) -> u64 {
+    (seconds.max(0.0) * 1000.0).round() as u64
+}
+
+impl SttProvider for OpenAiStt {
+    async fn transcribe(&self, audio_data: Vec<u8>) -> Result<String> {
+        Ok(self.transcribe_detailed(audio_data).await?.text)
+    }
+
+    async fn transcribe_detailed(&self, audio_data: Vec<u8>) -> Result<Transcript> {
+        let max_attempts = self.retry_policy.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let error = match self.request_transcription(audio_data.clone()).await {
+                Ok(transcript) => return Ok(transcript),
+                Err(error) => error,
+            };
+            let Some(retryable) = error.downcast_ref::<RetryableError>() else {
+                return Err(error);
+            };
+            if attempt >= max_attempts {
+                return Err(error.context(format!("Giving up after {attempt} attempts")));
+            }
+
+            let delay = self.retry_policy.delay(attempt, retryable.retry_after);
+            warn!("Transcription attempt {attempt}/{max_attempts} failed, retrying in {delay:?}: {retryable}");
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra_headers_are_validated() {
+        let stt = OpenAiStt::new("key")
+            .with_extra_headers(&[("X-Gateway-Token".into(), "secret".into())])
+            .unwrap();
+        assert_eq!(stt.extra_headers[0].0, "x-gateway-token");
+        assert!(stt.extra_headers[0].1.is_sensitive());
+
+        assert!(OpenAiStt::new("key")
+            .with_extra_headers(&[("Bad Header".into(), "value".into())])
+            .is_err());
+        assert!(OpenAiStt::new("key")
+            .with_extra_headers(&[("X-Token".into(), "line\nbreak".into())])
+            .is_err());
     }
 }
@@ -0,0 +1,94 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use echoes_config::RetryConfig;
+
+/// How often and how long to wait before retrying a failed cloud request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each further retry
+    pub base_delay: Duration,
+    /// Upper bound of the random delay added to each retry, so clients that
+    /// failed together don't retry together
+    pub jitter: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after `attempt` (starting at 1) failed. A
+    /// server-provided `retry_after` replaces the exponential backoff.
+    #[must_use]
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let backoff = retry_after.unwrap_or_else(|| {
+            let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+            self.base_delay.saturating_mul(factor)
+        });
+        backoff.saturating_add(self.random_jitter())
+    }
+
+    fn random_jitter(&self) -> Duration {
+        let jitter_nanos = u64::try_from(self.jitter.as_nanos()).unwrap_or(u64::MAX);
+        if jitter_nanos == 0 {
+            return Duration::ZERO;
+        }
+        // `RandomState` is randomly seeded, which is all the randomness needed here
+        let random = RandomState::new().build_hasher().finish();
+        Duration::from_nanos(random % jitter_nanos)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from(&RetryConfig::default())
+    }
+}
+
+impl From<&RetryConfig> for RetryPolicy {
+    fn from(config: &RetryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts.max(1),
+            base_delay: Duration::from_millis(config.base_delay_ms),
+            jitter: Duration::from_millis(config.jitter_ms),
+        }
+    }
+}
+
+/// A failure worth retrying: the provider was rate limited or unavailable
+#[derive(Debug)]
+pub struct RetryableError {
+    pub message: String,
+    /// Wait requested by the server's `Retry-After` header
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for RetryableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RetryableError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_backs_off_exponentially_and_honors_retry_after() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            jitter: Duration::ZERO,
+        };
+
+        assert_eq!(policy.delay(1, None), Duration::from_millis(100));
+        assert_eq!(policy.delay(2, None), Duration::from_millis(200));
+        assert_eq!(policy.delay(3, None), Duration::from_millis(400));
+        assert_eq!(policy.delay(2, Some(Duration::from_secs(7))), Duration::from_secs(7));
+    }
+}
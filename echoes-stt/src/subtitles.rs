@@ -0,0 +1,87 @@
+//! Serializing timed transcript segments as `SubRip` (`.srt`) and `WebVTT`
+//! subtitles
+
+use std::fmt::Write;
+
+use crate::TranscriptSegment;
+
+/// Formats `segments` as numbered `SubRip` cues
+#[must_use]
+pub fn to_srt(segments: &[TranscriptSegment]) -> String {
+    let mut srt = String::new();
+    for (index, segment) in segments.iter().enumerate() {
+        let _ = write!(
+            srt,
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            timestamp(segment.start_ms, ','),
+            timestamp(segment.end_ms, ','),
+            segment.text
+        );
+    }
+    srt
+}
+
+/// Formats `segments` as a `WebVTT` document
+#[must_use]
+pub fn to_vtt(segments: &[TranscriptSegment]) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+    for segment in segments {
+        let _ = write!(
+            vtt,
+            "{} --> {}\n{}\n\n",
+            timestamp(segment.start_ms, '.'),
+            timestamp(segment.end_ms, '.'),
+            segment.text
+        );
+    }
+    vtt
+}
+
+/// `HH:MM:SS` followed by `separator` and the milliseconds
+fn timestamp(ms: u64, separator: char) -> String {
+    let seconds = ms / 1000;
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments() -> Vec<TranscriptSegment> {
+        vec![
+            TranscriptSegment {
+                start_ms: 0,
+                end_ms: 1_500,
+                text: "Hello there.".to_string(),
+            },
+            TranscriptSegment {
+                start_ms: 3_661_020,
+                end_ms: 3_662_000,
+                text: "Goodbye.".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_srt_numbers_cues() {
+        assert_eq!(
+            to_srt(&segments()),
+            "1\n00:00:00,000 --> 00:00:01,500\nHello there.\n\n2\n01:01:01,020 --> 01:01:02,000\nGoodbye.\n\n"
+        );
+    }
+
+    #[test]
+    fn test_vtt_has_header() {
+        assert_eq!(
+            to_vtt(&segments()),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nHello there.\n\n01:01:01.020 --> 01:01:02.000\nGoodbye.\n\n"
+        );
+    }
+}
@@ -1,13 +1,27 @@
-use std::path::PathBuf;
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
-use echoes_config::{LocalWhisperConfig, WhisperModel};
+use echoes_config::{LocalWhisperConfig, TranscriptionTask, WhisperParams, WhisperSampling};
+use tracing::{debug, warn};
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
-use super::SttProvider;
+use super::{
+    languages::{find_language, validate_language, AUTO_DETECT},
+    SttProvider, Transcript, TranscriptSegment,
+};
+
+/// Whether a GPU backend of `whisper.cpp` was compiled in
+const GPU_BACKEND: bool = cfg!(any(feature = "metal", feature = "cuda", feature = "vulkan"));
 
 pub struct LocalWhisperStt {
     context: WhisperContext,
+    params: WhisperParams,
+    task: TranscriptionTask,
+    /// Configured spoken language, or [`AUTO_DETECT`]
+    language: &'static str,
 }
 
 impl LocalWhisperStt {
@@ -15,51 +29,60 @@ impl LocalWhisperStt {
     ///
     /// # Errors
     ///
-    /// Returns an error if the Whisper model cannot be loaded or if the model
-    /// file is not found.
+    /// Returns an error if the language isn't supported, the Whisper model
+    /// cannot be loaded or the model file is not found.
     pub fn new(config: &LocalWhisperConfig) -> Result<Self> {
+        validate_language(&config.language)?;
+        let language = find_language(&config.language).unwrap_or(AUTO_DETECT);
+
         let model_path = if let Some(path) = &config.model_path {
             path.clone()
         } else {
             Self::get_model_path(config)?
         };
 
-        let ctx_params = WhisperContextParameters::default();
-        let context = WhisperContext::new_with_params(&model_path.to_string_lossy(), ctx_params)
-            .context("Failed to create Whisper context")?;
+        if config.use_coreml {
+            check_coreml(&model_path);
+        }
 
-        Ok(Self { context })
-    }
+        let model_path = model_path.to_string_lossy();
+        let mut ctx_params = WhisperContextParameters::default();
+        ctx_params.use_gpu(config.use_gpu && GPU_BACKEND);
+        if config.use_gpu && !GPU_BACKEND {
+            warn!("GPU inference requested but no GPU backend was enabled at build time; using the CPU");
+        }
 
-    fn get_model_path(config: &LocalWhisperConfig) -> Result<PathBuf> {
-        // Use a standard location for models
-        let mut path = directories::ProjectDirs::from("com", "echoes", "echoes")
-            .context("Failed to get project directories")?
-            .data_dir()
-            .to_path_buf();
-
-        path.push("models");
-        std::fs::create_dir_all(&path)?;
-
-        let model_filename = match config.model {
-            WhisperModel::Tiny => "ggml-tiny.bin",
-            WhisperModel::TinyEn => "ggml-tiny.en.bin",
-            WhisperModel::Base => "ggml-base.bin",
-            WhisperModel::BaseEn => "ggml-base.en.bin",
-            WhisperModel::Small => "ggml-small.bin",
-            WhisperModel::SmallEn => "ggml-small.en.bin",
-            WhisperModel::Medium => "ggml-medium.bin",
-            WhisperModel::MediumEn => "ggml-medium.en.bin",
-            WhisperModel::LargeV1 => "ggml-large-v1.bin",
-            WhisperModel::LargeV2 => "ggml-large-v2.bin",
-            WhisperModel::LargeV3 => "ggml-large-v3.bin",
+        let context = match WhisperContext::new_with_params(&model_path, ctx_params) {
+            Ok(context) => context,
+            Err(e) if config.use_gpu && GPU_BACKEND => {
+                warn!("Failed to initialize Whisper on the GPU ({e}); falling back to the CPU");
+                let mut cpu_params = WhisperContextParameters::default();
+                cpu_params.use_gpu(false);
+                WhisperContext::new_with_params(&model_path, cpu_params).context("Failed to create Whisper context")?
+            }
+            Err(e) => return Err(e).context("Failed to create Whisper context"),
         };
 
-        path.push(model_filename);
+        Ok(Self {
+            context,
+            params: config.params,
+            task: TranscriptionTask::default(),
+            language,
+        })
+    }
+
+    #[must_use]
+    pub const fn with_task(mut self, task: TranscriptionTask) -> Self {
+        self.task = task;
+        self
+    }
+
+    fn get_model_path(config: &LocalWhisperConfig) -> Result<PathBuf> {
+        let path = crate::models::model_path(&config.model)?;
 
         if !path.exists() {
             anyhow::bail!(
-                "Whisper model not found at {:?}. Please download the model from https://huggingface.co/ggerganov/whisper.cpp/tree/main",
+                "Whisper model not found at {:?}. Download it from the Models section of the settings or from https://huggingface.co/ggerganov/whisper.cpp/tree/main",
                 path
             );
         }
@@ -68,57 +91,220 @@ impl LocalWhisperStt {
     }
 }
 
-impl SttProvider for LocalWhisperStt {
-    async fn transcribe(&self, audio_data: Vec<u8>) -> Result<String> {
-        // whisper-rs expects 16-bit PCM mono audio at 16kHz
-        // The audio_data should already be in WAV format from our recording module
-
-        // Parse WAV to get raw PCM data
-        let mut reader = hound::WavReader::new(std::io::Cursor::new(audio_data)).context("Failed to parse WAV data")?;
-
-        let spec = reader.spec();
-        if spec.channels != 1 {
-            anyhow::bail!("Audio must be mono, got {} channels", spec.channels);
-        }
-        if spec.sample_rate != 16000 {
-            anyhow::bail!("Audio must be 16kHz, got {}Hz", spec.sample_rate);
-        }
-
-        // Convert to f32 samples as expected by whisper-rs
-        let samples: Vec<f32> = reader
-            .samples::<i16>()
-            .map(|s| s.map(|sample| f32::from(sample) / f32::from(i16::MAX)))
-            .collect::<Result<Vec<_>, _>>()
-            .context("Failed to read audio samples")?;
-
-        // Create parameters for this transcription
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-
-        // Configure parameters for better accuracy
-        params.set_language(Some("en"));
-        params.set_translate(false);
-        params.set_no_context(true);
-        params.set_single_segment(false);
-        params.set_print_special(false);
-        params.set_print_progress(false);
-        params.set_print_realtime(false);
-        params.set_print_timestamps(false);
+impl LocalWhisperStt {
+    /// Runs inference on 16kHz mono samples and joins the segment texts
+    fn run(&self, samples: &[f32]) -> Result<Transcript> {
+        let language = spoken_language(self.task, self.language);
+        let params = full_params(&self.params, self.task, language);
 
         // Run inference
         let mut state = self.context.create_state().context("Failed to create Whisper state")?;
 
-        state.full(params, &samples).context("Whisper inference failed")?;
+        state.full(params, samples).context("Whisper inference failed")?;
 
         // Get the transcribed text
         let segment_count = state.full_n_segments().context("Failed to get segment count")?;
 
         let mut transcript = String::new();
+        let mut segments = Vec::new();
         for i in 0..segment_count {
             let text = state.full_get_segment_text(i).context("Failed to get segment text")?;
             transcript.push_str(&text);
             transcript.push(' ');
+
+            // Segment times are in centiseconds
+            let to_ms = |time: i64| u64::try_from(time).unwrap_or_default() * 10;
+            segments.push(TranscriptSegment {
+                start_ms: to_ms(state.full_get_segment_t0(i).context("Failed to get segment start")?),
+                end_ms: to_ms(state.full_get_segment_t1(i).context("Failed to get segment end")?),
+                text: text.trim().to_string(),
+            });
         }
 
-        Ok(transcript.trim().to_string())
+        // Whisper only detects the language when it isn't given one
+        let detected_language = if language == AUTO_DETECT {
+            let id = state
+                .full_lang_id_from_state()
+                .context("Failed to get detected language")?;
+            whisper_rs::get_lang_str_full(id).map(str::to_string)
+        } else {
+            None
+        };
+        if let Some(language) = &detected_language {
+            debug!("Whisper detected {language} speech");
+        }
+
+        Ok(Transcript {
+            text: transcript.trim().to_string(),
+            detected_language,
+            duration_ms: Some(samples.len() as u64 * 1000 / u64::from(WHISPER_SAMPLE_RATE)),
+            segments,
+        })
+    }
+}
+
+impl SttProvider for LocalWhisperStt {
+    async fn transcribe(&self, audio_data: Vec<u8>) -> Result<String> {
+        // whisper-rs expects mono audio at 16kHz; other WAV layouts and rates
+        // are converted when the samples are read
+        Ok(self.transcribe_detailed(audio_data).await?.text)
+    }
+
+    async fn transcribe_detailed(&self, audio_data: Vec<u8>) -> Result<Transcript> {
+        let reader = hound::WavReader::new(std::io::Cursor::new(audio_data)).context("Failed to parse WAV data")?;
+        self.run(&read_samples(reader)?)
+    }
+
+    /// Decodes the samples straight from the file instead of loading its
+    /// bytes first
+    async fn transcribe_file(&self, path: &Path) -> Result<Transcript> {
+        let reader =
+            hound::WavReader::open(path).with_context(|| format!("Failed to open WAV file {}", path.display()))?;
+        self.run(&read_samples(reader)?)
+    }
+}
+
+/// Sample rate whisper-rs expects
+const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+/// Reads WAV samples as mono 16kHz f32, as expected by whisper-rs,
+/// downmixing and resampling other layouts and rates
+fn read_samples<R: Read>(mut reader: hound::WavReader<R>) -> Result<Vec<f32>> {
+    let spec = reader.spec();
+    if spec.channels == 0 {
+        anyhow::bail!("Invalid WAV data: no audio channels");
+    }
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        hound::SampleFormat::Int => {
+            // Full scale of a signed sample with this many bits
+            let scale = f32::from(spec.bits_per_sample.saturating_sub(1)).exp2();
+            #[allow(clippy::cast_precision_loss)]
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|sample| sample as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()
+        }
+    }
+    .context("Failed to read audio samples")?;
+
+    let samples = downmix(samples, spec.channels);
+    if spec.sample_rate == WHISPER_SAMPLE_RATE {
+        return Ok(samples);
+    }
+
+    debug!(
+        "Resampling {}-channel {}Hz audio to {}Hz for Whisper",
+        spec.channels, spec.sample_rate, WHISPER_SAMPLE_RATE
+    );
+    echoes_audio::resample(&samples, spec.sample_rate, WHISPER_SAMPLE_RATE).with_context(|| {
+        format!(
+            "Failed to resample {}Hz {}-channel audio to {}Hz",
+            spec.sample_rate, spec.channels, WHISPER_SAMPLE_RATE
+        )
+    })
+}
+
+/// Averages the interleaved `channels` of `samples` into mono
+fn downmix(samples: Vec<f32>, channels: u16) -> Vec<f32> {
+    if channels == 1 {
+        return samples;
+    }
+
+    samples
+        .chunks(usize::from(channels))
+        .map(|frame| frame.iter().sum::<f32>() / f32::from(channels))
+        .collect()
+}
+
+/// Warn when the Core ML encoder requested in the config can't be used
+fn check_coreml(model_path: &Path) {
+    if !cfg!(feature = "coreml") {
+        warn!("CoreML requested but the coreml feature was not enabled at build time");
+        return;
+    }
+
+    let encoder = model_path.with_file_name(format!(
+        "{}-encoder.mlmodelc",
+        model_path.file_stem().unwrap_or_default().to_string_lossy()
+    ));
+    if !encoder.exists() {
+        warn!("CoreML encoder not found at {encoder:?}; the encoder runs without CoreML");
+    }
+}
+
+/// Language Whisper is told the speech is in; `auto` lets it detect it
+fn spoken_language(task: TranscriptionTask, language: &'static str) -> &'static str {
+    match task {
+        // The language set here is the spoken one and the translation is
+        // always English, so the English default only makes sense detected
+        TranscriptionTask::Translate if language == "en" => AUTO_DETECT,
+        _ => language,
+    }
+}
+
+/// Map the configured decoding settings onto `whisper.cpp` parameters
+fn full_params(
+    settings: &WhisperParams, task: TranscriptionTask, language: &'static str,
+) -> FullParams<'static, 'static> {
+    let to_i32 = |value: u32| i32::try_from(value).unwrap_or(i32::MAX);
+    let strategy = match settings.sampling {
+        WhisperSampling::Greedy { best_of } => SamplingStrategy::Greedy {
+            best_of: to_i32(best_of.max(1)),
+        },
+        WhisperSampling::BeamSearch { beam_size } => SamplingStrategy::BeamSearch {
+            beam_size: to_i32(beam_size.max(1)),
+            patience: -1.0,
+        },
+    };
+
+    let mut params = FullParams::new(strategy);
+    params.set_language(Some(language));
+    params.set_translate(task == TranscriptionTask::Translate);
+    params.set_no_context(settings.no_context);
+    params.set_temperature(settings.temperature);
+    if let Some(threads) = settings.threads {
+        params.set_n_threads(to_i32(threads.max(1)));
+    }
+    params.set_single_segment(false);
+    params.set_print_special(false);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_samples_downmixes_and_resamples() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut wav = std::io::Cursor::new(Vec::new());
+        {
+            let mut writer = hound::WavWriter::new(&mut wav, spec).unwrap();
+            for _ in 0..48_000 {
+                writer.write_sample(i16::MAX / 2).unwrap();
+                writer.write_sample(0i16).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+        wav.set_position(0);
+
+        let samples = read_samples(hound::WavReader::new(wav).unwrap()).unwrap();
+
+        assert!(
+            samples.len() <= 16_000 && samples.len() > 15_000,
+            "got {} samples",
+            samples.len()
+        );
+        assert!((samples[8_000] - 0.25).abs() < 0.01, "got {}", samples[8_000]);
     }
 }